@@ -1,6 +1,6 @@
 # flake8: noqa
 from ctypes import POINTER, c_uint8
-from typing import Callable, Generic, List, Optional, Tuple, TypeVar, Union
+from typing import Any, Callable, Dict, Generic, List, Optional, Tuple, TypeVar, Union
 
 # Constants
 VERSION: str
@@ -18,6 +18,13 @@ IMAGE_SIZE: int
 NUM_TILEMAPS: int
 TILEMAP_SIZE: int
 TILE_SIZE: int
+TILE_FLIP_H: int
+TILE_FLIP_V: int
+TILE_ROTATE_90: int  # Ignored unless tile_width == tile_height
+NUM_ANIMATIONS: int
+ANIM_LOOP: int
+ANIM_PINGPONG: int
+ANIM_ONCE: int
 
 COLOR_BLACK: int
 COLOR_NAVY: int
@@ -38,6 +45,16 @@ COLOR_PEACH: int
 
 FONT_WIDTH: int
 FONT_HEIGHT: int
+TEXT_ALIGN_LEFT: int
+TEXT_ALIGN_CENTER: int
+TEXT_ALIGN_RIGHT: int
+SCALE_INTEGER: int
+SCALE_FIT: int
+SCALE_STRETCH: int
+SCALE_FIXED: int
+DITHER_NONE: int
+DITHER_FLOYD_STEINBERG: int
+DITHER_ORDERED: int
 
 NUM_CHANNELS: int
 NUM_TONES: int
@@ -411,19 +428,42 @@ class Seq(Generic[T]):
     def to_list(self) -> List[T]: ...
 
 # Image class
+AffineMatrix = Tuple[float, float, float, float, float, float]
+
 class Image:
     width: int
     height: int
 
     def __init__(self, width: int, height: int) -> None: ...
     @staticmethod
-    def from_image(filename: str, *, incl_colors: Optional[bool] = None) -> Image: ...
+    def from_image(
+        filename: str,
+        *,
+        incl_colors: Optional[bool] = None,
+        dither: Optional[int] = None,
+        num_colors: Optional[int] = None,
+    ) -> Image: ...
     def data_ptr(self) -> POINTER(c_uint8): ...
     def set(self, x: int, y: int, data: List[str]) -> None: ...
     def load(
-        self, x: int, y: int, filename: str, *, incl_colors: Optional[bool] = None
+        self,
+        x: int,
+        y: int,
+        filename: str,
+        *,
+        incl_colors: Optional[bool] = None,
+        dither: Optional[int] = None,
+        num_colors: Optional[int] = None,
+    ) -> None: ...
+    def save(
+        self,
+        filename: str,
+        scale: int,
+        *,
+        colkey: Optional[int] = None,
+        indexed: Optional[bool] = None,
+        rect: Optional[Tuple[int, int, int, int]] = None,
     ) -> None: ...
-    def save(self, filename: str, scale: int) -> None: ...
     def clip(
         self,
         x: Optional[float] = None,
@@ -438,6 +478,13 @@ class Image:
     ) -> None: ...
     def pal(self, col1: Optional[int] = None, col2: Optional[int] = None) -> None: ...
     def dither(self, alpha: float) -> None: ...
+    def dither_matrix(self, matrix: Optional[List[float]] = None) -> None: ...
+    def fillp(
+        self, pattern: Optional[int] = None, col2: Optional[int] = None
+    ) -> None: ...
+    def blend(
+        self, mask: Optional[int] = None, table: Optional[Union[int, Image]] = None
+    ) -> None: ...
     def cls(self, col: int) -> None: ...
     def pget(self, x: float, y: float) -> int: ...
     def pset(self, x: float, y: float, col: int) -> None: ...
@@ -454,7 +501,35 @@ class Image:
     def trib(
         self, x1: float, y1: float, x2: float, y2: float, x3: float, y3: float, col: int
     ) -> None: ...
+    def poly(self, points: List[Tuple[float, float]], col: int) -> None: ...
+    def polyb(self, points: List[Tuple[float, float]], col: int) -> None: ...
+    def thick_line(
+        self, x1: float, y1: float, x2: float, y2: float, thickness: float, col: int
+    ) -> None: ...
+    def rrect(
+        self, x: float, y: float, w: float, h: float, r: float, col: int
+    ) -> None: ...
+    def rrectb(
+        self, x: float, y: float, w: float, h: float, r: float, col: int
+    ) -> None: ...
+    def arc(
+        self, x: float, y: float, r: float, start: float, end: float, col: int
+    ) -> None: ...
+    def pie(
+        self, x: float, y: float, r: float, start: float, end: float, col: int
+    ) -> None: ...
     def fill(self, x: float, y: float, col: int) -> None: ...
+    def fill_boundary(self, x: float, y: float, bcol: int, col: int) -> None: ...
+    def fill_pattern(
+        self,
+        x: float,
+        y: float,
+        img: Union[int, Image],
+        u: float,
+        v: float,
+        w: float,
+        h: float,
+    ) -> None: ...
     def blt(
         self,
         x: float,
@@ -465,6 +540,9 @@ class Image:
         w: float,
         h: float,
         colkey: Optional[int] = None,
+        *,
+        rotate: Optional[float] = None,
+        scale: Optional[float] = None,
     ) -> None: ...
     def bltm(
         self,
@@ -477,16 +555,58 @@ class Image:
         h: float,
         colkey: Optional[int] = None,
     ) -> None: ...
-    def text(self, x: float, y: float, s: str, col: int) -> None: ...
+    def blt_animation(
+        self,
+        x: float,
+        y: float,
+        anim: Union[int, Animation],
+        t: int,
+        tag: Optional[str] = None,
+        colkey: Optional[int] = None,
+        *,
+        flip_h: Optional[bool] = None,
+        flip_v: Optional[bool] = None,
+    ) -> None: ...
+    def bltm_affine(
+        self,
+        x: float,
+        y: float,
+        tm: Union[int, Tilemap],
+        w: float,
+        h: float,
+        matrix: Union[AffineMatrix, Callable[[int], Optional[AffineMatrix]]],
+        colkey: Optional[int] = None,
+        *,
+        wrap: Optional[bool] = None,
+    ) -> None: ...
+    def text(
+        self, x: float, y: float, s: str, col: int, font: Optional[Font] = None
+    ) -> None: ...
+    def text_box(
+        self,
+        x: float,
+        y: float,
+        w: float,
+        h: float,
+        s: str,
+        col: int,
+        font: Optional[Font] = None,
+        align: Optional[int] = None,
+    ) -> None: ...
 
 # Tilemap class
+Tile = Union[Tuple[int, int], Tuple[int, int, int]]
+
 class Tilemap:
     width: int
     height: int
     imgsrc: Union[int, Image]
+    tile_width: int
+    tile_height: int
 
     def __init__(self, width: int, height: int, img: Union[int, Image]) -> None: ...
     def from_tmx(filename: str, layer: int) -> Image: ...
+    def tmx_data(filename: str) -> Dict[str, Any]: ...
     def data_ptr(self) -> POINTER(c_uint8): ...
     def set(self, x: int, y: int, data: List[str]) -> None: ...
     def load(self, x: int, y: int, filename: str, layer: int) -> None: ...
@@ -502,26 +622,16 @@ class Tilemap:
         x: Optional[float] = None,
         y: Optional[float] = None,
     ) -> None: ...
-    def cls(self, tile: Tuple[int, int]) -> None: ...
-    def pget(self, x: float, y: float) -> Tuple[int, int]: ...
-    def pset(self, x: float, y: float, tile: Tuple[int, int]) -> None: ...
-    def line(
-        self, x1: float, y1: float, x2: float, y2: float, tile: Tuple[int, int]
-    ) -> None: ...
-    def rect(
-        self, x: float, y: float, w: float, h: float, tile: Tuple[int, int]
-    ) -> None: ...
-    def rectb(
-        self, x: float, y: float, w: float, h: float, tile: Tuple[int, int]
-    ) -> None: ...
-    def circ(self, x: float, y: float, r: float, tile: Tuple[int, int]) -> None: ...
-    def circb(self, x: float, y: float, r: float, tile: Tuple[int, int]) -> None: ...
-    def elli(
-        self, x: float, y: float, w: float, h: float, tile: Tuple[int, int]
-    ) -> None: ...
-    def ellib(
-        self, x: float, y: float, w: float, h: float, tile: Tuple[int, int]
-    ) -> None: ...
+    def cls(self, tile: Tile) -> None: ...
+    def pget(self, x: float, y: float) -> Tile: ...
+    def pset(self, x: float, y: float, tile: Tile) -> None: ...
+    def line(self, x1: float, y1: float, x2: float, y2: float, tile: Tile) -> None: ...
+    def rect(self, x: float, y: float, w: float, h: float, tile: Tile) -> None: ...
+    def rectb(self, x: float, y: float, w: float, h: float, tile: Tile) -> None: ...
+    def circ(self, x: float, y: float, r: float, tile: Tile) -> None: ...
+    def circb(self, x: float, y: float, r: float, tile: Tile) -> None: ...
+    def elli(self, x: float, y: float, w: float, h: float, tile: Tile) -> None: ...
+    def ellib(self, x: float, y: float, w: float, h: float, tile: Tile) -> None: ...
     def tri(
         self,
         x1: float,
@@ -530,7 +640,7 @@ class Tilemap:
         y2: float,
         x3: float,
         y3: float,
-        tile: Tuple[int, int],
+        tile: Tile,
     ) -> None: ...
     def trib(
         self,
@@ -540,9 +650,27 @@ class Tilemap:
         y2: float,
         x3: float,
         y3: float,
-        tile: Tuple[int, int],
+        tile: Tile,
+    ) -> None: ...
+    def poly(self, points: List[Tuple[float, float]], tile: Tile) -> None: ...
+    def polyb(self, points: List[Tuple[float, float]], tile: Tile) -> None: ...
+    def thick_line(
+        self, x1: float, y1: float, x2: float, y2: float, thickness: float, tile: Tile
+    ) -> None: ...
+    def rrect(
+        self, x: float, y: float, w: float, h: float, r: float, tile: Tile
+    ) -> None: ...
+    def rrectb(
+        self, x: float, y: float, w: float, h: float, r: float, tile: Tile
+    ) -> None: ...
+    def arc(
+        self, x: float, y: float, r: float, start: float, end: float, tile: Tile
+    ) -> None: ...
+    def pie(
+        self, x: float, y: float, r: float, start: float, end: float, tile: Tile
     ) -> None: ...
-    def fill(self, x: float, y: float, tile: Tuple[int, int]) -> None: ...
+    def fill(self, x: float, y: float, tile: Tile) -> None: ...
+    def fill_boundary(self, x: float, y: float, btile: Tile, tile: Tile) -> None: ...
     def blt(
         self,
         x: float,
@@ -552,13 +680,36 @@ class Tilemap:
         v: float,
         w: float,
         h: float,
-        tilekey: Optional[Tuple[int, int]] = None,
+        tilekey: Optional[Tile] = None,
     ) -> None: ...
 
     # Deprecated field
     image: Image
     refimg: Optional[int]
 
+# Animation class
+class Animation:
+    imgsrc: Union[int, Image]
+    frames: List[Tuple[int, int, int, int, int]]
+    tags: List[Tuple[str, int, int, int]]
+    mode: int
+
+    def __init__(self, img: Union[int, Image]) -> None: ...
+    def add_frame(self, u: int, v: int, w: int, h: int, duration: int) -> None: ...
+    def add_tag(
+        self, name: str, start: int, end: int, mode: Optional[int] = None
+    ) -> None: ...
+    def remove_tag(self, name: str) -> None: ...
+    def duration(self, tag: Optional[str] = None) -> int: ...
+    def frame_index(self, t: int, tag: Optional[str] = None) -> Optional[int]: ...
+
+# Font class
+class Font:
+    line_height: int
+
+    def __init__(self, filename: str) -> None: ...
+    def text_width(self, s: str) -> int: ...
+
 # Channel class
 class Channel:
     gain: float
@@ -642,6 +793,12 @@ def title(title: str) -> None: ...
 def icon(data: List[str], scale: int, colkey: Optional[int]) -> None: ...
 def fullscreen(full: bool) -> None: ...
 def screen_mode(scr: int) -> None: ...
+def resize(width: int, height: int) -> None: ...
+def scale_mode(mode: int, scale: Optional[float] = None) -> None: ...
+def border_color(rgb: int) -> None: ...
+def border_image(img: Optional[Union[int, Image]] = None) -> None: ...
+def add_screen_shader(code: str) -> int: ...
+def screen_uniform(name: str, value: float) -> None: ...
 def process_exists(pid: int) -> bool: ...
 
 # Resource
@@ -656,6 +813,14 @@ def load(
     incl_channels: Optional[bool] = None,
     incl_tones: Optional[bool] = None,
 ) -> None: ...
+def load_aseprite(
+    filename: str,
+    img: int,
+    x: int,
+    y: int,
+    *,
+    incl_colors: Optional[bool] = None,
+) -> Animation: ...
 def save(
     filename: str,
     *,
@@ -686,14 +851,21 @@ def btnr(key: int) -> bool: ...
 def btnv(key: int) -> int: ...
 def mouse(visible: bool) -> None: ...
 def warp_mouse(x: float, y: float) -> None: ...
+def start_recording() -> None: ...
+def stop_recording(filename: str) -> None: ...
+def start_replay(filename: str) -> None: ...
+def stop_replay() -> None: ...
+def is_replaying() -> bool: ...
 
 # Graphics
 class Image: ...
 class Tilemap: ...
+class Animation: ...
 
 colors: Seq[int]
 images: Seq[Image]
 tilemaps: Seq[Tilemap]
+animations: Seq[Animation]
 screen: Image
 cursor: Image
 font: Image
@@ -708,8 +880,21 @@ def camera(
     x: Optional[float] = None,
     y: Optional[float] = None,
 ) -> None: ...
-def pal(col1: Optional[int] = None, col2: Optional[int] = None) -> None: ...
+def pal(
+    col1: Optional[int] = None,
+    col2: Optional[int] = None,
+    *,
+    screen: Optional[bool] = None,
+) -> None: ...
+def store_palette(name: str) -> None: ...
+def load_palette(name: str) -> None: ...
+def lerp_palette(name1: str, name2: str, t: float) -> None: ...
 def dither(alpha: float) -> None: ...
+def dither_matrix(matrix: Optional[List[float]] = None) -> None: ...
+def fillp(pattern: Optional[int] = None, col2: Optional[int] = None) -> None: ...
+def blend(
+    mask: Optional[int] = None, table: Optional[Union[int, Image]] = None
+) -> None: ...
 def cls(col: int) -> None: ...
 def pget(x: float, y: float) -> int: ...
 def pset(x: float, y: float, col: int) -> None: ...
@@ -738,6 +923,15 @@ def trib(
     y3: float,
     col: int,
 ) -> None: ...
+def poly(points: List[Tuple[float, float]], col: int) -> None: ...
+def polyb(points: List[Tuple[float, float]], col: int) -> None: ...
+def thick_line(
+    x1: float, y1: float, x2: float, y2: float, thickness: float, col: int
+) -> None: ...
+def rrect(x: float, y: float, w: float, h: float, r: float, col: int) -> None: ...
+def rrectb(x: float, y: float, w: float, h: float, r: float, col: int) -> None: ...
+def arc(x: float, y: float, r: float, start: float, end: float, col: int) -> None: ...
+def pie(x: float, y: float, r: float, start: float, end: float, col: int) -> None: ...
 def fill(x: float, y: float, col: int) -> None: ...
 def blt(
     x: float,
@@ -748,6 +942,9 @@ def blt(
     w: float,
     h: float,
     colkey: Optional[int] = None,
+    *,
+    rotate: Optional[float] = None,
+    scale: Optional[float] = None,
 ) -> None: ...
 def bltm(
     x: float,
@@ -759,7 +956,43 @@ def bltm(
     h: float,
     colkey: Optional[int] = None,
 ) -> None: ...
-def text(x: float, y: float, s: str, col: int) -> None: ...
+def blt_animation(
+    x: float,
+    y: float,
+    anim: Union[int, Animation],
+    t: int,
+    tag: Optional[str] = None,
+    colkey: Optional[int] = None,
+    *,
+    flip_h: Optional[bool] = None,
+    flip_v: Optional[bool] = None,
+) -> None: ...
+def bltm_affine(
+    x: float,
+    y: float,
+    tm: Union[int, Tilemap],
+    w: float,
+    h: float,
+    matrix: Union[AffineMatrix, Callable[[int], Optional[AffineMatrix]]],
+    colkey: Optional[int] = None,
+    *,
+    wrap: Optional[bool] = None,
+) -> None: ...
+def text(
+    x: float, y: float, s: str, col: int, font: Optional[Font] = None
+) -> None: ...
+def text_box(
+    x: float,
+    y: float,
+    w: float,
+    h: float,
+    s: str,
+    col: int,
+    font: Optional[Font] = None,
+    align: Optional[int] = None,
+) -> None: ...
+def text_size(s: str, font: Optional[Font] = None) -> Tuple[int, int]: ...
+def wrap_text(s: str, w: int, font: Optional[Font] = None) -> str: ...
 
 # Audio
 class Channel: ...
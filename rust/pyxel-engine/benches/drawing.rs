@@ -0,0 +1,101 @@
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use pyxel::{Color, Image, ImageSource, SharedImage, SharedTilemap, Tilemap};
+
+const SCREEN_SIZE: u32 = 256;
+
+fn source_image() -> SharedImage {
+    let image = Image::new(SCREEN_SIZE, SCREEN_SIZE);
+    {
+        let mut image = image.lock();
+        for y in 0..SCREEN_SIZE {
+            for x in 0..SCREEN_SIZE {
+                image.pset(x as f64, y as f64, ((x ^ y) % 16) as Color);
+            }
+        }
+    }
+    image
+}
+
+fn source_tilemap(image: SharedImage) -> SharedTilemap {
+    let tilemap = Tilemap::new(64, 64, ImageSource::Image(image));
+    {
+        let mut tilemap = tilemap.lock();
+        for y in 0..64 {
+            for x in 0..64 {
+                tilemap.pset(x as f64, y as f64, ((x % 32) as u8, (y % 32) as u8, 0));
+            }
+        }
+    }
+    tilemap
+}
+
+fn bench_cls_rect(c: &mut Criterion) {
+    let screen = Image::new(SCREEN_SIZE, SCREEN_SIZE);
+    let size = SCREEN_SIZE as f64;
+
+    c.bench_function("cls", |b| b.iter(|| screen.lock().cls(black_box(1))));
+    c.bench_function("rect", |b| {
+        b.iter(|| {
+            screen
+                .lock()
+                .rect(-8.0, -8.0, size + 16.0, size + 16.0, black_box(2))
+        });
+    });
+
+    screen.lock().dither(0.5);
+    c.bench_function("rect_dither", |b| {
+        b.iter(|| screen.lock().rect(0.0, 0.0, size, size, black_box(3)));
+    });
+}
+
+fn bench_blt(c: &mut Criterion) {
+    let screen = Image::new(SCREEN_SIZE, SCREEN_SIZE);
+    let image = source_image();
+    let size = SCREEN_SIZE as f64;
+    let blt = |transparent: Option<Color>| {
+        screen.lock().blt(
+            black_box(0.0),
+            0.0,
+            image.clone(),
+            0.0,
+            0.0,
+            size,
+            size,
+            transparent,
+            None,
+            None,
+        );
+    };
+
+    c.bench_function("blt", |b| b.iter(|| blt(None)));
+    c.bench_function("blt_colkey", |b| b.iter(|| blt(Some(0))));
+
+    screen.lock().pal(1, 2);
+    c.bench_function("blt_pal", |b| b.iter(|| blt(None)));
+}
+
+fn bench_bltm(c: &mut Criterion) {
+    let screen = Image::new(SCREEN_SIZE, SCREEN_SIZE);
+    let tilemap = source_tilemap(source_image());
+    let size = SCREEN_SIZE as f64;
+    let mut scroll = 0;
+    let mut bltm = |transparent: Option<Color>| {
+        scroll = (scroll + 1) % SCREEN_SIZE;
+        screen.lock().bltm(
+            0.0,
+            0.0,
+            tilemap.clone(),
+            black_box(scroll as f64),
+            black_box(scroll as f64),
+            size,
+            size,
+            transparent,
+        );
+    };
+
+    c.bench_function("bltm_scroll", |b| b.iter(|| bltm(None)));
+    c.bench_function("bltm_scroll_colkey", |b| b.iter(|| bltm(Some(0))));
+}
+
+criterion_group!(benches, bench_cls_rect, bench_blt, bench_bltm);
+criterion_main!(benches);
@@ -0,0 +1,204 @@
+use crate::settings::{ANIM_LOOP, ANIM_ONCE, ANIM_PINGPONG};
+use crate::tilemap::ImageSource;
+
+pub type AnimationMode = u32;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct AnimationFrame {
+    pub u: u32,
+    pub v: u32,
+    pub width: u32,
+    pub height: u32,
+    pub duration: u32,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct AnimationTag {
+    pub name: String,
+    pub start: u32,
+    pub end: u32,
+    pub mode: AnimationMode,
+}
+
+pub struct Animation {
+    pub imgsrc: ImageSource,
+    pub frames: Vec<AnimationFrame>,
+    pub tags: Vec<AnimationTag>,
+    pub mode: AnimationMode,
+}
+
+pub type SharedAnimation = shared_type!(Animation);
+
+impl Animation {
+    pub fn new(imgsrc: ImageSource) -> SharedAnimation {
+        new_shared_type!(Self {
+            imgsrc,
+            frames: Vec::new(),
+            tags: Vec::new(),
+            mode: ANIM_LOOP,
+        })
+    }
+
+    pub fn add_frame(&mut self, u: u32, v: u32, width: u32, height: u32, duration: u32) {
+        self.frames.push(AnimationFrame {
+            u,
+            v,
+            width,
+            height,
+            duration,
+        });
+    }
+
+    pub fn add_tag(&mut self, name: &str, start: u32, end: u32, mode: Option<AnimationMode>) {
+        let tag = AnimationTag {
+            name: name.to_string(),
+            start,
+            end,
+            mode: mode.unwrap_or(self.mode),
+        };
+        if let Some(index) = self.tags.iter().position(|tag| tag.name == name) {
+            self.tags[index] = tag;
+        } else {
+            self.tags.push(tag);
+        }
+    }
+
+    pub fn remove_tag(&mut self, name: &str) {
+        self.tags.retain(|tag| tag.name != name);
+    }
+
+    pub fn duration(&self, tag: Option<&str>) -> u32 {
+        self.sequence(tag).map_or(0, |(indices, _)| {
+            indices
+                .iter()
+                .map(|&index| self.frame_duration(index))
+                .sum()
+        })
+    }
+
+    pub fn frame_index(&self, time: u32, tag: Option<&str>) -> Option<usize> {
+        let (indices, mode) = self.sequence(tag)?;
+        let total: u32 = indices
+            .iter()
+            .map(|&index| self.frame_duration(index))
+            .sum();
+        let mut time = if mode == ANIM_ONCE {
+            time.min(total - 1)
+        } else {
+            time % total
+        };
+        for &index in &indices {
+            let duration = self.frame_duration(index);
+            if time < duration {
+                return Some(index);
+            }
+            time -= duration;
+        }
+        indices.last().copied()
+    }
+
+    fn frame_duration(&self, index: usize) -> u32 {
+        // Frames always last at least one frame so that the sequence never stalls
+        self.frames[index].duration.max(1)
+    }
+
+    fn sequence(&self, tag: Option<&str>) -> Option<(Vec<usize>, AnimationMode)> {
+        if self.frames.is_empty() {
+            return None;
+        }
+        let last = self.frames.len() as u32 - 1;
+        let (start, end, mode) = match tag {
+            Some(name) => {
+                let tag = self.tags.iter().find(|tag| tag.name == name)?;
+                (tag.start.min(last), tag.end.min(last), tag.mode)
+            }
+            None => (0, last, self.mode),
+        };
+        let mut indices: Vec<usize> = if start <= end {
+            (start..=end).map(|index| index as usize).collect()
+        } else {
+            (end..=start).rev().map(|index| index as usize).collect()
+        };
+        if mode == ANIM_PINGPONG && indices.len() > 2 {
+            let returns: Vec<usize> = indices[1..indices.len() - 1]
+                .iter()
+                .rev()
+                .copied()
+                .collect();
+            indices.extend(returns);
+        }
+        Some((indices, mode))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::image::Image;
+
+    fn animation() -> SharedAnimation {
+        let animation = Animation::new(ImageSource::Index(0));
+        {
+            let mut animation = animation.lock();
+            animation.add_frame(0, 0, 8, 8, 2);
+            animation.add_frame(8, 0, 8, 8, 1);
+            animation.add_frame(16, 0, 8, 8, 3);
+        }
+        animation
+    }
+
+    #[test]
+    fn test_frame_index() {
+        let animation = animation();
+        let mut animation = animation.lock();
+        let indices = |animation: &Animation, tag| -> Vec<Option<usize>> {
+            (0..10)
+                .map(|time| animation.frame_index(time, tag))
+                .collect()
+        };
+
+        let expected = [0, 0, 1, 2, 2, 2, 0, 0, 1, 2];
+        assert_eq!(indices(&animation, None), expected.map(Some));
+        assert_eq!(animation.duration(None), 6);
+
+        animation.mode = ANIM_PINGPONG;
+        let expected = [0, 0, 1, 2, 2, 2, 1, 0, 0, 1];
+        assert_eq!(indices(&animation, None), expected.map(Some));
+        assert_eq!(animation.duration(None), 7);
+
+        animation.mode = ANIM_ONCE;
+        let expected = [0, 0, 1, 2, 2, 2, 2, 2, 2, 2];
+        assert_eq!(indices(&animation, None), expected.map(Some));
+
+        animation.add_tag("back", 2, 1, Some(ANIM_LOOP));
+        let expected = [2, 2, 2, 1, 2, 2, 2, 1, 2, 2];
+        assert_eq!(indices(&animation, Some("back")), expected.map(Some));
+        assert_eq!(animation.frame_index(0, Some("none")), None);
+
+        animation.remove_tag("back");
+        assert_eq!(animation.frame_index(0, Some("back")), None);
+        animation.frames.clear();
+        assert_eq!(animation.frame_index(0, None), None);
+    }
+
+    #[test]
+    fn test_blt_animation() {
+        let src = Image::new(4, 2);
+        src.lock().pset(0.0, 0.0, 1);
+        src.lock().pset(2.0, 1.0, 2);
+        let animation = Animation::new(ImageSource::Image(src));
+        animation.lock().add_frame(0, 0, 2, 2, 1);
+        animation.lock().add_frame(2, 0, 2, 2, 1);
+
+        let dst = Image::new(4, 4);
+        let pget = |x, y| dst.lock().pget(x, y);
+        dst.lock()
+            .blt_animation(0.0, 0.0, animation.clone(), 0, None, None, false, false);
+        assert_eq!([pget(0.0, 0.0), pget(1.0, 1.0)], [1, 0]);
+
+        dst.lock().cls(0);
+        dst.lock()
+            .blt_animation(0.0, 0.0, animation, 1, None, None, true, true);
+        assert_eq!([pget(0.0, 1.0), pget(1.0, 0.0)], [0, 2]);
+    }
+}
@@ -0,0 +1,602 @@
+use std::fs;
+use std::io::Read;
+
+use flate2::read::ZlibDecoder;
+
+use crate::animation::{Animation, AnimationMode, SharedAnimation};
+use crate::error::{PyxelError, PyxelResult};
+use crate::image::{Color, Rgb24};
+use crate::pyxel::Pyxel;
+use crate::settings::{ANIM_LOOP, ANIM_ONCE, ANIM_PINGPONG, MAX_COLORS};
+use crate::tilemap::ImageSource;
+
+const HEADER_SIZE: usize = 128;
+const FRAME_HEADER_SIZE: usize = 16;
+const CHUNK_HEADER_SIZE: usize = 6;
+const FILE_MAGIC: u16 = 0xa5e0;
+const FRAME_MAGIC: u16 = 0xf1fa;
+const INDEXED_COLOR_DEPTH: u16 = 8;
+
+const CHUNK_OLD_PALETTE: u16 = 0x0004;
+const CHUNK_LAYER: u16 = 0x2004;
+const CHUNK_CEL: u16 = 0x2005;
+const CHUNK_TAGS: u16 = 0x2018;
+const CHUNK_PALETTE: u16 = 0x2019;
+
+const LAYER_FLAG_VISIBLE: u16 = 0x1;
+const LAYER_FLAG_REFERENCE: u16 = 0x40;
+const LAYER_TYPE_NORMAL: u16 = 0;
+
+const CEL_TYPE_RAW: u16 = 0;
+const CEL_TYPE_LINKED: u16 = 1;
+const CEL_TYPE_COMPRESSED: u16 = 2;
+
+const TAG_REVERSE: u8 = 1;
+const TAG_PINGPONG: u8 = 2;
+const TAG_PINGPONG_REVERSE: u8 = 3;
+
+const PALETTE_ENTRY_HAS_NAME: u16 = 0x1;
+const MAX_PALETTE_SIZE: usize = 256;
+
+struct ByteReader<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> ByteReader<'a> {
+    const fn new(data: &'a [u8]) -> Self {
+        Self { data, pos: 0 }
+    }
+
+    const fn is_empty(&self) -> bool {
+        self.pos >= self.data.len()
+    }
+
+    fn bytes(&mut self, len: usize) -> Result<&'a [u8], String> {
+        let bytes = self
+            .data
+            .get(self.pos..self.pos + len)
+            .ok_or("unexpected end of data")?;
+        self.pos += len;
+        Ok(bytes)
+    }
+
+    fn rest(&mut self) -> &'a [u8] {
+        let bytes = &self.data[self.pos.min(self.data.len())..];
+        self.pos = self.data.len();
+        bytes
+    }
+
+    fn skip(&mut self, len: usize) -> Result<(), String> {
+        self.bytes(len).map(|_| ())
+    }
+
+    fn u8(&mut self) -> Result<u8, String> {
+        Ok(self.bytes(1)?[0])
+    }
+
+    fn u16(&mut self) -> Result<u16, String> {
+        Ok(u16::from_le_bytes(self.bytes(2)?.try_into().unwrap()))
+    }
+
+    fn i16(&mut self) -> Result<i16, String> {
+        Ok(i16::from_le_bytes(self.bytes(2)?.try_into().unwrap()))
+    }
+
+    fn u32(&mut self) -> Result<u32, String> {
+        Ok(u32::from_le_bytes(self.bytes(4)?.try_into().unwrap()))
+    }
+
+    fn string(&mut self) -> Result<String, String> {
+        let len = self.u16()? as usize;
+        Ok(String::from_utf8_lossy(self.bytes(len)?).into_owned())
+    }
+
+    fn rgb(&mut self) -> Result<Rgb24, String> {
+        let rgb = self.bytes(3)?;
+        Ok(((rgb[0] as Rgb24) << 16) | ((rgb[1] as Rgb24) << 8) | rgb[2] as Rgb24)
+    }
+}
+
+#[derive(Clone)]
+struct Cel {
+    layer: usize,
+    x: i32,
+    y: i32,
+    z_index: i32,
+    width: u32,
+    height: u32,
+    data: Vec<Color>,
+}
+
+struct AsepriteFrame {
+    duration: u32,
+    data: Vec<Color>,
+}
+
+struct AsepriteTag {
+    name: String,
+    start: u32,
+    end: u32,
+    direction: u8,
+    repeat: u16,
+}
+
+struct AsepriteFile {
+    width: u32,
+    height: u32,
+    colors: Vec<Rgb24>,
+    frames: Vec<AsepriteFrame>,
+    tags: Vec<AsepriteTag>,
+}
+
+impl AsepriteFile {
+    fn load(filename: &str) -> PyxelResult<Self> {
+        let data = fs::read(filename).map_err(|err| PyxelError::from_io_error(filename, &err))?;
+        Self::parse(&data).map_err(|message| PyxelError::invalid_format(filename, message))
+    }
+
+    fn parse(data: &[u8]) -> Result<Self, String> {
+        let mut reader = ByteReader::new(data);
+        let mut header = ByteReader::new(reader.bytes(HEADER_SIZE)?);
+        header.skip(4)?; // File size
+        if header.u16()? != FILE_MAGIC {
+            return Err("not an Aseprite file".to_string());
+        }
+        let num_frames = header.u16()?;
+        let width = header.u16()? as u32;
+        let height = header.u16()? as u32;
+        if width == 0 || height == 0 {
+            return Err("sprite size is zero".to_string());
+        }
+        let color_depth = header.u16()?;
+        if color_depth != INDEXED_COLOR_DEPTH {
+            return Err(format!(
+                "unsupported color depth {color_depth}, only indexed color mode is supported"
+            ));
+        }
+        header.skip(14)?; // Flags, speed and reserved fields
+        let transparent = header.u8()?;
+
+        let mut layers = Vec::new();
+        let mut parents_visible: Vec<bool> = Vec::new();
+        let mut colors = Vec::new();
+        let mut old_colors = Vec::new();
+        let mut tags = Vec::new();
+        let mut durations = Vec::new();
+        let mut frame_cels: Vec<Vec<Cel>> = Vec::new();
+
+        for _ in 0..num_frames {
+            let frame_size = reader.u32()? as usize;
+            if reader.u16()? != FRAME_MAGIC {
+                return Err("invalid frame header".to_string());
+            }
+            reader.skip(2)?; // Old chunk count
+            durations.push(reader.u16()? as u32);
+            reader.skip(6)?; // Reserved field and new chunk count
+            let frame_data = reader.bytes(frame_size.saturating_sub(FRAME_HEADER_SIZE))?;
+            let mut frame_reader = ByteReader::new(frame_data);
+            let mut cels = Vec::new();
+
+            while !frame_reader.is_empty() {
+                let chunk_size = frame_reader.u32()? as usize;
+                let chunk_type = frame_reader.u16()?;
+                let chunk_data =
+                    frame_reader.bytes(chunk_size.saturating_sub(CHUNK_HEADER_SIZE))?;
+                let mut chunk = ByteReader::new(chunk_data);
+                match chunk_type {
+                    CHUNK_LAYER => {
+                        layers.push(Self::parse_layer(&mut chunk, &mut parents_visible)?);
+                    }
+                    CHUNK_CEL => {
+                        if let Some(cel) = Self::parse_cel(&mut chunk, &frame_cels)? {
+                            cels.push(cel);
+                        }
+                    }
+                    CHUNK_PALETTE => Self::parse_palette(&mut chunk, &mut colors)?,
+                    CHUNK_OLD_PALETTE => Self::parse_old_palette(&mut chunk, &mut old_colors)?,
+                    CHUNK_TAGS => Self::parse_tags(&mut chunk, &mut tags)?,
+                    _ => {}
+                }
+            }
+            frame_cels.push(cels);
+        }
+
+        // Flatten the visible layers of each frame in Aseprite's drawing order
+        let frames = frame_cels
+            .iter()
+            .zip(durations)
+            .map(|(cels, duration)| {
+                let mut data = vec![transparent; (width * height) as usize];
+                let mut cels: Vec<&Cel> = cels
+                    .iter()
+                    .filter(|cel| layers.get(cel.layer).copied().unwrap_or(false))
+                    .collect();
+                cels.sort_by_key(|cel| (cel.layer as i32 + cel.z_index, cel.z_index));
+                for cel in cels {
+                    Self::draw_cel(&mut data, width, height, cel, transparent);
+                }
+                AsepriteFrame { duration, data }
+            })
+            .collect();
+
+        Ok(Self {
+            width,
+            height,
+            colors: if colors.is_empty() {
+                old_colors
+            } else {
+                colors
+            },
+            frames,
+            tags,
+        })
+    }
+
+    fn parse_layer(
+        chunk: &mut ByteReader,
+        parents_visible: &mut Vec<bool>,
+    ) -> Result<bool, String> {
+        let flags = chunk.u16()?;
+        let layer_type = chunk.u16()?;
+        let level = chunk.u16()? as usize;
+
+        // Layers inside hidden groups are hidden as well
+        let visible = flags & LAYER_FLAG_VISIBLE != 0
+            && parents_visible[..level.min(parents_visible.len())]
+                .iter()
+                .all(|&visible| visible);
+        parents_visible.truncate(level);
+        parents_visible.push(visible);
+        Ok(visible && layer_type == LAYER_TYPE_NORMAL && flags & LAYER_FLAG_REFERENCE == 0)
+    }
+
+    fn parse_palette(chunk: &mut ByteReader, colors: &mut Vec<Rgb24>) -> Result<(), String> {
+        let size = chunk.u32()? as usize;
+        let first = chunk.u32()? as usize;
+        let last = chunk.u32()? as usize;
+        // Indexed pixels are bytes, so a valid palette never exceeds 256 entries
+        if size > MAX_PALETTE_SIZE || last >= MAX_PALETTE_SIZE || first > last {
+            return Err("invalid palette range".to_string());
+        }
+        chunk.skip(8)?;
+        colors.resize(size.max(last + 1), 0);
+        for color in &mut colors[first..=last] {
+            let flags = chunk.u16()?;
+            *color = chunk.rgb()?;
+            chunk.skip(1)?; // Alpha
+            if flags & PALETTE_ENTRY_HAS_NAME != 0 {
+                chunk.string()?;
+            }
+        }
+        Ok(())
+    }
+
+    fn parse_old_palette(chunk: &mut ByteReader, colors: &mut Vec<Rgb24>) -> Result<(), String> {
+        let mut index = 0;
+        for _ in 0..chunk.u16()? {
+            index += chunk.u8()? as usize;
+            let count = match chunk.u8()? {
+                0 => 256,
+                count => count as usize,
+            };
+            colors.resize(colors.len().max(index + count), 0);
+            for color in &mut colors[index..index + count] {
+                *color = chunk.rgb()?;
+            }
+            index += count;
+        }
+        Ok(())
+    }
+
+    fn parse_tags(chunk: &mut ByteReader, tags: &mut Vec<AsepriteTag>) -> Result<(), String> {
+        let num_tags = chunk.u16()?;
+        chunk.skip(8)?;
+        for _ in 0..num_tags {
+            let start = chunk.u16()? as u32;
+            let end = chunk.u16()? as u32;
+            let direction = chunk.u8()?;
+            let repeat = chunk.u16()?;
+            chunk.skip(10)?; // Reserved fields and tag color
+            let name = chunk.string()?;
+            tags.push(AsepriteTag {
+                name,
+                start,
+                end,
+                direction,
+                repeat,
+            });
+        }
+        Ok(())
+    }
+
+    fn parse_cel(chunk: &mut ByteReader, frame_cels: &[Vec<Cel>]) -> Result<Option<Cel>, String> {
+        let layer = chunk.u16()? as usize;
+        let x = chunk.i16()? as i32;
+        let y = chunk.i16()? as i32;
+        chunk.skip(1)?; // Opacity
+        let cel_type = chunk.u16()?;
+        let z_index = chunk.i16()? as i32;
+        chunk.skip(5)?;
+        let (width, height, data) = match cel_type {
+            CEL_TYPE_RAW => {
+                let width = chunk.u16()? as u32;
+                let height = chunk.u16()? as u32;
+                let data = chunk.bytes((width * height) as usize)?.to_vec();
+                (width, height, data)
+            }
+            CEL_TYPE_LINKED => {
+                let frame = chunk.u16()? as usize;
+                return Ok(frame_cels
+                    .get(frame)
+                    .and_then(|cels| cels.iter().find(|cel| cel.layer == layer))
+                    .cloned());
+            }
+            CEL_TYPE_COMPRESSED => {
+                let width = chunk.u16()? as u32;
+                let height = chunk.u16()? as u32;
+                let mut data = Vec::new();
+                ZlibDecoder::new(chunk.rest())
+                    .read_to_end(&mut data)
+                    .map_err(|err| err.to_string())?;
+                if data.len() < (width * height) as usize {
+                    return Err("cel data is too short".to_string());
+                }
+                (width, height, data)
+            }
+            _ => return Ok(None),
+        };
+        Ok(Some(Cel {
+            layer,
+            x,
+            y,
+            z_index,
+            width,
+            height,
+            data,
+        }))
+    }
+
+    fn draw_cel(data: &mut [Color], width: u32, height: u32, cel: &Cel, transparent: Color) {
+        for yi in 0..cel.height as i32 {
+            let y = cel.y + yi;
+            if y < 0 || y >= height as i32 {
+                continue;
+            }
+            for xi in 0..cel.width as i32 {
+                let x = cel.x + xi;
+                let value = cel.data[(yi * cel.width as i32 + xi) as usize];
+                if x >= 0 && x < width as i32 && value != transparent {
+                    data[(y * width as i32 + x) as usize] = value;
+                }
+            }
+        }
+    }
+}
+
+impl Pyxel {
+    pub fn load_aseprite(
+        &mut self,
+        filename: &str,
+        image_index: u32,
+        x: u32,
+        y: u32,
+        include_colors: Option<bool>,
+    ) -> SharedAnimation {
+        self.try_load_aseprite(filename, image_index, x, y, include_colors)
+            .unwrap_or_else(|err| panic!("{err}"))
+    }
+
+    pub fn try_load_aseprite(
+        &mut self,
+        filename: &str,
+        image_index: u32,
+        x: u32,
+        y: u32,
+        include_colors: Option<bool>,
+    ) -> PyxelResult<SharedAnimation> {
+        let image = self
+            .images
+            .lock()
+            .get(image_index as usize)
+            .cloned()
+            .ok_or(PyxelError::ImageNotFound(image_index))?;
+        let aseprite = AsepriteFile::load(filename)?;
+        if include_colors.unwrap_or(false) && !aseprite.colors.is_empty() {
+            let mut colors = aseprite.colors.clone();
+            colors.truncate(MAX_COLORS as usize);
+            *self.colors.lock() = colors;
+        }
+
+        // Frames are laid out left to right and wrap at the right edge of the image bank
+        let mut image = image.lock();
+        let image_width = image.width();
+        let image_height = image.height();
+        let num_columns = (image_width.saturating_sub(x) / aseprite.width.max(1)).max(1);
+        let animation = Animation::new(ImageSource::Index(image_index));
+        {
+            let mut animation = animation.lock();
+            for (i, frame) in aseprite.frames.iter().enumerate() {
+                let u = x + (i as u32 % num_columns) * aseprite.width;
+                let v = y + (i as u32 / num_columns) * aseprite.height;
+                for (yi, row) in frame.data.chunks(aseprite.width as usize).enumerate() {
+                    for (xi, &value) in row.iter().enumerate() {
+                        let dst_x = u + xi as u32;
+                        let dst_y = v + yi as u32;
+                        if dst_x < image_width && dst_y < image_height {
+                            image
+                                .canvas
+                                .write_data(dst_x as usize, dst_y as usize, value);
+                        }
+                    }
+                }
+                let duration = (frame.duration as f64 / self.system.one_frame_ms).round() as u32;
+                animation.add_frame(u, v, aseprite.width, aseprite.height, duration.max(1));
+            }
+            for tag in &aseprite.tags {
+                let (start, end) = match tag.direction {
+                    TAG_REVERSE | TAG_PINGPONG_REVERSE => (tag.end, tag.start),
+                    _ => (tag.start, tag.end),
+                };
+                let mode: AnimationMode = match tag.direction {
+                    TAG_PINGPONG | TAG_PINGPONG_REVERSE => ANIM_PINGPONG,
+                    _ if tag.repeat == 1 => ANIM_ONCE,
+                    _ => ANIM_LOOP,
+                };
+                animation.add_tag(&tag.name, start, end, Some(mode));
+            }
+        }
+        Ok(animation)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+
+    use flate2::write::ZlibEncoder;
+    use flate2::Compression;
+
+    use super::*;
+
+    fn chunk(chunk_type: u16, data: &[u8]) -> Vec<u8> {
+        let mut bytes = ((data.len() + CHUNK_HEADER_SIZE) as u32)
+            .to_le_bytes()
+            .to_vec();
+        bytes.extend(chunk_type.to_le_bytes());
+        bytes.extend(data);
+        bytes
+    }
+
+    fn frame(duration: u16, chunks: &[Vec<u8>]) -> Vec<u8> {
+        let data = chunks.concat();
+        let mut bytes = ((data.len() + FRAME_HEADER_SIZE) as u32)
+            .to_le_bytes()
+            .to_vec();
+        bytes.extend(FRAME_MAGIC.to_le_bytes());
+        bytes.extend((chunks.len() as u16).to_le_bytes());
+        bytes.extend(duration.to_le_bytes());
+        bytes.extend([0; 2]);
+        bytes.extend((chunks.len() as u32).to_le_bytes());
+        bytes.extend(data);
+        bytes
+    }
+
+    fn layer(flags: u16, level: u16, name: &str) -> Vec<u8> {
+        let mut data = flags.to_le_bytes().to_vec();
+        data.extend(LAYER_TYPE_NORMAL.to_le_bytes());
+        data.extend(level.to_le_bytes());
+        data.extend([0; 10]);
+        data.extend((name.len() as u16).to_le_bytes());
+        data.extend(name.as_bytes());
+        chunk(CHUNK_LAYER, &data)
+    }
+
+    fn cel(layer: u16, x: i16, cel_type: u16, body: &[u8]) -> Vec<u8> {
+        let mut data = layer.to_le_bytes().to_vec();
+        data.extend(x.to_le_bytes());
+        data.extend(0i16.to_le_bytes());
+        data.push(255);
+        data.extend(cel_type.to_le_bytes());
+        data.extend([0; 7]);
+        data.extend(body);
+        chunk(CHUNK_CEL, &data)
+    }
+
+    fn aseprite_data() -> Vec<u8> {
+        let mut header = vec![0; HEADER_SIZE];
+        header[4..6].copy_from_slice(&FILE_MAGIC.to_le_bytes());
+        header[6..8].copy_from_slice(&2u16.to_le_bytes());
+        header[8..10].copy_from_slice(&3u16.to_le_bytes());
+        header[10..12].copy_from_slice(&2u16.to_le_bytes());
+        header[12..14].copy_from_slice(&INDEXED_COLOR_DEPTH.to_le_bytes());
+
+        let mut palette = 3u32.to_le_bytes().to_vec();
+        palette.extend(0u32.to_le_bytes());
+        palette.extend(2u32.to_le_bytes());
+        palette.extend([0; 8]);
+        for rgb in [[0, 0, 0], [255, 0, 0], [0, 0, 255]] {
+            palette.extend([0, 0]);
+            palette.extend(rgb);
+            palette.push(255);
+        }
+
+        let mut tags = 1u16.to_le_bytes().to_vec();
+        tags.extend([0; 8]);
+        tags.extend(0u16.to_le_bytes());
+        tags.extend(1u16.to_le_bytes());
+        tags.push(TAG_PINGPONG);
+        tags.extend(0u16.to_le_bytes());
+        tags.extend([0; 10]);
+        tags.extend(4u16.to_le_bytes());
+        tags.extend(b"walk");
+
+        let mut raw = 2u16.to_le_bytes().to_vec();
+        raw.extend(1u16.to_le_bytes());
+        raw.extend([1, 0]);
+        let mut compressed = 1u16.to_le_bytes().to_vec();
+        compressed.extend(2u16.to_le_bytes());
+        let mut encoder = ZlibEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(&[2, 2]).unwrap();
+        compressed.extend(encoder.finish().unwrap());
+
+        let frame1 = frame(
+            100,
+            &[
+                layer(LAYER_FLAG_VISIBLE, 0, "body"),
+                layer(0, 0, "hidden"),
+                layer(LAYER_FLAG_VISIBLE, 0, "face"),
+                chunk(CHUNK_PALETTE, &palette),
+                chunk(CHUNK_TAGS, &tags),
+                cel(0, 0, CEL_TYPE_RAW, &raw),
+                cel(1, 0, CEL_TYPE_RAW, &[1, 0, 1, 0, 2, 2]),
+                cel(2, 1, CEL_TYPE_COMPRESSED, &compressed),
+            ],
+        );
+        let frame2 = frame(50, &[cel(0, 1, CEL_TYPE_LINKED, &0u16.to_le_bytes())]);
+        [header, frame1, frame2].concat()
+    }
+
+    #[test]
+    fn test_parse_aseprite() {
+        let aseprite = AsepriteFile::parse(&aseprite_data()).unwrap();
+        assert_eq!((aseprite.width, aseprite.height), (3, 2));
+        assert_eq!(aseprite.colors, vec![0x000000, 0xff0000, 0x0000ff]);
+        assert_eq!(aseprite.frames.len(), 2);
+        assert_eq!(aseprite.frames[0].duration, 100);
+        assert_eq!(aseprite.frames[0].data, vec![1, 2, 0, 0, 2, 0]);
+        assert_eq!(aseprite.frames[1].data, vec![1, 0, 0, 0, 0, 0]);
+        assert_eq!(aseprite.tags.len(), 1);
+        assert_eq!(aseprite.tags[0].name, "walk");
+        assert_eq!((aseprite.tags[0].start, aseprite.tags[0].end), (0, 1));
+
+        let mut data = aseprite_data();
+        data[12] = 32;
+        assert!(AsepriteFile::parse(&data).is_err());
+        assert!(AsepriteFile::parse(&data[..100]).is_err());
+    }
+
+    #[test]
+    fn test_parse_palette_range() {
+        let palette = |size: u32, first: u32, last: u32| {
+            let mut data = size.to_le_bytes().to_vec();
+            data.extend(first.to_le_bytes());
+            data.extend(last.to_le_bytes());
+            data.extend([0; 8]);
+            data
+        };
+        let parse = |data: &[u8]| {
+            let mut colors = Vec::new();
+            AsepriteFile::parse_palette(&mut ByteReader::new(data), &mut colors).map(|()| colors)
+        };
+
+        let mut data = palette(256, 255, 255);
+        data.extend([0, 0, 0x12, 0x34, 0x56, 255]);
+        let colors = parse(&data).unwrap();
+        assert_eq!((colors.len(), colors[255]), (256, 0x123456));
+
+        assert!(parse(&palette(1, 0, u32::MAX)).is_err());
+        assert!(parse(&palette(u32::MAX, 0, 0)).is_err());
+        assert!(parse(&palette(2, 1, 0)).is_err());
+    }
+}
@@ -4,18 +4,67 @@ use std::mem::swap;
 use crate::rect_area::RectArea;
 use crate::utils::{f64_to_i32, f64_to_u32};
 
+const DITHERING_MATRIX: [[f32; 4]; 4] = [
+    [1.0 / 16.0, 9.0 / 16.0, 3.0 / 16.0, 11.0 / 16.0],
+    [13.0 / 16.0, 5.0 / 16.0, 15.0 / 16.0, 7.0 / 16.0],
+    [3.0 / 16.0, 11.0 / 16.0, 1.0 / 16.0, 9.0 / 16.0],
+    [15.0 / 16.0, 7.0 / 16.0, 13.0 / 16.0, 5.0 / 16.0],
+];
+
 pub trait ToIndex {
     fn to_index(&self) -> usize;
 }
 
+pub enum Blend<T> {
+    Mask(T),
+    Table {
+        width: usize,
+        height: usize,
+        values: Vec<T>,
+    },
+}
+
+impl<T: Copy + PartialEq + Default + ToIndex> Blend<T> {
+    pub fn table(canvas: &Canvas<T>) -> Self {
+        Self::Table {
+            width: canvas.width() as usize,
+            height: canvas.height() as usize,
+            values: canvas.data.clone(),
+        }
+    }
+
+    fn apply(&self, src_value: T, dst_value: T) -> Option<T> {
+        match self {
+            Self::Mask(mask_value) => (dst_value == *mask_value).then_some(src_value),
+            Self::Table {
+                width,
+                height,
+                values,
+            } => {
+                let src_index = src_value.to_index();
+                let dst_index = dst_value.to_index();
+                if src_index < *width && dst_index < *height {
+                    Some(values[width * dst_index + src_index])
+                } else {
+                    Some(src_value)
+                }
+            }
+        }
+    }
+}
+
 pub struct Canvas<T: Copy + PartialEq + Default + ToIndex> {
     pub self_rect: RectArea,
     pub clip_rect: RectArea,
     pub camera_x: i32,
     pub camera_y: i32,
     pub alpha: f32,
+    dither_thresholds: Vec<f32>,
+    dither_size: i32,
+    pub fill_pattern: Option<(u16, Option<T>)>,
+    pub blend: Option<Blend<T>>,
     pub data: Vec<T>,
-    should_write: fn(&Canvas<T>, i32, i32) -> bool,
+    write_value: fn(&Canvas<T>, i32, i32, T) -> Option<T>,
 }
 
 impl<T: Copy + PartialEq + Default + ToIndex> Canvas<T> {
@@ -26,8 +75,12 @@ impl<T: Copy + PartialEq + Default + ToIndex> Canvas<T> {
             camera_x: 0,
             camera_y: 0,
             alpha: 1.0,
+            dither_thresholds: DITHERING_MATRIX.concat(),
+            dither_size: DITHERING_MATRIX.len() as i32,
+            fill_pattern: None,
+            blend: None,
             data: vec![T::default(); (width * height) as usize],
-            should_write: Self::should_write_always,
+            write_value: Self::write_value_always,
         }
     }
 
@@ -43,6 +96,24 @@ impl<T: Copy + PartialEq + Default + ToIndex> Canvas<T> {
         self.data.as_mut_ptr()
     }
 
+    pub fn resize(&mut self, width: u32, height: u32) {
+        let mut canvas = Self::new(width, height);
+        canvas.blt(
+            0.0,
+            0.0,
+            self,
+            0.0,
+            0.0,
+            self.width() as f64,
+            self.height() as f64,
+            None,
+            None,
+        );
+        self.self_rect = canvas.self_rect;
+        self.clip_rect = canvas.clip_rect;
+        self.data = canvas.data;
+    }
+
     pub fn clip(&mut self, x: f64, y: f64, width: f64, height: f64) {
         let x = f64_to_i32(x);
         let y = f64_to_i32(y);
@@ -69,26 +140,44 @@ impl<T: Copy + PartialEq + Default + ToIndex> Canvas<T> {
 
     pub fn dither(&mut self, alpha: f32) {
         self.alpha = alpha;
-        if alpha <= 0.0 {
-            self.should_write = Self::should_write_never;
-        } else if alpha >= 1.0 {
-            self.should_write = Self::should_write_always;
-        } else {
-            self.should_write = Self::should_write_normal;
-        }
+        self.update_write_value();
+    }
+
+    pub fn dither_matrix(&mut self, matrix: &[f32]) {
+        let size = matrix.len().isqrt();
+        assert!(
+            size > 0 && size * size == matrix.len(),
+            "Dither matrix must be square"
+        );
+        self.dither_thresholds = matrix.to_vec();
+        self.dither_size = size as i32;
+    }
+
+    pub fn dither_matrix0(&mut self) {
+        self.dither_thresholds = DITHERING_MATRIX.concat();
+        self.dither_size = DITHERING_MATRIX.len() as i32;
+    }
+
+    pub fn fillp(&mut self, pattern: u16, secondary: Option<T>) {
+        self.fill_pattern = Some((pattern, secondary));
+        self.update_write_value();
+    }
+
+    pub fn fillp0(&mut self) {
+        self.fill_pattern = None;
+        self.update_write_value();
+    }
+
+    pub fn blend_mask(&mut self, value: T) {
+        self.blend = Some(Blend::Mask(value));
+    }
+
+    pub fn blend0(&mut self) {
+        self.blend = None;
     }
 
     pub fn cls(&mut self, value: T) {
-        let width = self.width();
-        let height = self.height();
-        let alpha = self.alpha;
-        self.dither(1.0);
-        for y in 0..height {
-            for x in 0..width {
-                self.write_data(x as usize, y as usize, value);
-            }
-        }
-        self.dither(alpha);
+        self.data.fill(value);
     }
 
     pub fn pget(&mut self, x: f64, y: f64) -> T {
@@ -161,6 +250,14 @@ impl<T: Copy + PartialEq + Default + ToIndex> Canvas<T> {
         let top = rect.top();
         let right = rect.right();
         let bottom = rect.bottom();
+        if self.writes_always() {
+            let width = self.width() as usize;
+            for y in top..=bottom {
+                let offset = width * y as usize;
+                self.data[offset + left as usize..=offset + right as usize].fill(value);
+            }
+            return;
+        }
         for y in top..=bottom {
             for x in left..=right {
                 self.write_data(x as usize, y as usize, value);
@@ -345,6 +442,131 @@ impl<T: Copy + PartialEq + Default + ToIndex> Canvas<T> {
         self.line(x2, y2, x3, y3, value);
     }
 
+    pub fn poly(&mut self, points: &[(f64, f64)], value: T) {
+        let local_points: Vec<(f64, f64)> = points
+            .iter()
+            .map(|&(x, y)| {
+                (
+                    (f64_to_i32(x) - self.camera_x) as f64,
+                    (f64_to_i32(y) - self.camera_y) as f64,
+                )
+            })
+            .collect();
+        self.fill_polygon(&local_points, value);
+        self.polyb(points, value);
+    }
+
+    pub fn polyb(&mut self, points: &[(f64, f64)], value: T) {
+        for (i, &(x1, y1)) in points.iter().enumerate() {
+            let (x2, y2) = points[(i + 1) % points.len()];
+            self.line(x1, y1, x2, y2, value);
+        }
+    }
+
+    pub fn thick_line(&mut self, x1: f64, y1: f64, x2: f64, y2: f64, thickness: f64, value: T) {
+        if thickness <= 1.0 {
+            self.line(x1, y1, x2, y2, value);
+            return;
+        }
+        let x1 = (f64_to_i32(x1) - self.camera_x) as f64;
+        let y1 = (f64_to_i32(y1) - self.camera_y) as f64;
+        let x2 = (f64_to_i32(x2) - self.camera_x) as f64;
+        let y2 = (f64_to_i32(y2) - self.camera_y) as f64;
+        let length = (x2 - x1).hypot(y2 - y1);
+        let (dir_x, dir_y) = if length > 0.0 {
+            ((x2 - x1) / length, (y2 - y1) / length)
+        } else {
+            (1.0, 0.0)
+        };
+        let (cap_x, cap_y) = (dir_x * 0.5, dir_y * 0.5);
+        let (normal_x, normal_y) = (-dir_y * thickness / 2.0, dir_x * thickness / 2.0);
+        self.fill_polygon(
+            &[
+                (x1 - cap_x + normal_x, y1 - cap_y + normal_y),
+                (x2 + cap_x + normal_x, y2 + cap_y + normal_y),
+                (x2 + cap_x - normal_x, y2 + cap_y - normal_y),
+                (x1 - cap_x - normal_x, y1 - cap_y - normal_y),
+            ],
+            value,
+        );
+    }
+
+    pub fn rrect(&mut self, x: f64, y: f64, width: f64, height: f64, radius: f64, value: T) {
+        let Some((left, top, right, bottom, radius)) =
+            self.rounded_rect_params(x, y, width, height, radius)
+        else {
+            return;
+        };
+        for yi in top..=bottom {
+            let inset = Self::rounded_rect_inset(yi, top, bottom, radius);
+            for xi in (left + inset)..=(right - inset) {
+                self.write_data_with_clipping(xi, yi, value);
+            }
+        }
+    }
+
+    pub fn rrectb(&mut self, x: f64, y: f64, width: f64, height: f64, radius: f64, value: T) {
+        let Some((left, top, right, bottom, radius)) =
+            self.rounded_rect_params(x, y, width, height, radius)
+        else {
+            return;
+        };
+        for yi in top..=bottom {
+            let inset = Self::rounded_rect_inset(yi, top, bottom, radius);
+            let edge_inset = if yi == top || yi == bottom {
+                right - left - inset
+            } else {
+                Self::rounded_rect_inset(yi - 1, top, bottom, radius)
+                    .max(Self::rounded_rect_inset(yi + 1, top, bottom, radius))
+                    .max(inset + 1)
+                    - 1
+            };
+            for xi in inset..=edge_inset {
+                self.write_data_with_clipping(left + xi, yi, value);
+                self.write_data_with_clipping(right - xi, yi, value);
+            }
+        }
+    }
+
+    pub fn arc(&mut self, x: f64, y: f64, radius: f64, start_angle: f64, end_angle: f64, value: T) {
+        let x = f64_to_i32(x) - self.camera_x;
+        let y = f64_to_i32(y) - self.camera_y;
+        let radius = f64_to_u32(radius);
+        for xi in 0..=radius as i32 {
+            let (x1, y1, x2, y2) = Self::ellipse_area(0.0, 0.0, radius as f64, radius as f64, xi);
+            for (dx, dy) in [
+                (x1, y1),
+                (x2, y1),
+                (x1, y2),
+                (x2, y2),
+                (y1, x1),
+                (y1, x2),
+                (y2, x1),
+                (y2, x2),
+            ] {
+                if Self::is_in_angle_range(dx, dy, start_angle, end_angle) {
+                    self.write_data_with_clipping(x + dx, y + dy, value);
+                }
+            }
+        }
+    }
+
+    pub fn pie(&mut self, x: f64, y: f64, radius: f64, start_angle: f64, end_angle: f64, value: T) {
+        let x = f64_to_i32(x) - self.camera_x;
+        let y = f64_to_i32(y) - self.camera_y;
+        let radius = f64_to_u32(radius);
+        for xi in 0..=radius as i32 {
+            let (x1, y1, x2, y2) = Self::ellipse_area(0.0, 0.0, radius as f64, radius as f64, xi);
+            for yi in y1..=y2 {
+                for (dx, dy) in [(x1, yi), (x2, yi), (yi, x1), (yi, x2)] {
+                    if Self::is_in_angle_range(dx, dy, start_angle, end_angle) {
+                        self.write_data_with_clipping(x + dx, y + dy, value);
+                    }
+                }
+            }
+        }
+    }
+
     pub fn fill(&mut self, x: f64, y: f64, value: T) {
         let x = f64_to_i32(x) - self.camera_x;
         let y = f64_to_i32(y) - self.camera_y;
@@ -353,10 +575,59 @@ impl<T: Copy + PartialEq + Default + ToIndex> Canvas<T> {
         }
         let dst_value = self.read_data(x as usize, y as usize);
         if value != dst_value {
-            self.fill_rec(x, y, value, dst_value);
+            self.fill_area(x, y, |area_value| area_value == dst_value, |_, _| value);
+        }
+    }
+
+    pub fn fill_boundary(&mut self, x: f64, y: f64, boundary: T, value: T) {
+        let x = f64_to_i32(x) - self.camera_x;
+        let y = f64_to_i32(y) - self.camera_y;
+        if self.clip_rect.contains(x, y) {
+            self.fill_area(x, y, |area_value| area_value != boundary, |_, _| value);
         }
     }
 
+    pub fn fill_pattern(
+        &mut self,
+        x: f64,
+        y: f64,
+        canvas: &Self,
+        canvas_x: f64,
+        canvas_y: f64,
+        width: f64,
+        height: f64,
+        palette: Option<&[T]>,
+    ) {
+        let x = f64_to_i32(x) - self.camera_x;
+        let y = f64_to_i32(y) - self.camera_y;
+        let pattern_rect = RectArea::new(
+            f64_to_i32(canvas_x),
+            f64_to_i32(canvas_y),
+            f64_to_u32(width.max(0.0)),
+            f64_to_u32(height.max(0.0)),
+        )
+        .intersects(canvas.self_rect);
+        if pattern_rect.is_empty() || !self.clip_rect.contains(x, y) {
+            return;
+        }
+        let camera_x = self.camera_x;
+        let camera_y = self.camera_y;
+        let dst_value = self.read_data(x as usize, y as usize);
+        self.fill_area(
+            x,
+            y,
+            |area_value| area_value == dst_value,
+            |xi, yi| {
+                let value_x =
+                    pattern_rect.left() + (xi + camera_x).rem_euclid(pattern_rect.width() as i32);
+                let value_y =
+                    pattern_rect.top() + (yi + camera_y).rem_euclid(pattern_rect.height() as i32);
+                let value = canvas.read_data(value_x as usize, value_y as usize);
+                palette.map_or(value, |palette| palette[value.to_index()])
+            },
+        );
+    }
+
     pub fn blt(
         &mut self,
         x: f64,
@@ -401,6 +672,19 @@ impl<T: Copy + PartialEq + Default + ToIndex> Canvas<T> {
             return;
         }
 
+        if transparent.is_none() && palette.is_none() && self.blend.is_none() && sign_x == 1 {
+            let src_width = canvas.width() as usize;
+            for yi in 0..height {
+                let value_y = src_y + sign_y * yi + offset_y;
+                let offset = src_width * value_y as usize + src_x as usize;
+                self.write_row(
+                    dst_x as usize,
+                    (dst_y + yi) as usize,
+                    &canvas.data[offset..offset + width as usize],
+                );
+            }
+            return;
+        }
         for yi in 0..height {
             for xi in 0..width {
                 let value_x = src_x + sign_x * xi + offset_x;
@@ -412,7 +696,87 @@ impl<T: Copy + PartialEq + Default + ToIndex> Canvas<T> {
                     }
                 }
                 let value = palette.map_or(value, |palette| palette[value.to_index()]);
-                self.write_data((dst_x + xi) as usize, (dst_y + yi) as usize, value);
+                self.write_blended_data((dst_x + xi) as usize, (dst_y + yi) as usize, value);
+            }
+        }
+    }
+
+    pub fn blt_transform(
+        &mut self,
+        x: f64,
+        y: f64,
+        canvas: &Self,
+        canvas_x: f64,
+        canvas_y: f64,
+        width: f64,
+        height: f64,
+        transparent: Option<T>,
+        palette: Option<&[T]>,
+        rotate: f64,
+        scale: f64,
+    ) {
+        let x = f64_to_i32(x) - self.camera_x;
+        let y = f64_to_i32(y) - self.camera_y;
+        let canvas_x = f64_to_i32(canvas_x);
+        let canvas_y = f64_to_i32(canvas_y);
+        let width = f64_to_i32(width);
+        let height = f64_to_i32(height);
+        let flip_x = width < 0;
+        let flip_y = height < 0;
+        let width = width.abs();
+        let height = height.abs();
+        if width == 0 || height == 0 || scale <= 0.0 {
+            return;
+        }
+
+        let half_width = width as f64 / 2.0;
+        let half_height = height as f64 / 2.0;
+        let center_x = x as f64 + half_width;
+        let center_y = y as f64 + half_height;
+        let (sin, cos) = rotate.to_radians().sin_cos();
+        let extent_x = (half_width * cos).abs() + (half_height * sin).abs();
+        let extent_y = (half_width * sin).abs() + (half_height * cos).abs();
+        let extent_x = extent_x * scale;
+        let extent_y = extent_y * scale;
+        let left = (center_x - extent_x).floor() as i32;
+        let top = (center_y - extent_y).floor() as i32;
+        let right = (center_x + extent_x).ceil() as i32;
+        let bottom = (center_y + extent_y).ceil() as i32;
+        let rect = RectArea::new(
+            left,
+            top,
+            (right - left + 1) as u32,
+            (bottom - top + 1) as u32,
+        )
+        .intersects(self.clip_rect);
+        if rect.is_empty() {
+            return;
+        }
+
+        for yi in rect.top()..=rect.bottom() {
+            let offset_y = yi as f64 + 0.5 - center_y;
+            for xi in rect.left()..=rect.right() {
+                let offset_x = xi as f64 + 0.5 - center_x;
+                let u = ((offset_x * cos + offset_y * sin) / scale + half_width).floor() as i32;
+                let v = ((offset_y * cos - offset_x * sin) / scale + half_height).floor() as i32;
+                if u < 0 || u >= width || v < 0 || v >= height {
+                    continue;
+                }
+                let u = if flip_x { width - 1 - u } else { u };
+                let v = if flip_y { height - 1 - v } else { v };
+                let value_x = canvas_x + u;
+                let value_y = canvas_y + v;
+                if !canvas.self_rect.contains(value_x, value_y) {
+                    continue;
+                }
+                let value = canvas.read_data(value_x as usize, value_y as usize);
+                if let Some(transparent) = transparent {
+                    if value == transparent {
+                        continue;
+                    }
+                }
+                let value = palette.map_or(value, |palette| palette[value.to_index()]);
+                self.write_blended_data(xi as usize, yi as usize, value);
             }
         }
     }
@@ -423,17 +787,106 @@ impl<T: Copy + PartialEq + Default + ToIndex> Canvas<T> {
     }
 
     pub fn write_data(&mut self, x: usize, y: usize, value: T) {
-        if (self.should_write)(self, x as i32, y as i32) {
+        if let Some(value) = (self.write_value)(self, x as i32, y as i32, value) {
             let width = self.width() as usize;
             self.data[width * y + x] = value;
         }
     }
 
+    pub fn write_row(&mut self, x: usize, y: usize, values: &[T]) {
+        if self.writes_always() {
+            let offset = self.width() as usize * y + x;
+            self.data[offset..offset + values.len()].copy_from_slice(values);
+        } else {
+            for (i, value) in values.iter().enumerate() {
+                self.write_data(x + i, y, *value);
+            }
+        }
+    }
+
+    fn write_blended_data(&mut self, x: usize, y: usize, value: T) {
+        let value = match &self.blend {
+            Some(blend) => blend.apply(value, self.read_data(x, y)),
+            None => Some(value),
+        };
+        if let Some(value) = value {
+            self.write_data(x, y, value);
+        }
+    }
+
     fn write_data_with_clipping(&mut self, x: i32, y: i32, value: T) {
-        if (self.should_write)(self, x, y) && self.clip_rect.contains(x, y) {
-            let width = self.width() as usize;
-            self.data[width * y as usize + x as usize] = value;
+        if self.clip_rect.contains(x, y) {
+            self.write_data(x as usize, y as usize, value);
+        }
+    }
+
+    fn fill_polygon(&mut self, points: &[(f64, f64)], value: T) {
+        if points.len() < 3 {
+            return;
+        }
+        let (min_y, max_y) = points
+            .iter()
+            .fold((f64::MAX, f64::MIN), |(min_y, max_y), &(_, y)| {
+                (min_y.min(y), max_y.max(y))
+            });
+        let top = max(min_y.ceil() as i32, self.clip_rect.top());
+        let bottom = (max_y.ceil() as i32 - 1).min(self.clip_rect.bottom());
+        let mut crossings = Vec::new();
+        for y in top..=bottom {
+            let yf = y as f64;
+            crossings.clear();
+            for (i, &(x1, y1)) in points.iter().enumerate() {
+                let (x2, y2) = points[(i + 1) % points.len()];
+                if (y1 <= yf) != (y2 <= yf) {
+                    crossings.push(x1 + (yf - y1) * (x2 - x1) / (y2 - y1));
+                }
+            }
+            crossings.sort_by(f64::total_cmp);
+            for span in crossings.chunks_exact(2) {
+                let left = max(span[0].ceil() as i32, self.clip_rect.left());
+                let right = (span[1].ceil() as i32 - 1).min(self.clip_rect.right());
+                for x in left..=right {
+                    self.write_data(x as usize, y as usize, value);
+                }
+            }
+        }
+    }
+
+    fn rounded_rect_params(
+        &self,
+        x: f64,
+        y: f64,
+        width: f64,
+        height: f64,
+        radius: f64,
+    ) -> Option<(i32, i32, i32, i32, i32)> {
+        let x = f64_to_i32(x) - self.camera_x;
+        let y = f64_to_i32(y) - self.camera_y;
+        let width = f64_to_i32(width);
+        let height = f64_to_i32(height);
+        if width <= 0 || height <= 0 {
+            return None;
         }
+        let radius = f64_to_i32(radius).clamp(0, (width.min(height) - 1) / 2);
+        Some((x, y, x + width - 1, y + height - 1, radius))
+    }
+
+    fn rounded_rect_inset(y: i32, top: i32, bottom: i32, radius: i32) -> i32 {
+        let dy = radius - (y - top).min(bottom - y);
+        if dy <= 0 {
+            return 0;
+        }
+        let dx = ((radius * radius - dy * dy) as f64).sqrt();
+        radius - f64_to_i32(dx)
+    }
+
+    fn is_in_angle_range(dx: i32, dy: i32, start_angle: f64, end_angle: f64) -> bool {
+        let range = end_angle - start_angle;
+        if range >= 360.0 || (dx == 0 && dy == 0) {
+            return true;
+        }
+        let angle = (dy as f64).atan2(dx as f64).to_degrees();
+        (angle - start_angle).rem_euclid(360.0) <= range.rem_euclid(360.0)
     }
 
     fn ellipse_params(x: i32, y: i32, width: u32, height: u32) -> (f64, f64, f64, f64) {
@@ -458,56 +911,98 @@ impl<T: Copy + PartialEq + Default + ToIndex> Canvas<T> {
         (x1, y1, x2, y2)
     }
 
-    fn fill_rec(&mut self, x: i32, y: i32, value: T, dst_value: T) {
-        if self.read_data(x as usize, y as usize) != dst_value {
-            return;
-        }
-        let mut xi = x;
-        while xi >= self.clip_rect.left() {
-            if self.read_data(xi as usize, y as usize) != dst_value {
-                break;
-            }
-            self.write_data(xi as usize, y as usize, value);
-            if y > self.clip_rect.top() {
-                self.fill_rec(xi, y - 1, value, dst_value);
+    fn fill_area<F: Fn(T) -> bool, G: Fn(i32, i32) -> T>(
+        &mut self,
+        x: i32,
+        y: i32,
+        is_inside: F,
+        value_at: G,
+    ) {
+        let rect = self.clip_rect;
+        let rect_width = rect.width() as usize;
+        let mut visited = vec![false; rect_width * rect.height() as usize];
+        let visited_index =
+            |x: i32, y: i32| (y - rect.top()) as usize * rect_width + (x - rect.left()) as usize;
+        let mut seeds = vec![(x, y)];
+
+        while let Some((x, y)) = seeds.pop() {
+            let can_fill = |canvas: &Self, visited: &[bool], x: i32| {
+                !visited[visited_index(x, y)] && is_inside(canvas.read_data(x as usize, y as usize))
+            };
+            if !can_fill(self, &visited, x) {
+                continue;
             }
-            if y < self.clip_rect.bottom() {
-                self.fill_rec(xi, y + 1, value, dst_value);
+            let mut left = x;
+            while left > rect.left() && can_fill(self, &visited, left - 1) {
+                left -= 1;
             }
-            xi -= 1;
-        }
-        let mut xi = x + 1;
-        while xi <= self.clip_rect.right() {
-            if self.read_data(xi as usize, y as usize) != dst_value {
-                break;
+            let mut right = x;
+            while right < rect.right() && can_fill(self, &visited, right + 1) {
+                right += 1;
             }
-            self.write_data(xi as usize, y as usize, value);
-            if y > self.clip_rect.top() {
-                self.fill_rec(xi, y - 1, value, dst_value);
+            for xi in left..=right {
+                visited[visited_index(xi, y)] = true;
+                self.write_data(xi as usize, y as usize, value_at(xi, y));
             }
-            if y < self.clip_rect.bottom() {
-                self.fill_rec(xi, y + 1, value, dst_value);
+
+            for yi in [y - 1, y + 1] {
+                if yi < rect.top() || yi > rect.bottom() {
+                    continue;
+                }
+                let mut is_in_span = false;
+                for xi in left..=right {
+                    let can_fill = !visited[visited_index(xi, yi)]
+                        && is_inside(self.read_data(xi as usize, yi as usize));
+                    if can_fill && !is_in_span {
+                        seeds.push((xi, yi));
+                    }
+                    is_in_span = can_fill;
+                }
             }
-            xi += 1;
         }
     }
 
-    fn should_write_always(&self, _x: i32, _y: i32) -> bool {
-        true
+    fn update_write_value(&mut self) {
+        self.write_value = if self.fill_pattern.is_some() {
+            Self::write_value_pattern
+        } else if self.alpha <= 0.0 {
+            Self::write_value_never
+        } else if self.alpha >= 1.0 {
+            Self::write_value_always
+        } else {
+            Self::write_value_normal
+        };
+    }
+
+    fn writes_always(&self) -> bool {
+        // Matches the cases where update_write_value selects write_value_always
+        self.fill_pattern.is_none() && self.alpha >= 1.0
     }
 
-    fn should_write_never(&self, _x: i32, _y: i32) -> bool {
-        false
+    #[allow(clippy::unnecessary_wraps)]
+    fn write_value_always(&self, _x: i32, _y: i32, value: T) -> Option<T> {
+        Some(value)
     }
 
-    fn should_write_normal(&self, x: i32, y: i32) -> bool {
-        const DITHERING_MATRIX: [[f32; 4]; 4] = [
-            [1.0 / 16.0, 9.0 / 16.0, 3.0 / 16.0, 11.0 / 16.0],
-            [13.0 / 16.0, 5.0 / 16.0, 15.0 / 16.0, 7.0 / 16.0],
-            [3.0 / 16.0, 11.0 / 16.0, 1.0 / 16.0, 9.0 / 16.0],
-            [15.0 / 16.0, 7.0 / 16.0, 13.0 / 16.0, 5.0 / 16.0],
-        ];
-        self.alpha > DITHERING_MATRIX[y.rem_euclid(4) as usize][x.rem_euclid(4) as usize]
+    fn write_value_never(&self, _x: i32, _y: i32, _value: T) -> Option<T> {
+        None
+    }
+
+    fn write_value_normal(&self, x: i32, y: i32, value: T) -> Option<T> {
+        let size = self.dither_size;
+        let threshold =
+            self.dither_thresholds[(y.rem_euclid(size) * size + x.rem_euclid(size)) as usize];
+        (self.alpha > threshold).then_some(value)
+    }
+
+    fn write_value_pattern(&self, x: i32, y: i32, value: T) -> Option<T> {
+        let (pattern, secondary) = self.fill_pattern?;
+        let bit = 15 - (y.rem_euclid(4) * 4 + x.rem_euclid(4));
+        if pattern & (1 << bit) == 0 {
+            Some(value)
+        } else {
+            secondary
+        }
     }
 }
 
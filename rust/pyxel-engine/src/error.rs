@@ -0,0 +1,54 @@
+use std::error::Error;
+use std::fmt;
+use std::io;
+
+#[derive(Debug)]
+pub enum PyxelError {
+    FileNotFound(String),
+    FileAccess(String, String),
+    InvalidFormat(String, String),
+    UnsupportedVersion(String, String),
+    PaletteNotFound(String),
+    ImageNotFound(u32),
+    InvalidShader(String),
+    RecordingNotStarted,
+}
+
+impl PyxelError {
+    pub(crate) fn from_io_error(filename: &str, err: &io::Error) -> Self {
+        if err.kind() == io::ErrorKind::NotFound {
+            Self::FileNotFound(filename.to_string())
+        } else {
+            Self::FileAccess(filename.to_string(), err.to_string())
+        }
+    }
+
+    pub(crate) fn invalid_format(filename: &str, message: impl fmt::Display) -> Self {
+        Self::InvalidFormat(filename.to_string(), message.to_string())
+    }
+}
+
+impl fmt::Display for PyxelError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::FileNotFound(filename) => write!(f, "File '{filename}' not found"),
+            Self::FileAccess(filename, message) => {
+                write!(f, "Failed to access file '{filename}': {message}")
+            }
+            Self::InvalidFormat(filename, message) => {
+                write!(f, "Invalid file '{filename}': {message}")
+            }
+            Self::UnsupportedVersion(filename, version) => {
+                write!(f, "Unsupported file version '{version}' in '{filename}'")
+            }
+            Self::PaletteNotFound(name) => write!(f, "Palette '{name}' not found"),
+            Self::ImageNotFound(index) => write!(f, "Image {index} not found"),
+            Self::InvalidShader(message) => write!(f, "Invalid shader: {message}"),
+            Self::RecordingNotStarted => write!(f, "Input recording is not started"),
+        }
+    }
+}
+
+impl Error for PyxelError {}
+
+pub type PyxelResult<T> = Result<T, PyxelError>;
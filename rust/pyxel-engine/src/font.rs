@@ -0,0 +1,252 @@
+use std::collections::HashMap;
+use std::fs;
+
+use crate::error::{PyxelError, PyxelResult};
+use crate::image::{Color, Image};
+use crate::text;
+
+struct Glyph {
+    width: i32,
+    height: i32,
+    offset_x: i32,
+    offset_y: i32,
+    advance: i32,
+    bitmap: Vec<u32>,
+}
+
+pub struct Font {
+    glyphs: HashMap<char, Glyph>,
+    default_char: Option<char>,
+    ascent: i32,
+    descent: i32,
+}
+
+pub type SharedFont = shared_type!(Font);
+
+impl Font {
+    pub fn from_bdf(filename: &str) -> SharedFont {
+        Self::try_from_bdf(filename).unwrap_or_else(|err| {
+            println!("{err}");
+            new_shared_type!(Self {
+                glyphs: HashMap::new(),
+                default_char: None,
+                ascent: 0,
+                descent: 0,
+            })
+        })
+    }
+
+    pub fn try_from_bdf(filename: &str) -> PyxelResult<SharedFont> {
+        let bdf_text = fs::read_to_string(filename)
+            .map_err(|err| PyxelError::from_io_error(filename, &err))?;
+        Self::parse_bdf(&bdf_text)
+            .map(|font| new_shared_type!(font))
+            .map_err(|message| PyxelError::invalid_format(filename, message))
+    }
+
+    pub const fn line_height(&self) -> i32 {
+        self.ascent + self.descent
+    }
+
+    pub fn text_width(&self, string: &str) -> i32 {
+        text::text_size(string, Some(self)).0
+    }
+
+    pub(crate) fn char_width(&self, c: char) -> i32 {
+        self.glyph(c).map_or(0, |glyph| glyph.advance)
+    }
+
+    pub(crate) fn draw_char(&self, image: &mut Image, x: i32, y: i32, c: char, color: Color) {
+        let Some(glyph) = self.glyph(c) else {
+            return;
+        };
+        let left = x + glyph.offset_x;
+        let top = y + self.ascent - glyph.offset_y - glyph.height;
+        for (yi, row) in glyph.bitmap.iter().enumerate() {
+            for xi in 0..glyph.width {
+                if row & (0x8000_0000 >> xi) != 0 {
+                    image.pset((left + xi) as f64, (top + yi as i32) as f64, color);
+                }
+            }
+        }
+    }
+
+    fn glyph(&self, c: char) -> Option<&Glyph> {
+        self.glyphs
+            .get(&c)
+            .or_else(|| self.glyphs.get(&self.default_char?))
+    }
+
+    fn parse_bdf(bdf_text: &str) -> Result<Self, String> {
+        let mut glyphs = HashMap::new();
+        let mut default_char = None;
+        let mut font_bounding_box = None;
+        let mut ascent = None;
+        let mut descent = None;
+        let mut lines = bdf_text.lines();
+
+        while let Some(line) = lines.next() {
+            let mut fields = line.split_whitespace();
+            match fields.next() {
+                Some("FONTBOUNDINGBOX") => font_bounding_box = Some(parse_numbers::<4>(fields)?),
+                Some("FONT_ASCENT") => ascent = Some(parse_numbers::<1>(fields)?[0]),
+                Some("FONT_DESCENT") => descent = Some(parse_numbers::<1>(fields)?[0]),
+                Some("DEFAULT_CHAR") => {
+                    default_char = char::from_u32(parse_numbers::<1>(fields)?[0] as u32);
+                }
+                Some("STARTCHAR") => {
+                    let (code, glyph) = Self::parse_glyph(&mut lines, font_bounding_box)?;
+                    if let Some(c) = code.and_then(char::from_u32) {
+                        glyphs.insert(c, glyph);
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        let [_, height, _, offset_y] = font_bounding_box.ok_or("missing FONTBOUNDINGBOX")?;
+        Ok(Self {
+            glyphs,
+            default_char,
+            ascent: ascent.unwrap_or(height + offset_y),
+            descent: descent.unwrap_or(-offset_y),
+        })
+    }
+
+    fn parse_glyph<'a>(
+        lines: &mut impl Iterator<Item = &'a str>,
+        font_bounding_box: Option<[i32; 4]>,
+    ) -> Result<(Option<u32>, Glyph), String> {
+        let mut code = None;
+        let mut advance = None;
+        let mut bounding_box = font_bounding_box;
+        for line in lines.by_ref() {
+            let mut fields = line.split_whitespace();
+            match fields.next() {
+                Some("ENCODING") => {
+                    let encoding = parse_numbers::<1>(fields)?[0];
+                    code = u32::try_from(encoding).ok();
+                }
+                Some("DWIDTH") => advance = Some(parse_numbers::<1>(fields)?[0]),
+                Some("BBX") => bounding_box = Some(parse_numbers::<4>(fields)?),
+                Some("BITMAP") => break,
+                Some("ENDCHAR") => return Err("missing BITMAP".to_string()),
+                _ => {}
+            }
+        }
+
+        let [width, height, offset_x, offset_y] = bounding_box.ok_or("missing BBX")?;
+        if !(0..=32).contains(&width) {
+            return Err(format!("unsupported glyph width {width}"));
+        }
+        let mut bitmap = Vec::new();
+        for line in lines.by_ref() {
+            let line = line.trim();
+            if line == "ENDCHAR" {
+                break;
+            }
+            let row = u64::from_str_radix(line, 16)
+                .map_err(|_| format!("invalid bitmap row '{line}'"))?;
+            let num_bits = line.len() as u32 * 4;
+            bitmap.push((row << (64 - num_bits) >> 32) as u32);
+        }
+        bitmap.resize(height.max(0) as usize, 0);
+
+        Ok((
+            code,
+            Glyph {
+                width,
+                height,
+                offset_x,
+                offset_y,
+                advance: advance.unwrap_or(width),
+                bitmap,
+            },
+        ))
+    }
+}
+
+fn parse_numbers<'a, const N: usize>(
+    fields: impl Iterator<Item = &'a str>,
+) -> Result<[i32; N], String> {
+    let numbers: Vec<i32> = fields
+        .take(N)
+        .map(|field| {
+            field
+                .parse()
+                .map_err(|_| format!("invalid number '{field}'"))
+        })
+        .collect::<Result<_, _>>()?;
+    numbers
+        .try_into()
+        .map_err(|_| format!("expected {N} numbers"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BDF_TEXT: &str = "STARTFONT 2.1
+FONT -test-fixed-medium-r-normal--8-80-75-75-c-40-iso10646-1
+SIZE 8 75 75
+FONTBOUNDINGBOX 8 8 0 -1
+STARTPROPERTIES 3
+FONT_ASCENT 7
+FONT_DESCENT 1
+DEFAULT_CHAR 63
+ENDPROPERTIES
+CHARS 3
+STARTCHAR question
+ENCODING 63
+DWIDTH 3 0
+BBX 2 2 0 4
+BITMAP
+C0
+C0
+ENDCHAR
+STARTCHAR I
+ENCODING 73
+DWIDTH 2 0
+BBX 1 3 0 0
+BITMAP
+80
+80
+80
+ENDCHAR
+STARTCHAR uni3042
+ENCODING 12354
+DWIDTH 8 0
+BBX 8 2 0 -1
+BITMAP
+FF
+81
+ENDCHAR
+ENDFONT
+";
+
+    #[test]
+    fn test_parse_bdf() {
+        let font = Font::parse_bdf(BDF_TEXT).unwrap();
+        assert_eq!(font.line_height(), 8);
+        assert_eq!(font.glyphs.len(), 3);
+        assert_eq!(font.text_width("II"), 4);
+        assert_eq!(font.text_width("Iあ\nI"), 10);
+        assert_eq!(font.text_width("x"), 3);
+        assert!(Font::parse_bdf("STARTFONT 2.1\nENDFONT\n").is_err());
+    }
+
+    #[test]
+    fn test_draw_text() {
+        let font = new_shared_type!(Font::parse_bdf(BDF_TEXT).unwrap());
+        let image = Image::new(16, 16);
+        image.lock().text(1.0, 0.0, "Iあ", 5, Some(font.clone()));
+        let pget = |x, y| image.lock().pget(x, y);
+        assert_eq!([pget(1.0, 3.0), pget(1.0, 6.0), pget(1.0, 2.0)], [0, 5, 0]);
+        assert_eq!([pget(3.0, 6.0), pget(10.0, 6.0), pget(3.0, 7.0)], [5, 5, 5]);
+        assert_eq!([pget(4.0, 7.0), pget(10.0, 7.0)], [0, 5]);
+
+        image.lock().cls(0);
+        image.lock().text(0.0, 0.0, "\n?", 6, Some(font));
+        assert_eq!([pget(0.0, 9.0), pget(1.0, 10.0), pget(0.0, 1.0)], [6, 6, 0]);
+    }
+}
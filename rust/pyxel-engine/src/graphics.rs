@@ -1,41 +1,59 @@
+#[cfg(not(feature = "headless"))]
 use std::collections::HashMap;
+#[cfg(not(feature = "headless"))]
 use std::mem::size_of;
 
 use cfg_if::cfg_if;
+#[cfg(not(feature = "headless"))]
 use glow::HasContext;
 
-use crate::image::Color;
+use crate::error::{PyxelError, PyxelResult};
+use crate::font::SharedFont;
+use crate::image::{AffineMatrix, Color, Rgb24, SharedImage};
 use crate::pyxel::Pyxel;
+#[cfg(not(feature = "headless"))]
 use crate::settings::{BACKGROUND_COLOR, MAX_COLORS, NUM_SCREEN_TYPES};
+use crate::text::TextAlign;
 
 cfg_if! {
-    if #[cfg(target_os = "macos")] {
+    if #[cfg(all(target_os = "macos", not(feature = "headless")))] {
         const GL_VERSION: &str = include_str!("shaders/gles_version.glsl");
-    } else {
+    } else if #[cfg(not(feature = "headless"))] {
         const GL_VERSION: &str = include_str!("shaders/gl_version.glsl");
     }
 }
+#[cfg(not(feature = "headless"))]
 const GLES_VERSION: &str = include_str!("shaders/gles_version.glsl");
+#[cfg(not(feature = "headless"))]
 const COMMON_VERT: &str = include_str!("shaders/common.vert");
+#[cfg(not(feature = "headless"))]
 const COMMON_FRAG: &str = include_str!("shaders/common.frag");
+#[cfg(not(feature = "headless"))]
 const SCREEN_FRAGS: [&str; NUM_SCREEN_TYPES as usize] = [
     include_str!("shaders/crisp.frag"),
     include_str!("shaders/smooth.frag"),
     include_str!("shaders/retro.frag"),
 ];
 
+#[cfg(not(feature = "headless"))]
 pub struct ScreenShader {
     shader_program: glow::Program,
     uniform_locations: HashMap<String, glow::UniformLocation>,
     vertex_array: glow::VertexArray,
 }
 
+#[cfg(not(feature = "headless"))]
 pub struct Graphics {
     screen_shaders: Vec<ScreenShader>,
+    screen_uniforms: HashMap<String, f32>,
     screen_texture: glow::NativeTexture,
     colors_texture: glow::NativeTexture,
+    border_texture: glow::NativeTexture,
+    border_color: Rgb24,
+    border_image: Option<SharedImage>,
 }
 
+#[cfg(not(feature = "headless"))]
 impl Graphics {
     pub fn new() -> Self {
         unsafe {
@@ -43,107 +61,141 @@ impl Graphics {
             let screen_shaders = Self::create_screen_shaders(gl);
             let screen_texture = Self::create_screen_texture(gl);
             let colors_texture = Self::create_colors_texture(gl);
+            let border_texture = Self::create_border_texture(gl);
             Self {
                 screen_shaders,
+                screen_uniforms: HashMap::new(),
                 screen_texture,
                 colors_texture,
+                border_texture,
+                border_color: BACKGROUND_COLOR,
+                border_image: None,
             }
         }
     }
 
     unsafe fn create_screen_shaders(gl: &mut glow::Context) -> Vec<ScreenShader> {
+        SCREEN_FRAGS
+            .iter()
+            .map(|screen_frag| {
+                Self::create_screen_shader(gl, screen_frag).unwrap_or_else(|err| panic!("{err}"))
+            })
+            .collect()
+    }
+
+    unsafe fn create_screen_shader(
+        gl: &mut glow::Context,
+        screen_frag: &str,
+    ) -> Result<ScreenShader, String> {
         let glsl_version = if pyxel_platform::is_gles_enabled() {
             GLES_VERSION
         } else {
             GL_VERSION
         };
-        let mut screen_shaders = Vec::new();
-        for &screen_frag in &SCREEN_FRAGS {
-            // Vertex shader
-            let vertex_shader = gl.create_shader(glow::VERTEX_SHADER).unwrap();
-            gl.shader_source(vertex_shader, &format!("{glsl_version}{COMMON_VERT}"));
-            gl.compile_shader(vertex_shader);
-            assert!(
-                gl.get_shader_compile_status(vertex_shader),
-                "\n[vertex shader]\n{}",
-                gl.get_shader_info_log(vertex_shader)
-            );
 
-            // Fragment shader
-            let fragment_shader = gl.create_shader(glow::FRAGMENT_SHADER).unwrap();
-            gl.shader_source(
-                fragment_shader,
-                &format!("{glsl_version}{COMMON_FRAG}{screen_frag}"),
-            );
-            gl.compile_shader(fragment_shader);
-            assert!(
-                gl.get_shader_compile_status(fragment_shader),
-                "\n[fragment shader]\n{}",
-                gl.get_shader_info_log(fragment_shader)
-            );
+        // Vertex shader
+        let vertex_shader = gl.create_shader(glow::VERTEX_SHADER)?;
+        gl.shader_source(vertex_shader, &format!("{glsl_version}{COMMON_VERT}"));
+        gl.compile_shader(vertex_shader);
+        if !gl.get_shader_compile_status(vertex_shader) {
+            let info_log = gl.get_shader_info_log(vertex_shader);
+            gl.delete_shader(vertex_shader);
+            return Err(format!("\n[vertex shader]\n{info_log}"));
+        }
 
-            // Shader program
-            let shader_program = gl.create_program().unwrap();
-            gl.attach_shader(shader_program, vertex_shader);
-            gl.attach_shader(shader_program, fragment_shader);
-            gl.link_program(shader_program);
-            assert!(
-                gl.get_program_link_status(shader_program),
-                "{}",
-                gl.get_program_info_log(shader_program)
-            );
-            gl.detach_shader(shader_program, vertex_shader);
+        // Fragment shader
+        let fragment_shader = gl.create_shader(glow::FRAGMENT_SHADER)?;
+        gl.shader_source(
+            fragment_shader,
+            &format!("{glsl_version}{COMMON_FRAG}{screen_frag}"),
+        );
+        gl.compile_shader(fragment_shader);
+        if !gl.get_shader_compile_status(fragment_shader) {
+            let info_log = gl.get_shader_info_log(fragment_shader);
             gl.delete_shader(vertex_shader);
-            gl.detach_shader(shader_program, fragment_shader);
             gl.delete_shader(fragment_shader);
+            return Err(format!("\n[fragment shader]\n{info_log}"));
+        }
 
-            // Uniform locations
-            let mut uniform_locations: HashMap<String, glow::UniformLocation> = HashMap::new();
-            let uniform_names = [
-                "u_screenPos",
-                "u_screenSize",
-                "u_screenScale",
-                "u_numColors",
-                "u_backgroundColor",
-                "u_screenTexture",
-                "u_colorsTexture",
-            ];
-            for &uniform_name in &uniform_names {
-                if let Some(location) = gl.get_uniform_location(shader_program, uniform_name) {
-                    uniform_locations.insert(uniform_name.to_string(), location);
-                }
+        // Shader program
+        let shader_program = gl.create_program()?;
+        gl.attach_shader(shader_program, vertex_shader);
+        gl.attach_shader(shader_program, fragment_shader);
+        gl.link_program(shader_program);
+        gl.detach_shader(shader_program, vertex_shader);
+        gl.delete_shader(vertex_shader);
+        gl.detach_shader(shader_program, fragment_shader);
+        gl.delete_shader(fragment_shader);
+        if !gl.get_program_link_status(shader_program) {
+            let info_log = gl.get_program_info_log(shader_program);
+            gl.delete_program(shader_program);
+            return Err(info_log);
+        }
+
+        // Uniform locations
+        let mut uniform_locations: HashMap<String, glow::UniformLocation> = HashMap::new();
+        let uniform_names = [
+            "u_windowSize",
+            "u_screenPos",
+            "u_screenSize",
+            "u_screenScale",
+            "u_screenTextureSize",
+            "u_numColors",
+            "u_backgroundColor",
+            "u_screenTexture",
+            "u_colorsTexture",
+            "u_borderTexture",
+            "u_borderTextureSize",
+            "u_time",
+        ];
+        for &uniform_name in &uniform_names {
+            if let Some(location) = gl.get_uniform_location(shader_program, uniform_name) {
+                uniform_locations.insert(uniform_name.to_string(), location);
             }
+        }
 
-            // Vertex array
-            let vertices: [f32; 8] = [-1.0, 1.0, -1.0, -1.0, 1.0, 1.0, 1.0, -1.0];
-            let vertex_array = gl.create_vertex_array().unwrap();
-            let vertex_buffer = gl.create_buffer().unwrap();
-            gl.bind_vertex_array(Some(vertex_array));
-            gl.bind_buffer(glow::ARRAY_BUFFER, Some(vertex_buffer));
-            gl.buffer_data_u8_slice(
-                glow::ARRAY_BUFFER,
-                vertices.align_to::<u8>().1,
-                glow::STATIC_DRAW,
-            );
-            let position = gl.get_attrib_location(shader_program, "position").unwrap();
-            gl.vertex_attrib_pointer_f32(
-                position,
-                2,
-                glow::FLOAT,
-                false,
-                2 * size_of::<f32>() as i32,
-                0,
-            );
-            gl.enable_vertex_attrib_array(position);
-
-            // Add screen shader
-            screen_shaders.push(ScreenShader {
-                shader_program,
-                uniform_locations,
-                vertex_array,
-            });
+        // Vertex array
+        let vertices: [f32; 8] = [-1.0, 1.0, -1.0, -1.0, 1.0, 1.0, 1.0, -1.0];
+        let vertex_array = gl.create_vertex_array()?;
+        let vertex_buffer = gl.create_buffer()?;
+        gl.bind_vertex_array(Some(vertex_array));
+        gl.bind_buffer(glow::ARRAY_BUFFER, Some(vertex_buffer));
+        gl.buffer_data_u8_slice(
+            glow::ARRAY_BUFFER,
+            vertices.align_to::<u8>().1,
+            glow::STATIC_DRAW,
+        );
+        let position = gl
+            .get_attrib_location(shader_program, "position")
+            .ok_or("missing attribute 'position'")?;
+        gl.vertex_attrib_pointer_f32(
+            position,
+            2,
+            glow::FLOAT,
+            false,
+            2 * size_of::<f32>() as i32,
+            0,
+        );
+        gl.enable_vertex_attrib_array(position);
+
+        Ok(ScreenShader {
+            shader_program,
+            uniform_locations,
+            vertex_array,
+        })
+    }
+
+    unsafe fn cache_uniform_location(
+        gl: &mut glow::Context,
+        shader: &mut ScreenShader,
+        name: &str,
+    ) {
+        if shader.uniform_locations.contains_key(name) {
+            return;
+        }
+        if let Some(location) = gl.get_uniform_location(shader.shader_program, name) {
+            shader.uniform_locations.insert(name.to_string(), location);
         }
-        screen_shaders
     }
 
     unsafe fn create_screen_texture(gl: &mut glow::Context) -> glow::NativeTexture {
@@ -199,6 +251,33 @@ impl Graphics {
         );
         colors_texture
     }
+
+    unsafe fn create_border_texture(gl: &mut glow::Context) -> glow::NativeTexture {
+        let border_texture = gl.create_texture().unwrap();
+        gl.active_texture(glow::TEXTURE2);
+        gl.bind_texture(glow::TEXTURE_2D, Some(border_texture));
+        gl.tex_parameter_i32(
+            glow::TEXTURE_2D,
+            glow::TEXTURE_MIN_FILTER,
+            glow::NEAREST as i32,
+        );
+        gl.tex_parameter_i32(
+            glow::TEXTURE_2D,
+            glow::TEXTURE_MAG_FILTER,
+            glow::NEAREST as i32,
+        );
+        gl.tex_parameter_i32(
+            glow::TEXTURE_2D,
+            glow::TEXTURE_WRAP_S,
+            glow::CLAMP_TO_EDGE as i32,
+        );
+        gl.tex_parameter_i32(
+            glow::TEXTURE_2D,
+            glow::TEXTURE_WRAP_T,
+            glow::CLAMP_TO_EDGE as i32,
+        );
+        border_texture
+    }
 }
 
 impl Pyxel {
@@ -226,10 +305,91 @@ impl Pyxel {
         self.screen.lock().pal0();
     }
 
+    pub fn screen_pal(&mut self, src_color: Color, dst_color: Color) {
+        self.screen_palette[src_color as usize] = dst_color;
+    }
+
+    pub fn screen_pal0(&mut self) {
+        for i in 0..self.screen_palette.len() {
+            self.screen_palette[i] = i as Color;
+        }
+    }
+
+    pub fn store_palette(&mut self, name: &str) {
+        let colors = self.colors.lock().clone();
+        self.palettes.insert(name.to_string(), colors);
+    }
+
+    pub fn load_palette(&mut self, name: &str) -> PyxelResult<()> {
+        let colors = self.stored_palette(name)?.clone();
+        *self.colors.lock() = colors;
+        Ok(())
+    }
+
+    pub fn lerp_palette(&mut self, name1: &str, name2: &str, t: f64) -> PyxelResult<()> {
+        let colors1 = self.stored_palette(name1)?;
+        let colors2 = self.stored_palette(name2)?;
+        let t = t.clamp(0.0, 1.0);
+        let colors = colors1
+            .iter()
+            .enumerate()
+            .map(|(i, &rgb1)| lerp_rgb(rgb1, colors2.get(i).copied().unwrap_or(rgb1), t))
+            .collect();
+        *self.colors.lock() = colors;
+        Ok(())
+    }
+
+    pub(crate) fn display_colors(&self) -> Vec<Rgb24> {
+        let colors = self.colors.lock();
+        (0..colors.len())
+            .map(|i| {
+                self.screen_palette
+                    .get(i)
+                    .and_then(|&color| colors.get(color as usize))
+                    .copied()
+                    .unwrap_or(colors[i])
+            })
+            .collect()
+    }
+
+    fn stored_palette(&self, name: &str) -> PyxelResult<&Vec<Rgb24>> {
+        self.palettes
+            .get(name)
+            .ok_or_else(|| PyxelError::PaletteNotFound(name.to_string()))
+    }
+
     pub fn dither(&self, alpha: f32) {
         self.screen.lock().dither(alpha);
     }
 
+    pub fn dither_matrix(&self, matrix: &[f32]) {
+        self.screen.lock().dither_matrix(matrix);
+    }
+
+    pub fn dither_matrix0(&self) {
+        self.screen.lock().dither_matrix0();
+    }
+
+    pub fn fillp(&self, pattern: u16, secondary_color: Option<Color>) {
+        self.screen.lock().fillp(pattern, secondary_color);
+    }
+
+    pub fn fillp0(&self) {
+        self.screen.lock().fillp0();
+    }
+
+    pub fn blend_mask(&self, color: Color) {
+        self.screen.lock().blend_mask(color);
+    }
+
+    pub fn blend_table(&self, table: SharedImage) {
+        self.screen.lock().blend_table(table);
+    }
+
+    pub fn blend0(&self) {
+        self.screen.lock().blend0();
+    }
+
     pub fn cls(&self, color: Color) {
         self.screen.lock().cls(color);
     }
@@ -278,6 +438,42 @@ impl Pyxel {
         self.screen.lock().trib(x1, y1, x2, y2, x3, y3, color);
     }
 
+    pub fn poly(&self, points: &[(f64, f64)], color: Color) {
+        self.screen.lock().poly(points, color);
+    }
+
+    pub fn polyb(&self, points: &[(f64, f64)], color: Color) {
+        self.screen.lock().polyb(points, color);
+    }
+
+    pub fn thick_line(&self, x1: f64, y1: f64, x2: f64, y2: f64, thickness: f64, color: Color) {
+        self.screen
+            .lock()
+            .thick_line(x1, y1, x2, y2, thickness, color);
+    }
+
+    pub fn rrect(&self, x: f64, y: f64, width: f64, height: f64, radius: f64, color: Color) {
+        self.screen.lock().rrect(x, y, width, height, radius, color);
+    }
+
+    pub fn rrectb(&self, x: f64, y: f64, width: f64, height: f64, radius: f64, color: Color) {
+        self.screen
+            .lock()
+            .rrectb(x, y, width, height, radius, color);
+    }
+
+    pub fn arc(&self, x: f64, y: f64, radius: f64, start_angle: f64, end_angle: f64, color: Color) {
+        self.screen
+            .lock()
+            .arc(x, y, radius, start_angle, end_angle, color);
+    }
+
+    pub fn pie(&self, x: f64, y: f64, radius: f64, start_angle: f64, end_angle: f64, color: Color) {
+        self.screen
+            .lock()
+            .pie(x, y, radius, start_angle, end_angle, color);
+    }
+
     pub fn fill(&self, x: f64, y: f64, color: Color) {
         self.screen.lock().fill(x, y, color);
     }
@@ -292,6 +488,8 @@ impl Pyxel {
         width: f64,
         height: f64,
         color_key: Option<Color>,
+        rotate: Option<f64>,
+        scale: Option<f64>,
     ) {
         self.screen.lock().blt(
             x,
@@ -302,6 +500,8 @@ impl Pyxel {
             width,
             height,
             color_key,
+            rotate,
+            scale,
         );
     }
 
@@ -328,10 +528,160 @@ impl Pyxel {
         );
     }
 
-    pub fn text(&self, x: f64, y: f64, string: &str, color: Color) {
-        self.screen.lock().text(x, y, string, color);
+    pub fn bltm_affine(
+        &self,
+        x: f64,
+        y: f64,
+        tilemap_index: u32,
+        width: f64,
+        height: f64,
+        matrix: AffineMatrix,
+        color_key: Option<Color>,
+        wrap: bool,
+    ) {
+        self.screen.lock().bltm_affine(
+            x,
+            y,
+            self.tilemaps.lock()[tilemap_index as usize].clone(),
+            width,
+            height,
+            matrix,
+            color_key,
+            wrap,
+        );
+    }
+
+    pub fn bltm_scanline<F: FnMut(i32) -> Option<AffineMatrix>>(
+        &self,
+        x: f64,
+        y: f64,
+        tilemap_index: u32,
+        width: f64,
+        height: f64,
+        scanline: F,
+        color_key: Option<Color>,
+        wrap: bool,
+    ) {
+        self.screen.lock().bltm_scanline(
+            x,
+            y,
+            self.tilemaps.lock()[tilemap_index as usize].clone(),
+            width,
+            height,
+            scanline,
+            color_key,
+            wrap,
+        );
+    }
+
+    pub fn blt_animation(
+        &self,
+        x: f64,
+        y: f64,
+        animation_index: u32,
+        time: u32,
+        tag: Option<&str>,
+        color_key: Option<Color>,
+        flip_h: bool,
+        flip_v: bool,
+    ) {
+        self.screen.lock().blt_animation(
+            x,
+            y,
+            self.animations.lock()[animation_index as usize].clone(),
+            time,
+            tag,
+            color_key,
+            flip_h,
+            flip_v,
+        );
+    }
+
+    pub fn text(&self, x: f64, y: f64, string: &str, color: Color, font: Option<SharedFont>) {
+        self.screen.lock().text(x, y, string, color, font);
+    }
+
+    pub fn text_box(
+        &self,
+        x: f64,
+        y: f64,
+        width: f64,
+        height: f64,
+        string: &str,
+        color: Color,
+        font: Option<SharedFont>,
+        align: TextAlign,
+    ) {
+        self.screen
+            .lock()
+            .text_box(x, y, width, height, string, color, font, align);
+    }
+
+    pub fn add_screen_shader(&mut self, screen_frag: &str) -> PyxelResult<u32> {
+        cfg_if! {
+            if #[cfg(feature = "headless")] {
+                let _ = screen_frag;
+            } else {
+                let screen_shader = unsafe {
+                    let gl = pyxel_platform::glow_context();
+                    let mut screen_shader = Graphics::create_screen_shader(gl, screen_frag)
+                        .map_err(PyxelError::InvalidShader)?;
+                    for name in self.graphics.screen_uniforms.keys() {
+                        Graphics::cache_uniform_location(gl, &mut screen_shader, name);
+                    }
+                    screen_shader
+                };
+                self.graphics.screen_shaders.push(screen_shader);
+            }
+        }
+        self.system.num_screen_modes += 1;
+        Ok(self.system.num_screen_modes - 1)
     }
 
+    pub fn border_color(&mut self, color: Rgb24) {
+        cfg_if! {
+            if #[cfg(feature = "headless")] {
+                let _ = color;
+            } else {
+                self.graphics.border_color = color;
+            }
+        }
+    }
+
+    pub fn border_image(&mut self, image: Option<SharedImage>) {
+        cfg_if! {
+            if #[cfg(feature = "headless")] {
+                let _ = image;
+            } else {
+                self.graphics.border_image = image;
+            }
+        }
+    }
+
+    pub fn screen_uniform(&mut self, name: &str, value: f32) {
+        cfg_if! {
+            if #[cfg(feature = "headless")] {
+                let _ = (name, value);
+            } else {
+                // Uniform locations are looked up once per shader rather than every frame
+                if self
+                    .graphics
+                    .screen_uniforms
+                    .insert(name.to_string(), value)
+                    .is_none()
+                {
+                    unsafe {
+                        let gl = pyxel_platform::glow_context();
+                        for shader in &mut self.graphics.screen_shaders {
+                            Graphics::cache_uniform_location(gl, shader, name);
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    #[cfg(not(feature = "headless"))]
     pub(crate) fn render_screen(&mut self) {
         unsafe {
             let gl = pyxel_platform::glow_context();
@@ -339,49 +689,58 @@ impl Pyxel {
             self.use_screen_shader(gl);
             self.bind_screen_texture(gl);
             self.bind_colors_texture(gl);
+            self.bind_border_texture(gl);
             gl.draw_arrays(glow::TRIANGLE_STRIP, 0, 4);
             pyxel_platform::swap_window();
         }
     }
 
+    #[cfg(not(feature = "headless"))]
     unsafe fn set_viewport(&self, gl: &mut glow::Context) {
         let (window_width, window_height) = pyxel_platform::window_size();
         gl.viewport(0, 0, window_width as i32, window_height as i32);
     }
 
+    #[cfg(not(feature = "headless"))]
     unsafe fn use_screen_shader(&self, gl: &mut glow::Context) {
         let shader = &self.graphics.screen_shaders[self.system.screen_mode as usize];
         gl.use_program(Some(shader.shader_program));
         let uniform_locations = &shader.uniform_locations;
+        let (window_width, window_height) = pyxel_platform::window_size();
+        if let Some(location) = uniform_locations.get("u_windowSize") {
+            gl.uniform_2_f32(Some(location), window_width as f32, window_height as f32);
+        }
         if let Some(location) = uniform_locations.get("u_screenPos") {
-            let (_, window_height) = pyxel_platform::window_size();
             gl.uniform_2_f32(
                 Some(location),
                 self.system.screen_x as f32,
-                (window_height as i32
-                    - self.system.screen_y
-                    - (self.height * self.system.screen_scale) as i32) as f32,
+                (window_height as i32 - self.system.screen_y - self.system.screen_height as i32)
+                    as f32,
             );
         }
         if let Some(location) = uniform_locations.get("u_screenSize") {
             gl.uniform_2_f32(
                 Some(location),
-                (self.width * self.system.screen_scale) as f32,
-                (self.height * self.system.screen_scale) as f32,
+                self.system.screen_width as f32,
+                self.system.screen_height as f32,
             );
         }
         if let Some(location) = uniform_locations.get("u_screenScale") {
             gl.uniform_1_f32(Some(location), self.system.screen_scale as f32);
         }
+        if let Some(location) = uniform_locations.get("u_screenTextureSize") {
+            gl.uniform_2_f32(Some(location), self.width as f32, self.height as f32);
+        }
         if let Some(location) = uniform_locations.get("u_numColors") {
             gl.uniform_1_i32(Some(location), self.colors.lock().len() as i32);
         }
         if let Some(location) = uniform_locations.get("u_backgroundColor") {
+            let border_color = self.graphics.border_color;
             gl.uniform_3_f32(
                 Some(location),
-                ((BACKGROUND_COLOR >> 16) as u8) as f32 / 255.0,
-                ((BACKGROUND_COLOR >> 8) as u8) as f32 / 255.0,
-                (BACKGROUND_COLOR as u8) as f32 / 255.0,
+                ((border_color >> 16) as u8) as f32 / 255.0,
+                ((border_color >> 8) as u8) as f32 / 255.0,
+                (border_color as u8) as f32 / 255.0,
             );
         }
         if let Some(location) = uniform_locations.get("u_screenTexture") {
@@ -390,9 +749,32 @@ impl Pyxel {
         if let Some(location) = uniform_locations.get("u_colorsTexture") {
             gl.uniform_1_i32(Some(location), 1);
         }
+        if let Some(location) = uniform_locations.get("u_borderTexture") {
+            gl.uniform_1_i32(Some(location), 2);
+        }
+        if let Some(location) = uniform_locations.get("u_borderTextureSize") {
+            let (border_width, border_height) =
+                self.graphics.border_image.as_ref().map_or((0, 0), |image| {
+                    let image = image.lock();
+                    (image.width(), image.height())
+                });
+            gl.uniform_2_f32(Some(location), border_width as f32, border_height as f32);
+        }
+        if let Some(location) = uniform_locations.get("u_time") {
+            gl.uniform_1_f32(
+                Some(location),
+                pyxel_platform::elapsed_time() as f32 / 1000.0,
+            );
+        }
+        for (name, value) in &self.graphics.screen_uniforms {
+            if let Some(location) = uniform_locations.get(name) {
+                gl.uniform_1_f32(Some(location), *value);
+            }
+        }
         gl.bind_vertex_array(Some(shader.vertex_array));
     }
 
+    #[cfg(not(feature = "headless"))]
     unsafe fn bind_screen_texture(&self, gl: &mut glow::Context) {
         gl.active_texture(glow::TEXTURE0);
         gl.bind_texture(glow::TEXTURE_2D, Some(self.graphics.screen_texture));
@@ -415,19 +797,47 @@ impl Pyxel {
         );
     }
 
+    #[cfg(not(feature = "headless"))]
+    unsafe fn bind_border_texture(&self, gl: &mut glow::Context) {
+        let Some(border_image) = &self.graphics.border_image else {
+            return;
+        };
+        gl.active_texture(glow::TEXTURE2);
+        gl.bind_texture(glow::TEXTURE_2D, Some(self.graphics.border_texture));
+        gl.pixel_store_i32(glow::UNPACK_ALIGNMENT, 1);
+        let texture_format = if pyxel_platform::is_gles_enabled() {
+            glow::LUMINANCE
+        } else {
+            glow::RED
+        };
+        let border_image = border_image.lock();
+        gl.tex_image_2d(
+            glow::TEXTURE_2D,
+            0,
+            texture_format as i32,
+            border_image.width() as i32,
+            border_image.height() as i32,
+            0,
+            texture_format,
+            glow::UNSIGNED_BYTE,
+            Some(&border_image.canvas.data),
+        );
+    }
+
+    #[cfg(not(feature = "headless"))]
     #[allow(clippy::uninlined_format_args)]
     unsafe fn bind_colors_texture(&self, gl: &mut glow::Context) {
         gl.active_texture(glow::TEXTURE1);
         gl.bind_texture(glow::TEXTURE_2D, Some(self.graphics.colors_texture));
         gl.pixel_store_i32(glow::UNPACK_ALIGNMENT, 4);
-        let colors = self.colors.lock();
+        let colors = self.display_colors();
         assert!(
             colors.len() >= 1 && colors.len() <= MAX_COLORS as usize,
             "Number of colors must be between 1 to {}",
             MAX_COLORS
         );
         let mut pixels: Vec<u8> = Vec::with_capacity(colors.len() * 3);
-        for color in &*colors {
+        for color in &colors {
             pixels.push((color >> 16) as u8);
             pixels.push((color >> 8) as u8);
             pixels.push(*color as u8);
@@ -445,3 +855,23 @@ impl Pyxel {
         );
     }
 }
+
+fn lerp_rgb(rgb1: Rgb24, rgb2: Rgb24, t: f64) -> Rgb24 {
+    [16, 8, 0].into_iter().fold(0, |rgb, shift| {
+        let value1 = ((rgb1 >> shift) & 0xff) as f64;
+        let value2 = ((rgb2 >> shift) & 0xff) as f64;
+        rgb | ((value1 + (value2 - value1) * t).round() as Rgb24) << shift
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_lerp_rgb() {
+        assert_eq!(lerp_rgb(0x102030, 0x000000, 0.0), 0x102030);
+        assert_eq!(lerp_rgb(0x102030, 0x000000, 1.0), 0x000000);
+        assert_eq!(lerp_rgb(0x000000, 0xff80ff, 0.5), 0x804080);
+    }
+}
@@ -1,16 +1,19 @@
 use std::array;
-use std::collections::HashMap;
 use std::path::Path;
 
-use image::imageops;
-
-use crate::canvas::{Canvas, CopyArea, ToIndex};
-use crate::pyxel::{COLORS, FONT_IMAGE, IMAGES};
+use crate::animation::SharedAnimation;
+use crate::canvas::{Blend, Canvas, CopyArea, ToIndex};
+use crate::error::{PyxelError, PyxelResult};
+use crate::font::SharedFont;
+use crate::indexed_png;
+use crate::pyxel::{COLORS, IMAGES};
+use crate::quantize::{self, DitherMode};
 use crate::rect_area::RectArea;
 use crate::settings::{
-    FONT_HEIGHT, FONT_WIDTH, MAX_COLORS, MAX_FONT_CODE, MIN_FONT_CODE, NUM_FONT_ROWS, TILE_SIZE,
+    DITHER_NONE, MAX_COLORS, TEXT_ALIGN_LEFT, TILE_FLIP_H, TILE_FLIP_V, TILE_ROTATE_90,
 };
-use crate::tilemap::{ImageSource, SharedTilemap};
+use crate::text::{self, TextAlign};
+use crate::tilemap::{ImageSource, SharedTilemap, TileFlags, Tilemap};
 use crate::utils;
 
 pub type Rgb24 = u32;
@@ -22,6 +25,31 @@ impl ToIndex for Color {
     }
 }
 
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct AffineMatrix {
+    pub a: f64,
+    pub b: f64,
+    pub c: f64,
+    pub d: f64,
+    pub tx: f64,
+    pub ty: f64,
+}
+
+impl AffineMatrix {
+    pub const IDENTITY: Self = Self::new(1.0, 0.0, 0.0, 1.0, 0.0, 0.0);
+
+    pub const fn new(a: f64, b: f64, c: f64, d: f64, tx: f64, ty: f64) -> Self {
+        Self { a, b, c, d, tx, ty }
+    }
+
+    pub fn transform(&self, x: f64, y: f64) -> (f64, f64) {
+        (
+            self.a * x + self.b * y + self.tx,
+            self.c * x + self.d * y + self.ty,
+        )
+    }
+}
+
 pub struct Image {
     pub(crate) canvas: Canvas<Color>,
     pub(crate) palette: [Color; MAX_COLORS as usize],
@@ -37,62 +65,71 @@ impl Image {
         })
     }
 
-    pub fn from_image(filename: &str, include_colors: Option<bool>) -> SharedImage {
+    pub fn from_image(
+        filename: &str,
+        include_colors: Option<bool>,
+        dither: Option<DitherMode>,
+        num_colors: Option<u32>,
+    ) -> SharedImage {
+        Self::try_from_image(filename, include_colors, dither, num_colors).unwrap_or_else(|err| {
+            println!("{err}");
+            Self::new(1, 1)
+        })
+    }
+
+    pub fn try_from_image(
+        filename: &str,
+        include_colors: Option<bool>,
+        dither: Option<DitherMode>,
+        num_colors: Option<u32>,
+    ) -> PyxelResult<SharedImage> {
         let include_colors = include_colors.unwrap_or(false);
+        let dither = dither.unwrap_or(DITHER_NONE);
+        let num_colors = num_colors.unwrap_or(MAX_COLORS).clamp(1, MAX_COLORS);
+        if let Some(image) = Self::from_indexed_png(filename, include_colors, num_colors) {
+            return Ok(image);
+        }
+        let file_image = image::open(Path::new(&filename))
+            .map_err(|err| match err {
+                image::ImageError::IoError(err) => PyxelError::from_io_error(filename, &err),
+                err => PyxelError::invalid_format(filename, err),
+            })?
+            .to_rgb8();
+        let (width, height) = file_image.dimensions();
+        let pixels: Vec<_> = file_image.pixels().map(|p| (p[0], p[1], p[2])).collect();
         let mut colors = COLORS.lock();
         if include_colors {
-            colors.clear();
-        }
-        let file = image::open(Path::new(&filename));
-        if file.is_err() {
-            println!("Failed to open file '{filename}'");
-            return Self::new(1, 1);
+            *colors = quantize::generate_palette(&pixels, num_colors as usize);
         }
-        let file_image = file.unwrap().to_rgb8();
-        let (width, height) = file_image.dimensions();
         let image = Self::new(width, height);
+        image.lock().canvas.data = quantize::quantize(&pixels, width, &colors, dither);
+        Ok(image)
+    }
+
+    fn from_indexed_png(
+        filename: &str,
+        include_colors: bool,
+        num_colors: u32,
+    ) -> Option<SharedImage> {
+        // Indexed PNGs keep their indices as long as the palette matches, which makes
+        // round-trips through save(indexed=True) lossless
+        let png = indexed_png::read_indexed_png(filename)?;
+        if png
+            .data
+            .iter()
+            .any(|&color| color as usize >= png.colors.len())
         {
-            let mut image = image.lock();
-            let mut color_table = HashMap::<(u8, u8, u8), Color>::new();
-            for y in 0..height {
-                for x in 0..width {
-                    let p = file_image.get_pixel(x, y);
-                    let src_rgb = (p[0], p[1], p[2]);
-                    if let Some(color) = color_table.get(&src_rgb) {
-                        image.canvas.write_data(x as usize, y as usize, *color);
-                    } else {
-                        let mut closest_color: Color = 0;
-                        if include_colors {
-                            colors.push(
-                                (src_rgb.0 as u32) << 16
-                                    | (src_rgb.1 as u32) << 8
-                                    | src_rgb.2 as u32,
-                            );
-                            closest_color = colors.len() as Color - 1;
-                        } else {
-                            let mut closest_dist: f64 = f64::MAX;
-                            for (i, pal_color) in colors.iter().enumerate() {
-                                let pal_rgb = (
-                                    (pal_color >> 16) as u8,
-                                    (pal_color >> 8) as u8,
-                                    *pal_color as u8,
-                                );
-                                let dist = Self::color_dist(src_rgb, pal_rgb);
-                                if dist < closest_dist {
-                                    closest_color = i as Color;
-                                    closest_dist = dist;
-                                }
-                            }
-                        }
-                        color_table.insert(src_rgb, closest_color);
-                        image
-                            .canvas
-                            .write_data(x as usize, y as usize, closest_color);
-                    }
-                }
-            }
+            return None;
         }
-        image
+        let mut colors = COLORS.lock();
+        if include_colors && png.colors.len() <= num_colors as usize {
+            colors.clone_from(&png.colors);
+        } else if include_colors || !colors.starts_with(&png.colors) {
+            return None;
+        }
+        let image = Self::new(png.width, png.height);
+        image.lock().canvas.data = png.data;
+        Some(image)
     }
 
     pub const fn width(&self) -> u32 {
@@ -133,11 +170,39 @@ impl Image {
             width as f64,
             height as f64,
             None,
+            None,
+            None,
         );
     }
 
-    pub fn load(&mut self, x: i32, y: i32, filename: &str, include_colors: Option<bool>) {
-        let image = Self::from_image(filename, include_colors);
+    pub fn load(
+        &mut self,
+        x: i32,
+        y: i32,
+        filename: &str,
+        include_colors: Option<bool>,
+        dither: Option<DitherMode>,
+        num_colors: Option<u32>,
+    ) {
+        let image = Self::from_image(filename, include_colors, dither, num_colors);
+        self.blt_image(x, y, image);
+    }
+
+    pub fn try_load(
+        &mut self,
+        x: i32,
+        y: i32,
+        filename: &str,
+        include_colors: Option<bool>,
+        dither: Option<DitherMode>,
+        num_colors: Option<u32>,
+    ) -> PyxelResult<()> {
+        let image = Self::try_from_image(filename, include_colors, dither, num_colors)?;
+        self.blt_image(x, y, image);
+        Ok(())
+    }
+
+    fn blt_image(&mut self, x: i32, y: i32, image: SharedImage) {
         let width = image.lock().width();
         let height = image.lock().height();
         self.blt(
@@ -149,33 +214,109 @@ impl Image {
             width as f64,
             height as f64,
             None,
+            None,
+            None,
         );
     }
 
-    pub fn save(&self, filename: &str, scale: u32) {
-        let colors = COLORS.lock();
-        let width = self.width();
-        let height = self.height();
-        let mut image = image::RgbImage::new(width, height);
-        for y in 0..height {
-            for x in 0..width {
-                let rgb = colors[self.canvas.read_data(x as usize, y as usize) as usize];
-                let r = (rgb >> 16) as u8;
-                let g = (rgb >> 8) as u8;
-                let b = rgb as u8;
-                image.put_pixel(x, y, image::Rgb([r, g, b]));
-            }
-        }
-        let image = imageops::resize(
-            &image,
-            width * scale,
-            height * scale,
-            imageops::FilterType::Nearest,
-        );
+    pub fn save(
+        &self,
+        filename: &str,
+        scale: u32,
+        transparent: Option<Color>,
+        indexed: Option<bool>,
+        rect: Option<(u32, u32, u32, u32)>,
+    ) {
+        self.try_save(filename, scale, transparent, indexed, rect)
+            .unwrap_or_else(|err| panic!("{err}"));
+    }
+
+    pub fn try_save(
+        &self,
+        filename: &str,
+        scale: u32,
+        transparent: Option<Color>,
+        indexed: Option<bool>,
+        rect: Option<(u32, u32, u32, u32)>,
+    ) -> PyxelResult<()> {
+        let colors = COLORS.lock().clone();
+        self.try_save_with_colors(
+            filename,
+            scale,
+            &colors,
+            transparent,
+            indexed.unwrap_or(false),
+            rect,
+        )
+    }
+
+    pub(crate) fn save_with_colors(&self, filename: &str, scale: u32, colors: &[Rgb24]) {
+        self.try_save_with_colors(filename, scale, colors, None, false, None)
+            .unwrap_or_else(|err| panic!("{err}"));
+    }
+
+    fn try_save_with_colors(
+        &self,
+        filename: &str,
+        scale: u32,
+        colors: &[Rgb24],
+        transparent: Option<Color>,
+        indexed: bool,
+        rect: Option<(u32, u32, u32, u32)>,
+    ) -> PyxelResult<()> {
+        let (x, y, width, height) = rect.unwrap_or((0, 0, self.width(), self.height()));
+        let left = x.min(self.width());
+        let top = y.min(self.height());
+        let width = x.saturating_add(width).min(self.width()) - left;
+        let height = y.saturating_add(height).min(self.height()) - top;
+        let scaled_width = width * scale;
+        let scaled_height = height * scale;
+        let data: Vec<Color> = (0..scaled_height)
+            .flat_map(|yi| {
+                (0..scaled_width).map(move |xi| {
+                    self.canvas
+                        .read_data((left + xi / scale) as usize, (top + yi / scale) as usize)
+                })
+            })
+            .collect();
         let filename = utils::add_file_extension(filename, ".png");
-        image
+        if indexed {
+            return indexed_png::write_indexed_png(
+                &filename,
+                scaled_width,
+                scaled_height,
+                &data,
+                colors,
+                transparent,
+            );
+        }
+
+        let pixel = |x: u32, y: u32| {
+            let color = data[(y * scaled_width + x) as usize];
+            let rgb = colors[color as usize];
+            ([(rgb >> 16) as u8, (rgb >> 8) as u8, rgb as u8], color)
+        };
+        let result = if let Some(transparent) = transparent {
+            image::RgbaImage::from_fn(scaled_width, scaled_height, |x, y| {
+                let ([red, green, blue], color) = pixel(x, y);
+                let alpha = if color == transparent { 0 } else { 255 };
+                image::Rgba([red, green, blue, alpha])
+            })
+            .save(&filename)
+        } else {
+            image::RgbImage::from_fn(scaled_width, scaled_height, |x, y| {
+                image::Rgb(pixel(x, y).0)
+            })
             .save(&filename)
-            .unwrap_or_else(|_| panic!("Failed to open file '{filename}'"));
+        };
+        result.map_err(|err| match err {
+            image::ImageError::IoError(err) => PyxelError::from_io_error(&filename, &err),
+            err => PyxelError::FileAccess(filename.clone(), err.to_string()),
+        })
+    }
+
+    pub fn resize(&mut self, width: u32, height: u32) {
+        self.canvas.resize(width, height);
     }
 
     pub fn clip(&mut self, x: f64, y: f64, width: f64, height: f64) {
@@ -208,6 +349,39 @@ impl Image {
         self.canvas.dither(alpha);
     }
 
+    pub fn dither_matrix(&mut self, matrix: &[f32]) {
+        self.canvas.dither_matrix(matrix);
+    }
+
+    pub fn dither_matrix0(&mut self) {
+        self.canvas.dither_matrix0();
+    }
+
+    pub fn fillp(&mut self, pattern: u16, secondary_color: Option<Color>) {
+        let secondary_color = secondary_color.map(|color| self.palette[color as usize]);
+        self.canvas.fillp(pattern, secondary_color);
+    }
+
+    pub fn fillp0(&mut self) {
+        self.canvas.fillp0();
+    }
+
+    pub fn blend_mask(&mut self, color: Color) {
+        self.canvas.blend_mask(color);
+    }
+
+    pub fn blend_table(&mut self, table: SharedImage) {
+        let blend = match table.try_lock() {
+            Some(table) => Blend::table(&table.canvas),
+            None => Blend::table(&self.canvas),
+        };
+        self.canvas.blend = Some(blend);
+    }
+
+    pub fn blend0(&mut self) {
+        self.canvas.blend0();
+    }
+
     pub fn cls(&mut self, color: Color) {
         self.canvas.cls(self.palette[color as usize]);
     }
@@ -264,10 +438,117 @@ impl Image {
             .trib(x1, y1, x2, y2, x3, y3, self.palette[color as usize]);
     }
 
+    pub fn poly(&mut self, points: &[(f64, f64)], color: Color) {
+        self.canvas.poly(points, self.palette[color as usize]);
+    }
+
+    pub fn polyb(&mut self, points: &[(f64, f64)], color: Color) {
+        self.canvas.polyb(points, self.palette[color as usize]);
+    }
+
+    pub fn thick_line(&mut self, x1: f64, y1: f64, x2: f64, y2: f64, thickness: f64, color: Color) {
+        self.canvas
+            .thick_line(x1, y1, x2, y2, thickness, self.palette[color as usize]);
+    }
+
+    pub fn rrect(&mut self, x: f64, y: f64, width: f64, height: f64, radius: f64, color: Color) {
+        self.canvas
+            .rrect(x, y, width, height, radius, self.palette[color as usize]);
+    }
+
+    pub fn rrectb(&mut self, x: f64, y: f64, width: f64, height: f64, radius: f64, color: Color) {
+        self.canvas
+            .rrectb(x, y, width, height, radius, self.palette[color as usize]);
+    }
+
+    pub fn arc(
+        &mut self,
+        x: f64,
+        y: f64,
+        radius: f64,
+        start_angle: f64,
+        end_angle: f64,
+        color: Color,
+    ) {
+        self.canvas.arc(
+            x,
+            y,
+            radius,
+            start_angle,
+            end_angle,
+            self.palette[color as usize],
+        );
+    }
+
+    pub fn pie(
+        &mut self,
+        x: f64,
+        y: f64,
+        radius: f64,
+        start_angle: f64,
+        end_angle: f64,
+        color: Color,
+    ) {
+        self.canvas.pie(
+            x,
+            y,
+            radius,
+            start_angle,
+            end_angle,
+            self.palette[color as usize],
+        );
+    }
+
     pub fn fill(&mut self, x: f64, y: f64, color: Color) {
         self.canvas.fill(x, y, self.palette[color as usize]);
     }
 
+    pub fn fill_boundary(&mut self, x: f64, y: f64, boundary_color: Color, color: Color) {
+        self.canvas
+            .fill_boundary(x, y, boundary_color, self.palette[color as usize]);
+    }
+
+    pub fn fill_pattern(
+        &mut self,
+        x: f64,
+        y: f64,
+        image: SharedImage,
+        image_x: f64,
+        image_y: f64,
+        width: f64,
+        height: f64,
+    ) {
+        if let Some(image) = image.try_lock() {
+            self.canvas.fill_pattern(
+                x,
+                y,
+                &image.canvas,
+                image_x,
+                image_y,
+                width,
+                height,
+                Some(&self.palette),
+            );
+        } else {
+            let copy_width = utils::f64_to_u32(width.abs());
+            let copy_height = utils::f64_to_u32(height.abs());
+            let mut canvas = Canvas::new(copy_width, copy_height);
+            canvas.blt(
+                0.0,
+                0.0,
+                &self.canvas,
+                image_x,
+                image_y,
+                copy_width as f64,
+                copy_height as f64,
+                None,
+                None,
+            );
+            self.canvas
+                .fill_pattern(x, y, &canvas, 0.0, 0.0, width, height, Some(&self.palette));
+        }
+    }
+
     pub fn blt(
         &mut self,
         x: f64,
@@ -278,9 +559,13 @@ impl Image {
         width: f64,
         height: f64,
         transparent: Option<Color>,
+        rotate: Option<f64>,
+        scale: Option<f64>,
     ) {
+        let rotate = rotate.unwrap_or(0.0);
+        let scale = scale.unwrap_or(1.0);
         if let Some(image) = image.try_lock() {
-            self.canvas.blt(
+            self.blt_canvas(
                 x,
                 y,
                 &image.canvas,
@@ -289,7 +574,8 @@ impl Image {
                 width,
                 height,
                 transparent,
-                Some(&self.palette),
+                rotate,
+                scale,
             );
         } else {
             let copy_width = utils::f64_to_u32(width.abs());
@@ -306,7 +592,7 @@ impl Image {
                 None,
                 None,
             );
-            self.canvas.blt(
+            self.blt_canvas(
                 x,
                 y,
                 &canvas,
@@ -315,7 +601,51 @@ impl Image {
                 width,
                 height,
                 transparent,
-                Some(&self.palette),
+                rotate,
+                scale,
+            );
+        }
+    }
+
+    #[allow(clippy::float_cmp)]
+    fn blt_canvas(
+        &mut self,
+        x: f64,
+        y: f64,
+        canvas: &Canvas<Color>,
+        canvas_x: f64,
+        canvas_y: f64,
+        width: f64,
+        height: f64,
+        transparent: Option<Color>,
+        rotate: f64,
+        scale: f64,
+    ) {
+        if rotate == 0.0 && scale == 1.0 {
+            self.canvas.blt(
+                x,
+                y,
+                canvas,
+                canvas_x,
+                canvas_y,
+                width,
+                height,
+                transparent,
+                Self::palette_remap(&self.palette),
+            );
+        } else {
+            self.canvas.blt_transform(
+                x,
+                y,
+                canvas,
+                canvas_x,
+                canvas_y,
+                width,
+                height,
+                transparent,
+                Self::palette_remap(&self.palette),
+                rotate,
+                scale,
             );
         }
     }
@@ -339,12 +669,7 @@ impl Image {
         let height = utils::f64_to_i32(height);
 
         let tilemap = tilemap.lock();
-        let tilemap_rect = RectArea::new(
-            tilemap.canvas.self_rect.left() * TILE_SIZE as i32,
-            tilemap.canvas.self_rect.top() * TILE_SIZE as i32,
-            tilemap.canvas.self_rect.width() * TILE_SIZE,
-            tilemap.canvas.self_rect.height() * TILE_SIZE,
-        );
+        let tilemap_rect = Self::tilemap_rect(&tilemap);
 
         let CopyArea {
             dst_x,
@@ -376,19 +701,19 @@ impl Image {
             ImageSource::Index(index) => images[*index as usize].lock(),
             ImageSource::Image(image) => image.lock(),
         };
+        let copy_rows =
+            transparent.is_none() && Self::palette_remap(&self.palette).is_none() && sign_x == 1;
         for yi in 0..height {
+            if copy_rows {
+                let tilemap_y = src_y + sign_y * yi + offset_y;
+                self.copy_tilemap_row(dst_x, dst_y + yi, &tilemap, &image, src_x, tilemap_y, width);
+                continue;
+            }
             for xi in 0..width {
                 let tilemap_x = src_x + sign_x * xi + offset_x;
                 let tilemap_y = src_y + sign_y * yi + offset_y;
 
-                let tile_x = tilemap_x / TILE_SIZE as i32;
-                let tile_y = tilemap_y / TILE_SIZE as i32;
-                let tile = tilemap.canvas.read_data(tile_x as usize, tile_y as usize);
-
-                let value_x = tile.0 as i32 * TILE_SIZE as i32 + tilemap_x % TILE_SIZE as i32;
-                let value_y = tile.1 as i32 * TILE_SIZE as i32 + tilemap_y % TILE_SIZE as i32;
-                let value = image.canvas.read_data(value_x as usize, value_y as usize);
-
+                let value = Self::tilemap_pixel(&tilemap, &image, tilemap_x, tilemap_y);
                 if let Some(transparent) = transparent {
                     if value == transparent {
                         continue;
@@ -401,46 +726,671 @@ impl Image {
         }
     }
 
-    pub fn text(&mut self, x: f64, y: f64, string: &str, color: Color) {
-        let mut x = utils::f64_to_i32(x); // No need to reflect camera_x
-        let mut y = utils::f64_to_i32(y); // No need to reflect camera_y
-        let color = self.palette[color as usize];
-        let palette1 = self.palette[1];
-        self.pal(1, color);
-        let start_x = x;
-        for c in string.chars() {
-            if c == '\n' {
-                x = start_x;
-                y += FONT_HEIGHT as i32;
+    pub fn bltm_affine(
+        &mut self,
+        x: f64,
+        y: f64,
+        tilemap: SharedTilemap,
+        width: f64,
+        height: f64,
+        matrix: AffineMatrix,
+        transparent: Option<Color>,
+        wrap: bool,
+    ) {
+        self.bltm_scanline(
+            x,
+            y,
+            tilemap,
+            width,
+            height,
+            |_| Some(matrix),
+            transparent,
+            wrap,
+        );
+    }
+
+    pub fn bltm_scanline<F: FnMut(i32) -> Option<AffineMatrix>>(
+        &mut self,
+        x: f64,
+        y: f64,
+        tilemap: SharedTilemap,
+        width: f64,
+        height: f64,
+        mut scanline: F,
+        transparent: Option<Color>,
+        wrap: bool,
+    ) {
+        let x = utils::f64_to_i32(x) - self.canvas.camera_x;
+        let y = utils::f64_to_i32(y) - self.canvas.camera_y;
+        let width = utils::f64_to_u32(width.max(0.0));
+        let height = utils::f64_to_u32(height.max(0.0));
+        let rect = RectArea::new(x, y, width, height).intersects(self.canvas.clip_rect);
+        if rect.is_empty() {
+            return;
+        }
+
+        let tilemap = tilemap.lock();
+        let tilemap_rect = Self::tilemap_rect(&tilemap);
+        if tilemap_rect.is_empty() {
+            return;
+        }
+        let tilemap_width = tilemap_rect.width() as i32;
+        let tilemap_height = tilemap_rect.height() as i32;
+
+        let images = IMAGES.lock();
+        let image = match &tilemap.imgsrc {
+            ImageSource::Index(index) => images[*index as usize].lock(),
+            ImageSource::Image(image) => image.lock(),
+        };
+        for dst_y in rect.top()..=rect.bottom() {
+            let Some(matrix) = scanline(dst_y - y) else {
                 continue;
+            };
+            let local_y = (dst_y - y) as f64 + 0.5;
+            for dst_x in rect.left()..=rect.right() {
+                let local_x = (dst_x - x) as f64 + 0.5;
+                let (tilemap_x, tilemap_y) = matrix.transform(local_x, local_y);
+                let mut tilemap_x = tilemap_x.floor() as i32;
+                let mut tilemap_y = tilemap_y.floor() as i32;
+                if wrap {
+                    tilemap_x = tilemap_x.rem_euclid(tilemap_width);
+                    tilemap_y = tilemap_y.rem_euclid(tilemap_height);
+                } else if !tilemap_rect.contains(tilemap_x, tilemap_y) {
+                    continue;
+                }
+
+                let value = Self::tilemap_pixel(&tilemap, &image, tilemap_x, tilemap_y);
+                if let Some(transparent) = transparent {
+                    if value == transparent {
+                        continue;
+                    }
+                }
+                let value = self.palette[value.to_index()];
+                self.canvas
+                    .write_data(dst_x as usize, dst_y as usize, value);
             }
-            if c < MIN_FONT_CODE || c > MAX_FONT_CODE {
-                continue;
+        }
+    }
+
+    pub fn blt_animation(
+        &mut self,
+        x: f64,
+        y: f64,
+        animation: SharedAnimation,
+        time: u32,
+        tag: Option<&str>,
+        transparent: Option<Color>,
+        flip_h: bool,
+        flip_v: bool,
+    ) {
+        let (image, frame) = {
+            let animation = animation.lock();
+            let Some(index) = animation.frame_index(time, tag) else {
+                return;
+            };
+            let image = match &animation.imgsrc {
+                ImageSource::Index(index) => IMAGES.lock()[*index as usize].clone(),
+                ImageSource::Image(image) => image.clone(),
+            };
+            (image, animation.frames[index])
+        };
+        let width = frame.width as f64;
+        let height = frame.height as f64;
+        self.blt(
+            x,
+            y,
+            image,
+            frame.u as f64,
+            frame.v as f64,
+            if flip_h { -width } else { width },
+            if flip_v { -height } else { height },
+            transparent,
+            None,
+            None,
+        );
+    }
+
+    pub fn text(&mut self, x: f64, y: f64, string: &str, color: Color, font: Option<SharedFont>) {
+        let font = font.as_ref().map(|font| font.lock());
+        text::draw_text(
+            self,
+            x,
+            y,
+            string,
+            color,
+            font.as_deref(),
+            TEXT_ALIGN_LEFT,
+            0,
+        );
+    }
+
+    pub fn text_box(
+        &mut self,
+        x: f64,
+        y: f64,
+        width: f64,
+        height: f64,
+        string: &str,
+        color: Color,
+        font: Option<SharedFont>,
+        align: TextAlign,
+    ) {
+        let font = font.as_ref().map(|font| font.lock());
+        let box_width = utils::f64_to_i32(width);
+        let string = text::wrap_text(string, box_width, font.as_deref());
+        let clip_rect = self.canvas.clip_rect;
+        self.canvas.clip_rect = clip_rect.intersects(RectArea::new(
+            utils::f64_to_i32(x) - self.canvas.camera_x,
+            utils::f64_to_i32(y) - self.canvas.camera_y,
+            box_width.max(0) as u32,
+            utils::f64_to_u32(height),
+        ));
+        text::draw_text(
+            self,
+            x,
+            y,
+            &string,
+            color,
+            font.as_deref(),
+            align,
+            box_width,
+        );
+        self.canvas.clip_rect = clip_rect;
+    }
+
+    fn palette_remap(palette: &[Color]) -> Option<&[Color]> {
+        let is_identity = palette
+            .iter()
+            .enumerate()
+            .all(|(i, &color)| color as usize == i);
+        (!is_identity).then_some(palette)
+    }
+
+    fn copy_tilemap_row(
+        &mut self,
+        x: i32,
+        y: i32,
+        tilemap: &Tilemap,
+        image: &Self,
+        tilemap_x: i32,
+        tilemap_y: i32,
+        width: i32,
+    ) {
+        // Each span within a tile maps to one image row unless the tile is transformed
+        let tile_width = tilemap.tile_width as i32;
+        let tile_height = tilemap.tile_height as i32;
+        let image_width = image.canvas.width() as usize;
+        let mut xi = 0;
+        while xi < width {
+            let offset_x = (tilemap_x + xi) % tile_width;
+            let span = (tile_width - offset_x).min(width - xi);
+            let tile = tilemap.canvas.read_data(
+                ((tilemap_x + xi) / tile_width) as usize,
+                (tilemap_y / tile_height) as usize,
+            );
+            if tile.2 == 0 {
+                let value_x = tile.0 as usize * tile_width as usize + offset_x as usize;
+                let value_y =
+                    tile.1 as usize * tile_height as usize + (tilemap_y % tile_height) as usize;
+                let offset = image_width * value_y + value_x;
+                self.canvas.write_row(
+                    (x + xi) as usize,
+                    y as usize,
+                    &image.canvas.data[offset..offset + span as usize],
+                );
+            } else {
+                for i in xi..xi + span {
+                    let value = Self::tilemap_pixel(tilemap, image, tilemap_x + i, tilemap_y);
+                    self.canvas.write_data((x + i) as usize, y as usize, value);
+                }
             }
-            let code = c as i32 - MIN_FONT_CODE as i32;
-            let src_x = (code % NUM_FONT_ROWS as i32) * FONT_WIDTH as i32;
-            let src_y = (code / NUM_FONT_ROWS as i32) * FONT_HEIGHT as i32;
-            self.blt(
-                x as f64,
-                y as f64,
-                FONT_IMAGE.clone(),
-                src_x as f64,
-                src_y as f64,
-                FONT_WIDTH as f64,
-                FONT_HEIGHT as f64,
+            xi += span;
+        }
+    }
+
+    fn tilemap_rect(tilemap: &Tilemap) -> RectArea {
+        RectArea::new(
+            0,
+            0,
+            tilemap.canvas.width() * tilemap.tile_width,
+            tilemap.canvas.height() * tilemap.tile_height,
+        )
+    }
+
+    fn tilemap_pixel(tilemap: &Tilemap, image: &Self, tilemap_x: i32, tilemap_y: i32) -> Color {
+        let tile_width = tilemap.tile_width as i32;
+        let tile_height = tilemap.tile_height as i32;
+        let tile_x = tilemap_x / tile_width;
+        let tile_y = tilemap_y / tile_height;
+        let tile = tilemap.canvas.read_data(tile_x as usize, tile_y as usize);
+
+        let (offset_x, offset_y) = Self::tile_offset(
+            tilemap_x % tile_width,
+            tilemap_y % tile_height,
+            tile_width,
+            tile_height,
+            tile.2,
+        );
+        let value_x = tile.0 as i32 * tile_width + offset_x;
+        let value_y = tile.1 as i32 * tile_height + offset_y;
+        image.canvas.read_data(value_x as usize, value_y as usize)
+    }
+
+    fn tile_offset(x: i32, y: i32, width: i32, height: i32, flags: TileFlags) -> (i32, i32) {
+        // Undo the tile transform, which rotates the tile first and then flips it
+        let x = if flags & TILE_FLIP_H != 0 {
+            width - 1 - x
+        } else {
+            x
+        };
+        let y = if flags & TILE_FLIP_V != 0 {
+            height - 1 - y
+        } else {
+            y
+        };
+        // Only square tiles can be rotated in place
+        if flags & TILE_ROTATE_90 != 0 && width == height {
+            (y, width - 1 - x)
+        } else {
+            (x, y)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tile_offset() {
+        let offset = |x, y, flags| Image::tile_offset(x, y, 8, 8, flags);
+        assert_eq!(offset(1, 2, 0), (1, 2));
+        assert_eq!(offset(1, 2, TILE_FLIP_H), (6, 2));
+        assert_eq!(offset(1, 2, TILE_FLIP_V), (1, 5));
+        assert_eq!(offset(0, 0, TILE_ROTATE_90), (0, 7));
+        assert_eq!(offset(7, 0, TILE_ROTATE_90), (0, 0));
+        assert_eq!(offset(0, 0, TILE_ROTATE_90 | TILE_FLIP_H), (0, 0));
+
+        assert_eq!(Image::tile_offset(1, 2, 8, 16, TILE_FLIP_V), (1, 13));
+        assert_eq!(Image::tile_offset(1, 2, 8, 16, TILE_ROTATE_90), (1, 2));
+    }
+
+    #[test]
+    fn test_blt_rotate_scale() {
+        let src = Image::new(2, 2);
+        src.lock().pset(0.0, 0.0, 1);
+        src.lock().pset(1.0, 0.0, 2);
+        src.lock().pset(0.0, 1.0, 3);
+
+        let dst = Image::new(8, 8);
+        let blt = |x, y, rotate, scale| {
+            dst.lock().cls(9);
+            dst.lock().blt(
+                x,
+                y,
+                src.clone(),
+                0.0,
+                0.0,
+                2.0,
+                2.0,
                 Some(0),
+                rotate,
+                scale,
             );
-            x += FONT_WIDTH as i32;
+        };
+        let pget = |x, y| dst.lock().pget(x, y);
+
+        blt(0.0, 0.0, Some(90.0), None);
+        assert_eq!([pget(0.0, 0.0), pget(1.0, 0.0)], [3, 1]);
+        assert_eq!([pget(0.0, 1.0), pget(1.0, 1.0)], [9, 2]);
+
+        blt(0.0, 0.0, None, Some(0.0));
+        assert_eq!(pget(0.0, 0.0), 9);
+
+        blt(2.0, 2.0, None, Some(2.0));
+        assert_eq!([pget(0.0, 0.0), pget(1.0, 1.0), pget(2.0, 2.0)], [9, 1, 1]);
+        assert_eq!([pget(3.0, 1.0), pget(1.0, 3.0), pget(4.0, 4.0)], [2, 3, 9]);
+        assert_eq!(pget(5.0, 5.0), 9);
+    }
+
+    #[test]
+    fn test_fill() {
+        let image = Image::new(256, 256);
+        let pget = |x, y| image.lock().pget(x, y);
+        image.lock().rectb(10.0, 10.0, 20.0, 20.0, 1);
+        image.lock().pset(15.0, 15.0, 2);
+
+        image.lock().fill(0.0, 0.0, 3);
+        assert_eq!([pget(0.0, 0.0), pget(255.0, 255.0)], [3, 3]);
+        assert_eq!([pget(10.0, 10.0), pget(11.0, 11.0)], [1, 0]);
+
+        image.lock().fill_boundary(11.0, 11.0, 1, 4);
+        assert_eq!(
+            [pget(11.0, 11.0), pget(15.0, 15.0), pget(28.0, 28.0)],
+            [4, 4, 4]
+        );
+        assert_eq!([pget(10.0, 10.0), pget(9.0, 9.0)], [1, 3]);
+
+        let pattern = Image::new(2, 1);
+        pattern.lock().pset(0.0, 0.0, 5);
+        pattern.lock().pset(1.0, 0.0, 6);
+        image
+            .lock()
+            .fill_pattern(0.0, 0.0, pattern, 0.0, 0.0, 2.0, 1.0);
+        assert_eq!(
+            [pget(0.0, 0.0), pget(1.0, 0.0), pget(255.0, 255.0)],
+            [5, 6, 6]
+        );
+        assert_eq!([pget(10.0, 10.0), pget(11.0, 11.0)], [1, 4]);
+
+        image.lock().dither(0.5);
+        image.lock().fill(11.0, 11.0, 7);
+        assert_eq!([pget(11.0, 11.0), pget(12.0, 11.0)], [7, 4]);
+    }
+
+    #[test]
+    fn test_dither_patterns() {
+        let image = Image::new(8, 8);
+        let pget = |x, y| image.lock().pget(x, y);
+
+        image.lock().dither_matrix(&[0.0, 0.5, 0.75, 0.25]);
+        image.lock().dither(0.5);
+        image.lock().rect(0.0, 0.0, 8.0, 8.0, 1);
+        assert_eq!([pget(0.0, 0.0), pget(1.0, 0.0)], [1, 0]);
+        assert_eq!([pget(2.0, 1.0), pget(3.0, 1.0)], [0, 1]);
+
+        image.lock().dither_matrix0();
+        image.lock().dither(1.0);
+        image.lock().cls(0);
+        image.lock().fillp(0x8421, None);
+        image.lock().rect(0.0, 0.0, 8.0, 8.0, 2);
+        assert_eq!([pget(0.0, 0.0), pget(1.0, 1.0), pget(7.0, 7.0)], [0, 0, 0]);
+        assert_eq!(pget(1.0, 0.0), 2);
+
+        image.lock().fillp(0x8421, Some(3));
+        image.lock().cls(5);
+        assert_eq!([pget(0.0, 4.0), pget(1.0, 4.0), pget(4.0, 0.0)], [5, 5, 5]);
+        image.lock().line(0.0, 4.0, 7.0, 4.0, 4);
+        assert_eq!([pget(0.0, 4.0), pget(1.0, 4.0), pget(4.0, 4.0)], [3, 4, 3]);
+
+        image.lock().fillp0();
+        image.lock().pset(0.0, 0.0, 6);
+        assert_eq!(pget(0.0, 0.0), 6);
+    }
+
+    #[test]
+    fn test_resize() {
+        let image = Image::new(4, 4);
+        image.lock().pset(1.0, 1.0, 7);
+        image.lock().pset(3.0, 3.0, 8);
+        image.lock().clip(0.0, 0.0, 2.0, 2.0);
+        image.lock().resize(3, 2);
+        let pget = |x, y| image.lock().pget(x, y);
+        let size = |image: &SharedImage| {
+            let image = image.lock();
+            (image.width(), image.height())
+        };
+        assert_eq!(size(&image), (3, 2));
+        assert_eq!([pget(1.0, 1.0), pget(2.0, 1.0)], [7, 0]);
+
+        image.lock().resize(6, 6);
+        assert_eq!([pget(1.0, 1.0), pget(5.0, 5.0)], [7, 0]);
+    }
+
+    #[test]
+    fn test_save() {
+        let dir = std::env::temp_dir().join("pyxel_test_image_save");
+        std::fs::create_dir_all(&dir).unwrap();
+        let filename = |name: &str| dir.join(name).to_string_lossy().to_string();
+        let image = Image::new(4, 2);
+        image.lock().pset(1.0, 0.0, 7);
+        image.lock().pset(2.0, 1.0, 8);
+
+        image
+            .lock()
+            .save(&filename("indexed"), 1, Some(0), Some(true), None);
+        let loaded = Image::from_image(&filename("indexed.png"), None, None, None);
+        assert_eq!(loaded.lock().canvas.data, image.lock().canvas.data);
+
+        image
+            .lock()
+            .save(&filename("rgba"), 2, Some(0), None, Some((1, 0, 8, 1)));
+        let rgba = image::open(filename("rgba.png")).unwrap().to_rgba8();
+        assert_eq!(rgba.dimensions(), (6, 2));
+        assert_eq!(rgba.get_pixel(0, 0)[3], 255);
+        assert_eq!(rgba.get_pixel(2, 1)[3], 0);
+    }
+
+    #[test]
+    fn test_blend() {
+        let src = Image::new(4, 1);
+        let image = Image::new(4, 1);
+        let pget = |x| image.lock().pget(x, 0.0);
+        for (x, color) in [1, 2, 3, 0].into_iter().enumerate() {
+            src.lock().pset(x as f64, 0.0, color);
         }
-        self.pal(1, palette1);
+
+        image.lock().cls(5);
+        image.lock().pset(1.0, 0.0, 6);
+        image.lock().blend_mask(5);
+        image.lock().blt(
+            0.0,
+            0.0,
+            src.clone(),
+            0.0,
+            0.0,
+            4.0,
+            1.0,
+            Some(0),
+            None,
+            None,
+        );
+        assert_eq!([pget(0.0), pget(1.0), pget(2.0), pget(3.0)], [1, 6, 3, 5]);
+
+        let table = Image::new(2, 8);
+        table.lock().pset(1.0, 5.0, 9);
+        image.lock().cls(5);
+        image.lock().blend_table(table);
+        image
+            .lock()
+            .blt(0.0, 0.0, src.clone(), 0.0, 0.0, 4.0, 1.0, None, None, None);
+        assert_eq!([pget(0.0), pget(1.0), pget(2.0), pget(3.0)], [9, 2, 3, 0]);
+
+        image.lock().cls(5);
+        image.lock().pset(1.0, 0.0, 6);
+        image.lock().blend_mask(5);
+        image.lock().blt(
+            0.0,
+            0.0,
+            src,
+            0.0,
+            0.0,
+            4.0,
+            1.0,
+            Some(0),
+            Some(180.0),
+            None,
+        );
+        assert_eq!([pget(0.0), pget(1.0), pget(2.0), pget(3.0)], [5, 6, 2, 1]);
+
+        image.lock().blend0();
+        image.lock().pset(1.0, 0.0, 7);
+        assert_eq!(pget(1.0), 7);
+    }
+
+    #[test]
+    fn test_shapes() {
+        let image = Image::new(32, 32);
+        let pget = |x, y| image.lock().pget(x, y);
+
+        image
+            .lock()
+            .poly(&[(2.0, 2.0), (6.0, 2.0), (6.0, 6.0), (2.0, 6.0)], 1);
+        assert_eq!([pget(2.0, 2.0), pget(4.0, 4.0), pget(6.0, 6.0)], [1, 1, 1]);
+        assert_eq!([pget(1.0, 4.0), pget(7.0, 4.0), pget(4.0, 7.0)], [0, 0, 0]);
+
+        let star = [
+            (16.0, 0.0),
+            (26.0, 30.0),
+            (0.0, 11.0),
+            (31.0, 11.0),
+            (6.0, 30.0),
+        ];
+        image.lock().cls(0);
+        image.lock().poly(&star, 2);
+        assert_eq!(
+            [pget(16.0, 5.0), pget(16.0, 16.0), pget(16.0, 31.0)],
+            [2, 0, 0]
+        );
+
+        image.lock().cls(0);
+        image.lock().thick_line(2.0, 10.0, 12.0, 10.0, 3.0, 3);
+        assert_eq!(
+            [pget(2.0, 9.0), pget(12.0, 11.0), pget(7.0, 10.0)],
+            [3, 3, 3]
+        );
+        assert_eq!(
+            [pget(1.0, 10.0), pget(13.0, 10.0), pget(7.0, 12.0)],
+            [0, 0, 0]
+        );
+
+        image.lock().cls(0);
+        image.lock().rrect(0.0, 0.0, 10.0, 8.0, 3.0, 4);
+        image.lock().rrectb(12.0, 0.0, 10.0, 8.0, 3.0, 5);
+        assert_eq!([pget(0.0, 0.0), pget(3.0, 0.0), pget(0.0, 3.0)], [0, 4, 4]);
+        assert_eq!([pget(9.0, 7.0), pget(5.0, 4.0)], [0, 4]);
+        assert_eq!(
+            [pget(12.0, 0.0), pget(15.0, 0.0), pget(17.0, 4.0)],
+            [0, 5, 0]
+        );
+        assert_eq!(
+            [pget(12.0, 4.0), pget(21.0, 4.0), pget(17.0, 7.0)],
+            [5, 5, 5]
+        );
+
+        image.lock().cls(0);
+        image.lock().arc(8.0, 8.0, 5.0, 0.0, 90.0, 6);
+        image.lock().pie(24.0, 8.0, 5.0, 180.0, 270.0, 7);
+        assert_eq!(
+            [pget(13.0, 8.0), pget(8.0, 13.0), pget(3.0, 8.0)],
+            [6, 6, 0]
+        );
+        assert_eq!(
+            [pget(21.0, 5.0), pget(24.0, 8.0), pget(27.0, 11.0)],
+            [7, 7, 0]
+        );
+    }
+
+    #[test]
+    fn test_bltm_affine() {
+        let src = Image::new(16, 8);
+        src.lock().rect(0.0, 0.0, 8.0, 8.0, 1);
+        src.lock().rect(8.0, 0.0, 8.0, 8.0, 2);
+        let tilemap = Tilemap::new(2, 1, ImageSource::Image(src));
+        tilemap.lock().pset(1.0, 0.0, (1, 0, 0));
+
+        let dst = Image::new(32, 8);
+        let bltm = |matrix, wrap| {
+            dst.lock().cls(9);
+            dst.lock()
+                .bltm_affine(0.0, 0.0, tilemap.clone(), 32.0, 8.0, matrix, None, wrap);
+        };
+        let pget = |x| dst.lock().pget(x, 0.0);
+
+        bltm(AffineMatrix::IDENTITY, false);
+        assert_eq!([pget(7.0), pget(8.0), pget(15.0), pget(16.0)], [1, 2, 2, 9]);
+
+        bltm(AffineMatrix::new(0.5, 0.0, 0.0, 0.5, 0.0, 0.0), false);
+        assert_eq!([pget(15.0), pget(16.0), pget(31.0)], [1, 2, 2]);
+
+        bltm(AffineMatrix::new(-1.0, 0.0, 0.0, 1.0, 16.0, 0.0), true);
+        assert_eq!([pget(0.0), pget(8.0), pget(16.0), pget(24.0)], [2, 1, 2, 1]);
+
+        dst.lock().cls(9);
+        dst.lock().bltm_scanline(
+            0.0,
+            0.0,
+            tilemap.clone(),
+            32.0,
+            8.0,
+            |y| (y >= 4).then_some(AffineMatrix::IDENTITY),
+            Some(1),
+            false,
+        );
+        let pget = |x, y| dst.lock().pget(x, y);
+        assert_eq!([pget(0.0, 3.0), pget(0.0, 4.0)], [9, 9]);
+        assert_eq!([pget(8.0, 3.0), pget(8.0, 4.0)], [9, 2]);
     }
 
-    fn color_dist(rgb1: (u8, u8, u8), rgb2: (u8, u8, u8)) -> f64 {
-        let (r1, g1, b1) = rgb1;
-        let (r2, g2, b2) = rgb2;
-        let dx = (r1 as f64 - r2 as f64) * 0.30;
-        let dy = (g1 as f64 - g2 as f64) * 0.59;
-        let dz = (b1 as f64 - b2 as f64) * 0.11;
-        dx * dx + dy * dy + dz * dz
+    #[test]
+    fn test_row_fast_paths() {
+        let src = Image::new(16, 16);
+        for y in 0..16 {
+            for x in 0..16 {
+                src.lock()
+                    .pset(x as f64, y as f64, ((x + y * 3) % 16) as Color);
+            }
+        }
+        let tilemap = Tilemap::new(4, 4, ImageSource::Image(src.clone()));
+        tilemap.lock().pset(1.0, 0.0, (1, 1, 0));
+        tilemap.lock().pset(2.0, 1.0, (1, 0, TILE_FLIP_H));
+        let pixels = |image: &SharedImage| image.lock().canvas.data.clone();
+
+        // A color key that never matches forces the per-pixel path without changing the output
+        let draw = |transparent| {
+            let dst = Image::new(24, 20);
+            let mut image = dst.lock();
+            image.cls(1);
+            image.clip(1.0, 2.0, 20.0, 16.0);
+            image.rect(-2.0, 3.0, 10.0, 30.0, 5);
+            image.blt(
+                3.0,
+                -1.0,
+                src.clone(),
+                2.0,
+                1.0,
+                12.0,
+                -9.0,
+                transparent,
+                None,
+                None,
+            );
+            image.bltm(
+                -5.0,
+                6.0,
+                tilemap.clone(),
+                3.0,
+                4.0,
+                26.0,
+                11.0,
+                transparent,
+            );
+            drop(image);
+            dst
+        };
+        let fast = draw(None);
+        assert_eq!(pixels(&fast), pixels(&draw(Some(255))));
+        let pget = |x, y| fast.lock().canvas.read_data(x, y);
+        assert_eq!(
+            [pget(0, 0), pget(1, 3), pget(7, 17), pget(8, 17)],
+            [1, 5, 5, 1]
+        );
+        assert_eq!([pget(3, 2), pget(14, 5), pget(15, 5)], [4, 6, 1]);
+        assert_eq!([pget(1, 6), pget(10, 14), pget(20, 17)], [13, 9, 1]);
+
+        let dst = Image::new(8, 8);
+        let expected = Image::new(8, 8);
+        dst.lock().dither(0.5);
+        expected.lock().dither(0.5);
+        dst.lock().rect(0.0, 0.0, 8.0, 8.0, 3);
+        for y in 0..8 {
+            for x in 0..8 {
+                expected.lock().pset(x as f64, y as f64, 3);
+            }
+        }
+        assert_eq!(pixels(&dst), pixels(&expected));
+        assert!(pixels(&dst).contains(&0) && pixels(&dst).contains(&3));
+        dst.lock().cls(2);
+        assert!(pixels(&dst).iter().all(|&color| color == 2));
     }
 }
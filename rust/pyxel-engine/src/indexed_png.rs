@@ -0,0 +1,85 @@
+use std::fs::File;
+use std::io::{BufReader, BufWriter};
+
+use crate::error::{PyxelError, PyxelResult};
+use crate::image::{Color, Rgb24};
+use crate::settings::MAX_COLORS;
+
+pub(crate) struct IndexedPng {
+    pub width: u32,
+    pub height: u32,
+    pub colors: Vec<Rgb24>,
+    pub data: Vec<Color>,
+}
+
+pub(crate) fn read_indexed_png(filename: &str) -> Option<IndexedPng> {
+    let file = File::open(filename).ok()?;
+    let mut decoder = png::Decoder::new(BufReader::new(file));
+    decoder.set_transformations(png::Transformations::IDENTITY);
+    let mut reader = decoder.read_info().ok()?;
+    let info = reader.info();
+    if info.color_type != png::ColorType::Indexed {
+        return None;
+    }
+    let colors: Vec<Rgb24> = info
+        .palette
+        .as_ref()?
+        .chunks_exact(3)
+        .map(|rgb| ((rgb[0] as Rgb24) << 16) | ((rgb[1] as Rgb24) << 8) | rgb[2] as Rgb24)
+        .collect();
+    if colors.len() > MAX_COLORS as usize {
+        return None;
+    }
+    let bit_depth = info.bit_depth as usize;
+    let mut buffer = vec![0; reader.output_buffer_size()];
+    let frame = reader.next_frame(&mut buffer).ok()?;
+
+    // Rows of low bit depth images pack several pixels into each byte
+    let pixels_per_byte = 8 / bit_depth;
+    let mask = ((1u16 << bit_depth) - 1) as u8;
+    let mut data = Vec::with_capacity((frame.width * frame.height) as usize);
+    for row in buffer.chunks(frame.line_size).take(frame.height as usize) {
+        for x in 0..frame.width as usize {
+            let shift = 8 - bit_depth * (x % pixels_per_byte + 1);
+            data.push((row[x / pixels_per_byte] >> shift) & mask);
+        }
+    }
+    Some(IndexedPng {
+        width: frame.width,
+        height: frame.height,
+        colors,
+        data,
+    })
+}
+
+pub(crate) fn write_indexed_png(
+    filename: &str,
+    width: u32,
+    height: u32,
+    data: &[Color],
+    colors: &[Rgb24],
+    transparent: Option<Color>,
+) -> PyxelResult<()> {
+    let file = File::create(filename).map_err(|err| PyxelError::from_io_error(filename, &err))?;
+    let mut encoder = png::Encoder::new(BufWriter::new(file), width, height);
+    encoder.set_color(png::ColorType::Indexed);
+    encoder.set_depth(png::BitDepth::Eight);
+    encoder.set_palette(
+        colors
+            .iter()
+            .flat_map(|rgb| [(rgb >> 16) as u8, (rgb >> 8) as u8, *rgb as u8])
+            .collect::<Vec<_>>(),
+    );
+    if let Some(transparent) = transparent.filter(|&color| (color as usize) < colors.len()) {
+        let mut alphas = vec![255; transparent as usize + 1];
+        alphas[transparent as usize] = 0;
+        encoder.set_trns(alphas);
+    }
+    encoder
+        .write_header()
+        .and_then(|mut writer| writer.write_image_data(data))
+        .map_err(|err| match err {
+            png::EncodingError::IoError(err) => PyxelError::from_io_error(filename, &err),
+            err => PyxelError::FileAccess(filename.to_string(), err.to_string()),
+        })
+}
@@ -134,11 +134,13 @@ impl Pyxel {
         let mut value = value;
         match key {
             MOUSE_POS_X => {
-                value = (value - self.system.screen_x) / self.system.screen_scale as i32;
+                value = (value - self.system.screen_x) * self.width as i32
+                    / self.system.screen_width as i32;
                 self.mouse_x = value;
             }
             MOUSE_POS_Y => {
-                value = (value - self.system.screen_y) / self.system.screen_scale as i32;
+                value = (value - self.system.screen_y) * self.height as i32
+                    / self.system.screen_height as i32;
                 self.mouse_y = value;
             }
             MOUSE_WHEEL_Y => {
@@ -0,0 +1,274 @@
+use std::fs;
+
+use pyxel_platform::Event;
+use serde::{Deserialize, Serialize};
+
+use crate::error::{PyxelError, PyxelResult};
+use crate::keys::{Key, KeyValue};
+use crate::pyxel::Pyxel;
+use crate::settings::INPUT_RECORD_FORMAT_VERSION;
+
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum RecordedEvent {
+    KeyPressed { key: Key },
+    KeyReleased { key: Key },
+    KeyValueChanged { key: Key, value: KeyValue },
+    TextInput { text: String },
+    FileDropped { filename: String },
+}
+
+impl RecordedEvent {
+    fn from_event(event: &Event) -> Option<Self> {
+        match event {
+            Event::KeyPressed { key } => Some(Self::KeyPressed { key: *key }),
+            Event::KeyReleased { key } => Some(Self::KeyReleased { key: *key }),
+            Event::KeyValueChanged { key, value } => Some(Self::KeyValueChanged {
+                key: *key,
+                value: *value,
+            }),
+            Event::TextInput { text } => Some(Self::TextInput { text: text.clone() }),
+            Event::FileDropped { filename } => Some(Self::FileDropped {
+                filename: filename.clone(),
+            }),
+            Event::WindowShown | Event::WindowHidden | Event::Quit => None,
+        }
+    }
+
+    fn to_event(&self) -> Event {
+        match self {
+            Self::KeyPressed { key } => Event::KeyPressed { key: *key },
+            Self::KeyReleased { key } => Event::KeyReleased { key: *key },
+            Self::KeyValueChanged { key, value } => Event::KeyValueChanged {
+                key: *key,
+                value: *value,
+            },
+            Self::TextInput { text } => Event::TextInput { text: text.clone() },
+            Self::FileDropped { filename } => Event::FileDropped {
+                filename: filename.clone(),
+            },
+        }
+    }
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+struct RecordedFrame {
+    frame: u32,
+    events: Vec<RecordedEvent>,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+struct InputRecordData {
+    format_version: u32,
+    rseed: u32,
+    nseed: u32,
+    num_frames: u32,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    frames: Vec<RecordedFrame>,
+}
+
+#[derive(PartialEq)]
+enum InputRecordMode {
+    Idle,
+    Recording,
+    Replaying,
+}
+
+pub struct InputRecord {
+    mode: InputRecordMode,
+    start_frame: u32,
+    data: InputRecordData,
+    next_frame_index: usize,
+}
+
+impl InputRecord {
+    pub fn new() -> Self {
+        Self {
+            mode: InputRecordMode::Idle,
+            start_frame: 0,
+            data: InputRecordData {
+                format_version: INPUT_RECORD_FORMAT_VERSION,
+                rseed: 0,
+                nseed: 0,
+                num_frames: 0,
+                frames: Vec::new(),
+            },
+            next_frame_index: 0,
+        }
+    }
+
+    pub fn process_events(&mut self, frame_count: u32, events: Vec<Event>) -> Vec<Event> {
+        let frame = frame_count - self.start_frame;
+        match self.mode {
+            InputRecordMode::Idle => events,
+            InputRecordMode::Recording => {
+                let recorded_events: Vec<RecordedEvent> = events
+                    .iter()
+                    .filter_map(RecordedEvent::from_event)
+                    .collect();
+                if !recorded_events.is_empty() {
+                    self.data.frames.push(RecordedFrame {
+                        frame,
+                        events: recorded_events,
+                    });
+                }
+                self.data.num_frames = frame + 1;
+                events
+            }
+            InputRecordMode::Replaying => {
+                if frame >= self.data.num_frames {
+                    self.mode = InputRecordMode::Idle;
+                    return events;
+                }
+                let mut events: Vec<Event> = events
+                    .into_iter()
+                    .filter(|event| RecordedEvent::from_event(event).is_none())
+                    .collect();
+                while let Some(recorded_frame) = self.data.frames.get(self.next_frame_index) {
+                    if recorded_frame.frame > frame {
+                        break;
+                    }
+                    if recorded_frame.frame == frame {
+                        events.extend(recorded_frame.events.iter().map(RecordedEvent::to_event));
+                    }
+                    self.next_frame_index += 1;
+                }
+                events
+            }
+        }
+    }
+
+    fn stop_recording(&mut self, filename: &str) -> PyxelResult<()> {
+        if self.mode != InputRecordMode::Recording {
+            return Err(PyxelError::RecordingNotStarted);
+        }
+        self.mode = InputRecordMode::Idle;
+        let toml_text =
+            toml::to_string(&self.data).map_err(|err| PyxelError::invalid_format(filename, err))?;
+        fs::write(filename, toml_text).map_err(|err| PyxelError::from_io_error(filename, &err))
+    }
+}
+
+impl Pyxel {
+    pub fn start_recording(&mut self) {
+        let seed = pyxel_platform::elapsed_time();
+        self.rseed(seed);
+        self.nseed(seed);
+        let input_record = &mut self.input_record;
+        input_record.mode = InputRecordMode::Recording;
+        input_record.start_frame = self.frame_count;
+        input_record.data = InputRecordData {
+            format_version: INPUT_RECORD_FORMAT_VERSION,
+            rseed: seed,
+            nseed: seed,
+            num_frames: 0,
+            frames: Vec::new(),
+        };
+    }
+
+    pub fn stop_recording(&mut self, filename: &str) {
+        self.try_stop_recording(filename)
+            .unwrap_or_else(|err| panic!("{err}"));
+    }
+
+    pub fn try_stop_recording(&mut self, filename: &str) -> PyxelResult<()> {
+        self.input_record.stop_recording(filename)?;
+        #[cfg(target_os = "emscripten")]
+        pyxel_platform::emscripten::save_file(filename);
+        Ok(())
+    }
+
+    pub fn start_replay(&mut self, filename: &str) {
+        self.try_start_replay(filename)
+            .unwrap_or_else(|err| panic!("{err}"));
+    }
+
+    pub fn try_start_replay(&mut self, filename: &str) -> PyxelResult<()> {
+        let toml_text = fs::read_to_string(filename)
+            .map_err(|err| PyxelError::from_io_error(filename, &err))?;
+        let data: InputRecordData =
+            toml::from_str(&toml_text).map_err(|err| PyxelError::invalid_format(filename, err))?;
+        if data.format_version > INPUT_RECORD_FORMAT_VERSION {
+            return Err(PyxelError::UnsupportedVersion(
+                filename.to_string(),
+                data.format_version.to_string(),
+            ));
+        }
+        self.rseed(data.rseed);
+        self.nseed(data.nseed);
+        let input_record = &mut self.input_record;
+        input_record.mode = InputRecordMode::Replaying;
+        input_record.start_frame = self.frame_count;
+        input_record.data = data;
+        input_record.next_frame_index = 0;
+        Ok(())
+    }
+
+    pub fn stop_replay(&mut self) {
+        if self.input_record.mode == InputRecordMode::Replaying {
+            self.input_record.mode = InputRecordMode::Idle;
+        }
+    }
+
+    pub fn is_replaying(&self) -> bool {
+        self.input_record.mode == InputRecordMode::Replaying
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use super::*;
+
+    #[test]
+    fn test_record_and_replay() {
+        let mut input_record = InputRecord::new();
+        input_record.mode = InputRecordMode::Recording;
+        input_record.start_frame = 10;
+        input_record.process_events(10, vec![Event::KeyPressed { key: 1 }]);
+        input_record.process_events(11, vec![Event::WindowHidden]);
+        input_record.process_events(
+            12,
+            vec![
+                Event::KeyReleased { key: 1 },
+                Event::TextInput {
+                    text: "abc".to_string(),
+                },
+            ],
+        );
+        assert_eq!(input_record.data.num_frames, 3);
+        assert_eq!(input_record.data.frames.len(), 2);
+
+        let toml_text = toml::to_string(&input_record.data).unwrap();
+        input_record.data = toml::from_str(&toml_text).unwrap();
+        input_record.mode = InputRecordMode::Replaying;
+        input_record.start_frame = 0;
+        input_record.next_frame_index = 0;
+
+        let events = input_record.process_events(0, vec![Event::KeyPressed { key: 2 }]);
+        assert!(matches!(events[..], [Event::KeyPressed { key: 1 }]));
+        let events = input_record.process_events(1, vec![Event::WindowShown]);
+        assert!(matches!(events[..], [Event::WindowShown]));
+        let events = input_record.process_events(2, Vec::new());
+        assert!(matches!(
+            &events[..],
+            [Event::KeyReleased { key: 1 }, Event::TextInput { text }] if text == "abc"
+        ));
+        let events = input_record.process_events(3, vec![Event::KeyPressed { key: 2 }]);
+        assert!(matches!(events[..], [Event::KeyPressed { key: 2 }]));
+        assert!(input_record.mode == InputRecordMode::Idle);
+    }
+
+    #[test]
+    fn test_stop_recording_without_start() {
+        let filename = std::env::temp_dir().join("pyxel_test_stop_recording_without_start.pyxrec");
+        let filename = filename.to_string_lossy();
+        let mut input_record = InputRecord::new();
+        assert!(matches!(
+            input_record.stop_recording(&filename),
+            Err(PyxelError::RecordingNotStarted)
+        ));
+        assert!(!Path::new(&*filename).exists());
+    }
+}
@@ -6,6 +6,7 @@
     clippy::cast_precision_loss,
     clippy::cast_sign_loss,
     clippy::fn_params_excessive_bools,
+    clippy::missing_errors_doc,
     clippy::missing_panics_doc,
     clippy::module_name_repetitions,
     clippy::must_use_candidate,
@@ -18,19 +19,26 @@
 
 #[macro_use]
 mod utils;
+mod animation;
+mod aseprite_file;
 mod audio;
 mod blip_buf;
 mod canvas;
 mod channel;
+mod error;
+mod font;
 mod graphics;
 mod image;
+mod indexed_png;
 mod input;
+mod input_record;
 mod math;
 mod music;
 mod old_resource_data;
 mod oscillator;
 mod profiler;
 mod pyxel;
+mod quantize;
 mod rect_area;
 mod resource;
 mod resource_data;
@@ -38,6 +46,7 @@ mod screencast;
 mod settings;
 mod sound;
 mod system;
+mod text;
 mod tiled_map_file;
 mod tilemap;
 mod tone;
@@ -45,14 +54,27 @@ mod watch_info;
 
 use pyxel_platform::keys;
 
+pub use crate::animation::{
+    Animation, AnimationFrame, AnimationMode, AnimationTag, SharedAnimation,
+};
 pub use crate::channel::{Channel, Detune, Note, SharedChannel, Speed, Volume};
-pub use crate::image::{Color, Image, Rgb24, SharedImage};
+pub use crate::error::{PyxelError, PyxelResult};
+pub use crate::font::{Font, SharedFont};
+pub use crate::image::{AffineMatrix, Color, Image, Rgb24, SharedImage};
 pub use crate::keys::*;
 pub use crate::music::{Music, SharedMusic, SharedSeq};
 pub use crate::oscillator::{Effect, Gain};
 pub use crate::pyxel::{init, Pyxel};
+pub use crate::quantize::DitherMode;
 pub use crate::settings::*;
 pub use crate::sound::{SharedSound, Sound};
-pub use crate::system::PyxelCallback;
-pub use crate::tilemap::{ImageSource, SharedTilemap, Tile, TileCoord, Tilemap};
+pub use crate::system::{PyxelCallback, ScaleMode};
+pub use crate::text::{text_size, wrap_text, TextAlign};
+pub use crate::tiled_map_file::{
+    TiledLayer, TiledLayerKind, TiledMap, TiledObject, TiledObjectShape, TiledProperties,
+    TiledProperty,
+};
+pub use crate::tilemap::{ImageSource, SharedTilemap, Tile, TileCoord, TileFlags, Tilemap};
 pub use crate::tone::{Amp4, Noise, SharedTone, Tone, Waveform};
+#[cfg(feature = "headless")]
+pub use pyxel_platform::{push_event, Event};
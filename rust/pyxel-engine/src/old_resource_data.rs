@@ -5,6 +5,7 @@ use std::path::Path;
 
 use zip::ZipArchive;
 
+use crate::error::{PyxelError, PyxelResult};
 use crate::image::{Color, Image, Rgb24};
 use crate::music::Music;
 use crate::pyxel::Pyxel;
@@ -58,7 +59,7 @@ impl ResourceItem for Tilemap {
     }
 
     fn clear(&mut self) {
-        self.cls((0, 0));
+        self.cls((0, 0, 0));
     }
 
     fn deserialize(&mut self, version: u32, input: &str) {
@@ -70,7 +71,7 @@ impl ResourceItem for Tilemap {
                         self.canvas.write_data(
                             x,
                             y,
-                            ((tile % 32) as TileCoord, (tile / 32) as TileCoord),
+                            ((tile % 32) as TileCoord, (tile / 32) as TileCoord, 0),
                         );
                     });
                 } else {
@@ -78,7 +79,7 @@ impl ResourceItem for Tilemap {
                         let tile_x = parse_hex_string(&tile[0..2]).unwrap();
                         let tile_y = parse_hex_string(&tile[2..4]).unwrap();
                         self.canvas
-                            .write_data(x, y, (tile_x as TileCoord, tile_y as TileCoord));
+                            .write_data(x, y, (tile_x as TileCoord, tile_y as TileCoord, 0));
                     });
                 }
             } else {
@@ -165,19 +166,25 @@ impl Pyxel {
         include_tilemaps: bool,
         include_sounds: bool,
         include_musics: bool,
-    ) {
+    ) -> PyxelResult<()> {
         let version_name = RESOURCE_ARCHIVE_DIRNAME.to_string() + "version";
         let contents = {
-            let mut file = archive.by_name(&version_name).unwrap();
+            let mut file = archive
+                .by_name(&version_name)
+                .map_err(|err| PyxelError::invalid_format(filename, err))?;
             let mut contents = String::new();
-            file.read_to_string(&mut contents).unwrap();
+            file.read_to_string(&mut contents)
+                .map_err(|err| PyxelError::from_io_error(filename, &err))?;
             contents
         };
-        let version = parse_version_string(&contents).unwrap();
-        assert!(
-            version <= parse_version_string(VERSION).unwrap(),
-            "Unsupported resource file version '{contents}'"
-        );
+        let version = parse_version_string(&contents)
+            .map_err(|err| PyxelError::invalid_format(filename, err))?;
+        if version > parse_version_string(VERSION).unwrap() {
+            return Err(PyxelError::UnsupportedVersion(
+                filename.to_string(),
+                simplify_string(&contents),
+            ));
+        }
 
         macro_rules! deserialize {
             ($type: ty, $list: ident, $count: expr) => {
@@ -227,6 +234,7 @@ impl Pyxel {
             self.colors.lock().clear();
             self.colors.lock().extend(colors.iter());
         }
+        Ok(())
     }
 }
 
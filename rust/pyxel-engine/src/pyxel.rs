@@ -1,13 +1,18 @@
+use std::array;
 use std::cmp::max;
+use std::collections::HashMap;
 use std::sync::atomic::{AtomicBool, Ordering};
 
 use once_cell::sync::Lazy;
 
+use crate::animation::{Animation, SharedAnimation};
 use crate::audio::Audio;
 use crate::channel::{Channel, SharedChannel};
+#[cfg(not(feature = "headless"))]
 use crate::graphics::Graphics;
-use crate::image::{Image, Rgb24, SharedImage};
+use crate::image::{Color, Image, Rgb24, SharedImage};
 use crate::input::Input;
+use crate::input_record::InputRecord;
 use crate::keys::Key;
 use crate::math::Math;
 use crate::music::{Music, SharedMusic};
@@ -15,8 +20,8 @@ use crate::resource::Resource;
 use crate::settings::{
     CURSOR_DATA, CURSOR_HEIGHT, CURSOR_WIDTH, DEFAULT_COLORS, DEFAULT_FPS, DEFAULT_QUIT_KEY,
     DEFAULT_TITLE, DEFAULT_TONES, DISPLAY_RATIO, FONT_DATA, FONT_HEIGHT, FONT_WIDTH, ICON_COLKEY,
-    ICON_DATA, ICON_SCALE, IMAGE_SIZE, NUM_CHANNELS, NUM_FONT_ROWS, NUM_IMAGES, NUM_MUSICS,
-    NUM_SAMPLES, NUM_SOUNDS, NUM_TILEMAPS, NUM_TONES, SAMPLE_RATE, TILEMAP_SIZE,
+    ICON_DATA, ICON_SCALE, IMAGE_SIZE, NUM_ANIMATIONS, NUM_CHANNELS, NUM_FONT_ROWS, NUM_IMAGES,
+    NUM_MUSICS, NUM_SAMPLES, NUM_SOUNDS, NUM_TILEMAPS, NUM_TONES, SAMPLE_RATE, TILEMAP_SIZE,
 };
 use crate::sound::{SharedSound, Sound};
 use crate::system::System;
@@ -40,6 +45,12 @@ static TILEMAPS: Lazy<shared_type!(Vec<SharedTilemap>)> = Lazy::new(|| {
         .collect())
 });
 
+static ANIMATIONS: Lazy<shared_type!(Vec<SharedAnimation>)> = Lazy::new(|| {
+    new_shared_type!((0..NUM_ANIMATIONS)
+        .map(|_| Animation::new(ImageSource::Index(0)))
+        .collect())
+});
+
 static CURSOR_IMAGE: Lazy<SharedImage> = Lazy::new(|| {
     let image = Image::new(CURSOR_WIDTH, CURSOR_HEIGHT);
     image.lock().set(0, 0, &CURSOR_DATA);
@@ -106,6 +117,7 @@ pub struct Pyxel {
 
     // Input
     pub(crate) input: Input,
+    pub(crate) input_record: InputRecord,
     pub mouse_x: i32,
     pub mouse_y: i32,
     pub mouse_wheel: i32,
@@ -113,10 +125,14 @@ pub struct Pyxel {
     pub dropped_files: Vec<String>,
 
     // Graphics
+    #[cfg(not(feature = "headless"))]
     pub(crate) graphics: Graphics,
     pub colors: shared_type!(Vec<Rgb24>),
+    pub(crate) palettes: HashMap<String, Vec<Rgb24>>,
+    pub(crate) screen_palette: [Color; Color::MAX as usize + 1],
     pub images: shared_type!(Vec<SharedImage>),
     pub tilemaps: shared_type!(Vec<SharedTilemap>),
+    pub animations: shared_type!(Vec<SharedAnimation>),
     pub screen: SharedImage,
     pub cursor: SharedImage,
     pub font: SharedImage,
@@ -177,6 +193,7 @@ pub fn init(
 
     // Input
     let input = Input::new();
+    let input_record = InputRecord::new();
     let mouse_x = 0;
     let mouse_y = 0;
     let mouse_wheel = 0;
@@ -184,10 +201,14 @@ pub fn init(
     let dropped_files = Vec::new();
 
     // Graphics
+    #[cfg(not(feature = "headless"))]
     let graphics = Graphics::new();
     let colors = COLORS.clone();
+    let palettes = HashMap::new();
+    let screen_palette = array::from_fn(|i| i as Color);
     let images = IMAGES.clone();
     let tilemaps = TILEMAPS.clone();
+    let animations = ANIMATIONS.clone();
     let screen = Image::new(width, height);
     let cursor = CURSOR_IMAGE.clone();
     let font = FONT_IMAGE.clone();
@@ -209,15 +230,20 @@ pub fn init(
         frame_count,
         resource,
         input,
+        input_record,
         mouse_x,
         mouse_y,
         mouse_wheel,
         input_text,
         dropped_files,
+        #[cfg(not(feature = "headless"))]
         graphics,
         colors,
+        palettes,
+        screen_palette,
         images,
         tilemaps,
+        animations,
         screen,
         cursor,
         font,
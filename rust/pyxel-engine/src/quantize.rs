@@ -0,0 +1,276 @@
+use std::collections::HashMap;
+
+use crate::image::{Color, Rgb24};
+use crate::settings::{DITHER_FLOYD_STEINBERG, DITHER_ORDERED};
+
+pub type DitherMode = u32;
+
+type Rgb = (u8, u8, u8);
+
+const BAYER_MATRIX: [[f64; 4]; 4] = [
+    [0.0, 8.0, 2.0, 10.0],
+    [12.0, 4.0, 14.0, 6.0],
+    [3.0, 11.0, 1.0, 9.0],
+    [15.0, 7.0, 13.0, 5.0],
+];
+const KMEANS_ITERATIONS: u32 = 4;
+
+fn rgb24_to_rgb(rgb: Rgb24) -> Rgb {
+    ((rgb >> 16) as u8, (rgb >> 8) as u8, rgb as u8)
+}
+
+fn rgb_to_rgb24(rgb: Rgb) -> Rgb24 {
+    ((rgb.0 as Rgb24) << 16) | ((rgb.1 as Rgb24) << 8) | rgb.2 as Rgb24
+}
+
+fn color_dist(rgb1: [f64; 3], rgb2: [f64; 3]) -> f64 {
+    let dr = (rgb1[0] - rgb2[0]) * 0.30;
+    let dg = (rgb1[1] - rgb2[1]) * 0.59;
+    let db = (rgb1[2] - rgb2[2]) * 0.11;
+    dr * dr + dg * dg + db * db
+}
+
+fn to_f64(rgb: Rgb) -> [f64; 3] {
+    [rgb.0 as f64, rgb.1 as f64, rgb.2 as f64]
+}
+
+fn nearest_index(rgb: [f64; 3], centers: &[[f64; 3]]) -> usize {
+    let mut nearest = 0;
+    let mut nearest_dist = f64::MAX;
+    for (i, center) in centers.iter().enumerate() {
+        let dist = color_dist(rgb, *center);
+        if dist < nearest_dist {
+            nearest = i;
+            nearest_dist = dist;
+        }
+    }
+    nearest
+}
+
+pub(crate) fn generate_palette(pixels: &[Rgb], num_colors: usize) -> Vec<Rgb24> {
+    // Images that already fit keep their exact colors in order of appearance
+    let mut counts: HashMap<Rgb, u32> = HashMap::new();
+    let mut unique_colors = Vec::new();
+    for &rgb in pixels {
+        let count = counts.entry(rgb).or_insert(0);
+        if *count == 0 {
+            unique_colors.push(rgb);
+        }
+        *count += 1;
+    }
+    if unique_colors.len() <= num_colors {
+        return unique_colors.into_iter().map(rgb_to_rgb24).collect();
+    }
+
+    let entries: Vec<([f64; 3], f64)> = unique_colors
+        .iter()
+        .map(|rgb| (to_f64(*rgb), counts[rgb] as f64))
+        .collect();
+    let mut centers = median_cut(entries.clone(), num_colors);
+    refine_kmeans(&entries, &mut centers);
+    centers
+        .iter()
+        .map(|center| {
+            let [r, g, b] = center.map(|value| value.round().clamp(0.0, 255.0) as u8);
+            rgb_to_rgb24((r, g, b))
+        })
+        .collect()
+}
+
+fn median_cut(entries: Vec<([f64; 3], f64)>, num_colors: usize) -> Vec<[f64; 3]> {
+    let channel_range = |entries: &[([f64; 3], f64)], channel: usize| {
+        let (min, max) = entries
+            .iter()
+            .fold((f64::MAX, f64::MIN), |(min, max), entry| {
+                (min.min(entry.0[channel]), max.max(entry.0[channel]))
+            });
+        max - min
+    };
+    let widest_channel = |entries: &[([f64; 3], f64)]| {
+        (0..3)
+            .map(|channel| (channel, channel_range(entries, channel)))
+            .fold((0, -1.0), |widest, current| {
+                if current.1 > widest.1 {
+                    current
+                } else {
+                    widest
+                }
+            })
+    };
+
+    let mut boxes = vec![entries];
+    while boxes.len() < num_colors {
+        let Some((index, channel)) = boxes
+            .iter()
+            .enumerate()
+            .filter(|(_, entries)| entries.len() > 1)
+            .map(|(index, entries)| (index, widest_channel(entries)))
+            .max_by(|(_, (_, range1)), (_, (_, range2))| range1.total_cmp(range2))
+            .map(|(index, (channel, _))| (index, channel))
+        else {
+            break;
+        };
+
+        // Split at the weighted median so that frequent colors get more palette entries
+        let mut entries = boxes.swap_remove(index);
+        entries.sort_by(|entry1, entry2| entry1.0[channel].total_cmp(&entry2.0[channel]));
+        let total: f64 = entries.iter().map(|entry| entry.1).sum();
+        let mut count = 0.0;
+        let mut split = entries.len() - 1;
+        for (i, entry) in entries.iter().enumerate() {
+            count += entry.1;
+            if count >= total / 2.0 {
+                split = i + 1;
+                break;
+            }
+        }
+        let split = split.clamp(1, entries.len() - 1);
+        let upper = entries.split_off(split);
+        boxes.push(entries);
+        boxes.push(upper);
+    }
+
+    boxes
+        .iter()
+        .map(|entries| {
+            let total: f64 = entries.iter().map(|entry| entry.1).sum();
+            let mut sum = [0.0; 3];
+            for (rgb, count) in entries {
+                for channel in 0..3 {
+                    sum[channel] += rgb[channel] * count;
+                }
+            }
+            sum.map(|value| value / total)
+        })
+        .collect()
+}
+
+fn refine_kmeans(entries: &[([f64; 3], f64)], centers: &mut [[f64; 3]]) {
+    for _ in 0..KMEANS_ITERATIONS {
+        let mut sums = vec![[0.0; 3]; centers.len()];
+        let mut totals = vec![0.0; centers.len()];
+        for (rgb, count) in entries {
+            let index = nearest_index(*rgb, centers);
+            for channel in 0..3 {
+                sums[index][channel] += rgb[channel] * count;
+            }
+            totals[index] += count;
+        }
+        for (i, center) in centers.iter_mut().enumerate() {
+            if totals[i] > 0.0 {
+                *center = sums[i].map(|value| value / totals[i]);
+            }
+        }
+    }
+}
+
+pub(crate) fn quantize(
+    pixels: &[Rgb],
+    width: u32,
+    palette: &[Rgb24],
+    dither: DitherMode,
+) -> Vec<Color> {
+    let centers: Vec<[f64; 3]> = palette
+        .iter()
+        .map(|rgb| to_f64(rgb24_to_rgb(*rgb)))
+        .collect();
+    if centers.is_empty() {
+        return vec![0; pixels.len()];
+    }
+    let mut color_table = HashMap::<Rgb, Color>::new();
+    let mut nearest_color = |rgb: [f64; 3]| {
+        let [r, g, b] = rgb.map(|value| value.round().clamp(0.0, 255.0) as u8);
+        *color_table
+            .entry((r, g, b))
+            .or_insert_with(|| nearest_index(to_f64((r, g, b)), &centers) as Color)
+    };
+    let width = width as usize;
+
+    match dither {
+        DITHER_FLOYD_STEINBERG => {
+            let mut errors = vec![[0.0; 3]; pixels.len()];
+            let mut data = Vec::with_capacity(pixels.len());
+            for (i, rgb) in pixels.iter().enumerate() {
+                let mut value = to_f64(*rgb);
+                for channel in 0..3 {
+                    value[channel] = (value[channel] + errors[i][channel]).clamp(0.0, 255.0);
+                }
+                let color = nearest_color(value);
+                data.push(color);
+
+                let x = i % width;
+                let error =
+                    [0, 1, 2].map(|channel| value[channel] - centers[color as usize][channel]);
+                let mut spread = |index: usize, weight: f64| {
+                    if let Some(target) = errors.get_mut(index) {
+                        for channel in 0..3 {
+                            target[channel] += error[channel] * weight;
+                        }
+                    }
+                };
+                if x + 1 < width {
+                    spread(i + 1, 7.0 / 16.0);
+                    spread(i + width + 1, 1.0 / 16.0);
+                }
+                if x > 0 {
+                    spread(i + width - 1, 3.0 / 16.0);
+                }
+                spread(i + width, 5.0 / 16.0);
+            }
+            data
+        }
+        DITHER_ORDERED => {
+            // Offsets scale with the typical distance between palette colors per channel
+            let step = 256.0 / (palette.len().max(1) as f64).cbrt();
+            pixels
+                .iter()
+                .enumerate()
+                .map(|(i, rgb)| {
+                    let (x, y) = (i % width, i / width);
+                    let offset = ((BAYER_MATRIX[y % 4][x % 4] + 0.5) / 16.0 - 0.5) * step;
+                    nearest_color(to_f64(*rgb).map(|value| value + offset))
+                })
+                .collect()
+        }
+        _ => pixels
+            .iter()
+            .map(|rgb| nearest_color(to_f64(*rgb)))
+            .collect(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_generate_palette() {
+        let pixels = [(1, 2, 3), (255, 0, 0), (1, 2, 3)];
+        assert_eq!(generate_palette(&pixels, 4), vec![0x010203, 0xff0000]);
+
+        let mut pixels = vec![(0, 0, 0); 10];
+        pixels.extend([(10, 0, 0), (250, 250, 250), (240, 250, 250)]);
+        let palette = generate_palette(&pixels, 2);
+        assert_eq!(palette.len(), 2);
+        assert!(palette.contains(&0x010000) || palette.contains(&0x000000));
+        assert!(palette.iter().any(|&rgb| rgb >> 16 >= 240));
+    }
+
+    #[test]
+    fn test_quantize() {
+        let palette = [0x000000, 0xffffff];
+        let pixels = vec![(128, 128, 128); 16];
+        let plain = quantize(&pixels, 4, &palette, 0);
+        assert!(plain.iter().all(|&color| color == plain[0]));
+
+        for dither in [DITHER_FLOYD_STEINBERG, DITHER_ORDERED] {
+            let data = quantize(&pixels, 4, &palette, dither);
+            let num_white: u32 = data.iter().map(|&color| u32::from(color)).sum();
+            assert!((6..=10).contains(&num_white), "{dither}: {num_white}");
+        }
+        assert_eq!(
+            quantize(&[(250, 250, 250)], 1, &palette, DITHER_ORDERED),
+            [1]
+        );
+    }
+}
@@ -5,9 +5,11 @@ use std::path::{Path, PathBuf};
 
 use cfg_if::cfg_if;
 use platform_dirs::UserDirs;
+use zip::result::ZipError;
 use zip::write::SimpleFileOptions;
 use zip::{ZipArchive, ZipWriter};
 
+use crate::error::{PyxelError, PyxelResult};
 use crate::image::{Color, Image, Rgb24};
 use crate::pyxel::Pyxel;
 use crate::resource_data::{ResourceData1, ResourceData3};
@@ -43,11 +45,34 @@ impl Pyxel {
         include_channels: Option<bool>,
         include_tones: Option<bool>,
     ) {
-        let mut archive = ZipArchive::new(
-            File::open(Path::new(&filename))
-                .unwrap_or_else(|_| panic!("Failed to open file '{filename}'")),
+        self.try_load(
+            filename,
+            exclude_images,
+            exclude_tilemaps,
+            exclude_sounds,
+            exclude_musics,
+            include_colors,
+            include_channels,
+            include_tones,
         )
-        .unwrap();
+        .unwrap_or_else(|err| panic!("{err}"));
+    }
+
+    pub fn try_load(
+        &mut self,
+        filename: &str,
+        exclude_images: Option<bool>,
+        exclude_tilemaps: Option<bool>,
+        exclude_sounds: Option<bool>,
+        exclude_musics: Option<bool>,
+        include_colors: Option<bool>,
+        include_channels: Option<bool>,
+        include_tones: Option<bool>,
+    ) -> PyxelResult<()> {
+        let file = File::open(Path::new(&filename))
+            .map_err(|err| PyxelError::from_io_error(filename, &err))?;
+        let mut archive =
+            ZipArchive::new(file).map_err(|err| PyxelError::invalid_format(filename, err))?;
 
         // Old resource file
         if archive.by_name("pyxel_resource/version").is_ok() {
@@ -59,51 +84,60 @@ impl Pyxel {
                 !exclude_tilemaps.unwrap_or(false),
                 !exclude_sounds.unwrap_or(false),
                 !exclude_musics.unwrap_or(false),
-            );
-            self.load_pyxel_palette_file(filename);
-            return;
+            )?;
+            self.load_pyxel_palette_file(filename)?;
+            return Ok(());
         }
 
         // New resource file
-        let mut file = archive.by_name(RESOURCE_ARCHIVE_NAME).unwrap();
+        let mut file = archive
+            .by_name(RESOURCE_ARCHIVE_NAME)
+            .map_err(|err| PyxelError::invalid_format(filename, err))?;
         let mut toml_text = String::new();
-        file.read_to_string(&mut toml_text).unwrap();
-        let format_version = Self::parse_format_version(&toml_text);
+        file.read_to_string(&mut toml_text)
+            .map_err(|err| PyxelError::from_io_error(filename, &err))?;
+        let format_version = Self::parse_format_version(&toml_text)
+            .ok_or_else(|| PyxelError::invalid_format(filename, "format version not found"))?;
         if format_version < RESOURCE_FORMAT_VERSION {
             Self::warn_format_version(filename);
         }
-        if format_version == 3 || format_version == 2 {
-            let resource_data = ResourceData3::from_toml(&toml_text);
-            resource_data.to_runtime(
-                self,
-                exclude_images.unwrap_or(false),
-                exclude_tilemaps.unwrap_or(false),
-                exclude_sounds.unwrap_or(false),
-                exclude_musics.unwrap_or(false),
-                include_colors.unwrap_or(false),
-                include_channels.unwrap_or(false),
-                include_tones.unwrap_or(false),
-            );
-            self.load_pyxel_palette_file(filename);
+        if (2..=RESOURCE_FORMAT_VERSION).contains(&format_version) {
+            let resource_data = ResourceData3::from_toml(&toml_text)
+                .map_err(|err| PyxelError::invalid_format(filename, err))?;
+            resource_data
+                .to_runtime(
+                    self,
+                    exclude_images.unwrap_or(false),
+                    exclude_tilemaps.unwrap_or(false),
+                    exclude_sounds.unwrap_or(false),
+                    exclude_musics.unwrap_or(false),
+                    include_colors.unwrap_or(false),
+                    include_channels.unwrap_or(false),
+                    include_tones.unwrap_or(false),
+                )
+                .map_err(|err| PyxelError::invalid_format(filename, err))?;
         } else if format_version == 1 {
-            let resource_data = ResourceData1::from_toml(&toml_text);
-            resource_data.to_runtime(
-                self,
-                exclude_images.unwrap_or(false),
-                exclude_tilemaps.unwrap_or(false),
-                exclude_sounds.unwrap_or(false),
-                exclude_musics.unwrap_or(false),
-                include_colors.unwrap_or(false),
-                include_channels.unwrap_or(false),
-                include_tones.unwrap_or(false),
-            );
-            self.load_pyxel_palette_file(filename);
+            let resource_data = ResourceData1::from_toml(&toml_text)
+                .map_err(|err| PyxelError::invalid_format(filename, err))?;
+            resource_data
+                .to_runtime(
+                    self,
+                    exclude_images.unwrap_or(false),
+                    exclude_tilemaps.unwrap_or(false),
+                    exclude_sounds.unwrap_or(false),
+                    exclude_musics.unwrap_or(false),
+                    include_colors.unwrap_or(false),
+                    include_channels.unwrap_or(false),
+                    include_tones.unwrap_or(false),
+                )
+                .map_err(|err| PyxelError::invalid_format(filename, err))?;
         } else {
-            assert!(
-                format_version <= RESOURCE_FORMAT_VERSION,
-                "Unknown resource file version"
-            );
+            return Err(PyxelError::UnsupportedVersion(
+                filename.to_string(),
+                format_version.to_string(),
+            ));
         }
+        self.load_pyxel_palette_file(filename)
     }
 
     pub fn save(
@@ -117,6 +151,30 @@ impl Pyxel {
         include_channels: Option<bool>,
         include_tones: Option<bool>,
     ) {
+        self.try_save(
+            filename,
+            exclude_images,
+            exclude_tilemaps,
+            exclude_sounds,
+            exclude_musics,
+            include_colors,
+            include_channels,
+            include_tones,
+        )
+        .unwrap_or_else(|err| panic!("{err}"));
+    }
+
+    pub fn try_save(
+        &mut self,
+        filename: &str,
+        exclude_images: Option<bool>,
+        exclude_tilemaps: Option<bool>,
+        exclude_sounds: Option<bool>,
+        exclude_musics: Option<bool>,
+        include_colors: Option<bool>,
+        include_channels: Option<bool>,
+        include_tones: Option<bool>,
+    ) -> PyxelResult<()> {
         let toml_text = ResourceData3::from_runtime(self).to_toml(
             exclude_images.unwrap_or(false),
             exclude_tilemaps.unwrap_or(false),
@@ -127,31 +185,45 @@ impl Pyxel {
             include_tones.unwrap_or(false),
         );
         let path = std::path::Path::new(&filename);
-        let file = std::fs::File::create(path)
-            .unwrap_or_else(|_| panic!("Failed to open file '{filename}'"));
+        let file =
+            std::fs::File::create(path).map_err(|err| PyxelError::from_io_error(filename, &err))?;
+        let zip_error = |err: ZipError| match err {
+            ZipError::Io(err) => PyxelError::from_io_error(filename, &err),
+            err => PyxelError::FileAccess(filename.to_string(), err.to_string()),
+        };
         let mut zip = ZipWriter::new(file);
         zip.start_file(RESOURCE_ARCHIVE_NAME, SimpleFileOptions::default())
-            .unwrap();
-        zip.write_all(toml_text.as_bytes()).unwrap();
-        zip.finish().unwrap();
+            .map_err(zip_error)?;
+        zip.write_all(toml_text.as_bytes())
+            .map_err(|err| PyxelError::from_io_error(filename, &err))?;
+        zip.finish().map_err(zip_error)?;
         #[cfg(target_os = "emscripten")]
         pyxel_platform::emscripten::save_file(filename);
+        Ok(())
     }
 
     pub fn screenshot(&mut self, scale: Option<u32>) {
         let filename = Self::prepend_desktop_path(&format!("pyxel-{}", Self::datetime_string()));
         let scale = max(scale.unwrap_or(self.resource.capture_scale), 1);
-        self.screen.lock().save(&filename, scale);
+        self.screen
+            .lock()
+            .save_with_colors(&filename, scale, &self.display_colors());
         #[cfg(target_os = "emscripten")]
         pyxel_platform::emscripten::save_file(&(filename + ".png"));
     }
 
     pub fn screencast(&mut self, scale: Option<u32>) {
+        self.try_screencast(scale)
+            .unwrap_or_else(|err| panic!("{err}"));
+    }
+
+    pub fn try_screencast(&mut self, scale: Option<u32>) -> PyxelResult<()> {
         let filename = Self::prepend_desktop_path(&format!("pyxel-{}", Self::datetime_string()));
         let scale = max(scale.unwrap_or(self.resource.capture_scale), 1);
-        self.resource.screencast.save(&filename, scale);
+        self.resource.screencast.try_save(&filename, scale)?;
         #[cfg(target_os = "emscripten")]
         pyxel_platform::emscripten::save_file(&(filename + ".gif"));
+        Ok(())
     }
 
     pub fn reset_screencast(&mut self) {
@@ -163,7 +235,7 @@ impl Pyxel {
             self.width,
             self.height,
             &self.screen.lock().canvas.data,
-            &self.colors.lock(),
+            &self.display_colors(),
             self.frame_count,
         );
     }
@@ -171,7 +243,7 @@ impl Pyxel {
     pub(crate) fn dump_image_bank(&self, image_index: u32) {
         let filename = Self::prepend_desktop_path(&format!("pyxel-image{image_index}"));
         if let Some(image) = self.images.lock().get(image_index as usize) {
-            image.lock().save(&filename, 1);
+            image.lock().save(&filename, 1, None, None, None);
             #[cfg(target_os = "emscripten")]
             pyxel_platform::emscripten::save_file(&(filename + ".png"));
         }
@@ -186,7 +258,7 @@ impl Pyxel {
             for i in 0..num_colors {
                 image.pset(i as f64, 0.0, i as Color);
             }
-            image.save(&filename, 16);
+            image.save(&filename, 16, None, None, None);
             #[cfg(target_os = "emscripten")]
             pyxel_platform::emscripten::save_file(&(filename + ".png"));
         }
@@ -211,14 +283,12 @@ impl Pyxel {
         desktop_dir.join(basename).to_str().unwrap().to_string()
     }
 
-    fn parse_format_version(toml_text: &str) -> u32 {
+    fn parse_format_version(toml_text: &str) -> Option<u32> {
         toml_text
             .lines()
             .find(|line| line.trim().starts_with("format_version"))
             .and_then(|line| line.split_once('='))
-            .map(|(_, value)| value.trim().parse::<u32>())
-            .unwrap()
-            .unwrap()
+            .and_then(|(_, value)| value.trim().parse::<u32>().ok())
     }
 
     fn warn_format_version(filename: &str) {
@@ -228,7 +298,7 @@ impl Pyxel {
         );
     }
 
-    fn load_pyxel_palette_file(&mut self, filename: &str) {
+    fn load_pyxel_palette_file(&mut self, filename: &str) -> PyxelResult<()> {
         let filename = filename
             .rfind('.')
             .map_or(filename, |i| &filename[..i])
@@ -236,14 +306,20 @@ impl Pyxel {
             + PALETTE_FILE_EXTENSION;
         if let Ok(mut file) = File::open(Path::new(&filename)) {
             let mut contents = String::new();
-            file.read_to_string(&mut contents).unwrap();
+            file.read_to_string(&mut contents)
+                .map_err(|err| PyxelError::from_io_error(&filename, &err))?;
             *self.colors.lock() = contents
                 .replace("\r\n", "\n")
                 .replace('\r', "\n")
                 .split('\n')
                 .filter(|s| !s.is_empty())
-                .map(|s| u32::from_str_radix(s.trim(), 16).unwrap() as Rgb24)
-                .collect();
+                .map(|s| {
+                    u32::from_str_radix(s.trim(), 16)
+                        .map(|rgb| rgb as Rgb24)
+                        .map_err(|err| PyxelError::invalid_format(&filename, err))
+                })
+                .collect::<PyxelResult<_>>()?;
         }
+        Ok(())
     }
 }
@@ -1,13 +1,14 @@
 use serde::{Deserialize, Serialize};
 
+use crate::animation::{Animation, AnimationMode, AnimationTag, SharedAnimation};
 use crate::channel::{Channel, Detune, Note, Speed, Volume};
 use crate::image::{Color, Image, SharedImage};
 use crate::music::{Music, SharedMusic};
 use crate::oscillator::{Effect, Gain};
 use crate::pyxel::Pyxel;
-use crate::settings::RESOURCE_FORMAT_VERSION;
+use crate::settings::{NUM_ANIMATIONS, RESOURCE_FORMAT_VERSION, TILE_ROTATE_90, TILE_SIZE};
 use crate::sound::{SharedSound, Sound};
-use crate::tilemap::{ImageSource, SharedTilemap, TileCoord, Tilemap};
+use crate::tilemap::{ImageSource, SharedTilemap, TileCoord, TileFlags, Tilemap};
 use crate::tone::{Noise, SharedTone, Tone, Waveform};
 use crate::utils::{compress_vec2, expand_vec2, trim_empty_vecs};
 use crate::{Rgb24, SharedChannel};
@@ -55,6 +56,16 @@ struct TilemapData {
     height: u32,
     imgsrc: u32,
     data: Vec<Vec<TileCoord>>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    flags: Vec<Vec<TileFlags>>,
+    #[serde(default = "default_tile_size")]
+    tile_width: u32,
+    #[serde(default = "default_tile_size")]
+    tile_height: u32,
+}
+
+const fn default_tile_size() -> u32 {
+    TILE_SIZE
 }
 
 impl TilemapData {
@@ -70,30 +81,176 @@ impl TilemapData {
             .canvas
             .data
             .iter()
-            .flat_map(|(tx, ty)| [*tx, *ty].to_vec())
+            .flat_map(|(tx, ty, _)| [*tx, *ty].to_vec())
             .collect();
         let data: Vec<Vec<_>> = data
             .chunks((width * 2) as usize)
             .map(<[TileCoord]>::to_vec)
             .collect();
         let data = compress_vec2(&data);
+        let flags = if tilemap.canvas.data.iter().any(|(_, _, flags)| *flags != 0) {
+            let flags: Vec<_> = tilemap
+                .canvas
+                .data
+                .iter()
+                .map(|(_, _, flags)| *flags)
+                .collect();
+            let flags: Vec<Vec<_>> = flags
+                .chunks(width as usize)
+                .map(<[TileFlags]>::to_vec)
+                .collect();
+            compress_vec2(&flags)
+        } else {
+            Vec::new()
+        };
         Self {
             width,
             height,
             imgsrc,
             data,
+            flags,
+            tile_width: tilemap.tile_width,
+            tile_height: tilemap.tile_height,
         }
     }
 
-    fn to_tilemap(&self) -> SharedTilemap {
+    fn to_tilemap(&self) -> Result<SharedTilemap, String> {
+        if self.tile_width == 0 || self.tile_height == 0 {
+            return Err("tile size is zero".to_string());
+        }
         let data = expand_vec2(&self.data, self.height as usize, (self.width * 2) as usize);
         let tilemap = Tilemap::new(self.width, self.height, ImageSource::Index(self.imgsrc));
         {
             let mut tilemap = tilemap.lock();
+            tilemap.tile_width = self.tile_width;
+            tilemap.tile_height = self.tile_height;
             let data: Vec<_> = data.clone().into_iter().flatten().collect();
-            tilemap.canvas.data = data.chunks(2).map(|chunk| (chunk[0], chunk[1])).collect();
+            tilemap.canvas.data = data
+                .chunks(2)
+                .map(|chunk| (chunk[0], chunk[1], 0))
+                .collect();
+            if !self.flags.is_empty() {
+                let flags = expand_vec2(&self.flags, self.height as usize, self.width as usize);
+                if self.tile_width != self.tile_height
+                    && flags
+                        .iter()
+                        .flatten()
+                        .any(|tile_flags| tile_flags & TILE_ROTATE_90 != 0)
+                {
+                    return Err("rotated tiles require a square tile size".to_string());
+                }
+                for (tile, flags) in tilemap
+                    .canvas
+                    .data
+                    .iter_mut()
+                    .zip(flags.into_iter().flatten())
+                {
+                    tile.2 = flags;
+                }
+            }
+        }
+        Ok(tilemap)
+    }
+}
+
+fn checked_colors_and_tilemaps(
+    colors: &[String],
+    tilemaps: &[TilemapData],
+    include_colors: bool,
+    exclude_tilemaps: bool,
+) -> Result<(Vec<Rgb24>, Vec<SharedTilemap>), String> {
+    // Everything that can fail is converted before any runtime data is replaced,
+    // so that a broken file loads nothing
+    let colors = if include_colors {
+        colors
+            .iter()
+            .map(|hex| {
+                u32::from_str_radix(hex, 16)
+                    .map(|rgb| rgb as Rgb24)
+                    .map_err(|_| format!("invalid color '{hex}'"))
+            })
+            .collect::<Result<_, _>>()?
+    } else {
+        Vec::new()
+    };
+    let tilemaps = if exclude_tilemaps {
+        Vec::new()
+    } else {
+        tilemaps
+            .iter()
+            .map(TilemapData::to_tilemap)
+            .collect::<Result<_, _>>()?
+    };
+    Ok((colors, tilemaps))
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+struct AnimationTagData {
+    name: String,
+    start: u32,
+    end: u32,
+    mode: AnimationMode,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+struct AnimationData {
+    imgsrc: u32,
+    mode: AnimationMode,
+    frames: Vec<[u32; 5]>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    tags: Vec<AnimationTagData>,
+}
+
+impl AnimationData {
+    fn from_animation(animation: SharedAnimation) -> Self {
+        let animation = animation.lock();
+        let imgsrc = match animation.imgsrc {
+            ImageSource::Index(value) => value,
+            ImageSource::Image(_) => 0,
+        };
+        let frames = animation
+            .frames
+            .iter()
+            .map(|frame| [frame.u, frame.v, frame.width, frame.height, frame.duration])
+            .collect();
+        let tags = animation
+            .tags
+            .iter()
+            .map(|tag| AnimationTagData {
+                name: tag.name.clone(),
+                start: tag.start,
+                end: tag.end,
+                mode: tag.mode,
+            })
+            .collect();
+        Self {
+            imgsrc,
+            mode: animation.mode,
+            frames,
+            tags,
         }
-        tilemap
+    }
+
+    fn to_animation(&self) -> SharedAnimation {
+        let animation = Animation::new(ImageSource::Index(self.imgsrc));
+        {
+            let mut animation = animation.lock();
+            animation.mode = self.mode;
+            for &[u, v, width, height, duration] in &self.frames {
+                animation.add_frame(u, v, width, height, duration);
+            }
+            animation.tags = self
+                .tags
+                .iter()
+                .map(|tag| AnimationTag {
+                    name: tag.name.clone(),
+                    start: tag.start,
+                    end: tag.end,
+                    mode: tag.mode,
+                })
+                .collect();
+        }
+        animation
     }
 }
 
@@ -224,6 +381,8 @@ pub struct ResourceData3 {
     #[serde(default, skip_serializing_if = "Vec::is_empty")]
     tilemaps: Vec<TilemapData>,
     #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    animations: Vec<AnimationData>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
     channels: Vec<ChannelData>,
     #[serde(default, skip_serializing_if = "Vec::is_empty")]
     tones: Vec<ToneData>,
@@ -234,8 +393,8 @@ pub struct ResourceData3 {
 }
 
 impl ResourceData3 {
-    pub fn from_toml(toml_text: &str) -> Self {
-        toml::from_str(toml_text).unwrap()
+    pub fn from_toml(toml_text: &str) -> Result<Self, toml::de::Error> {
+        toml::from_str(toml_text)
     }
 
     pub fn from_runtime(pyxel: &Pyxel) -> Self {
@@ -244,6 +403,7 @@ impl ResourceData3 {
             colors: Vec::new(),
             images: Vec::new(),
             tilemaps: Vec::new(),
+            animations: Vec::new(),
             channels: Vec::new(),
             tones: Vec::new(),
             sounds: Vec::new(),
@@ -265,6 +425,20 @@ impl ResourceData3 {
                 .tilemaps
                 .push(TilemapData::from_tilemap(tilemap.clone()));
         }
+        for animation in &*pyxel.animations.lock() {
+            resource_data
+                .animations
+                .push(AnimationData::from_animation(animation.clone()));
+        }
+
+        // Unused animation slots are restored on load, so they are not written out
+        let num_animations = resource_data
+            .animations
+            .iter()
+            .rposition(|animation_data| !animation_data.frames.is_empty())
+            .map_or(0, |index| index + 1);
+        resource_data.animations.truncate(num_animations);
+
         for channel in &*pyxel.channels.lock() {
             resource_data
                 .channels
@@ -296,13 +470,15 @@ impl ResourceData3 {
         include_colors: bool,
         include_channels: bool,
         include_tones: bool,
-    ) {
-        if include_colors && !self.colors.is_empty() {
-            *pyxel.colors.lock() = self
-                .colors
-                .iter()
-                .map(|hex| u32::from_str_radix(hex, 16).unwrap() as Rgb24)
-                .collect();
+    ) -> Result<(), String> {
+        let (colors, tilemaps) = checked_colors_and_tilemaps(
+            &self.colors,
+            &self.tilemaps,
+            include_colors,
+            exclude_tilemaps,
+        )?;
+        if !colors.is_empty() {
+            *pyxel.colors.lock() = colors;
         }
         if !exclude_images && !self.images.is_empty() {
             let mut images = Vec::new();
@@ -311,13 +487,19 @@ impl ResourceData3 {
             }
             *pyxel.images.lock() = images;
         }
-        if !exclude_tilemaps && !self.tilemaps.is_empty() {
-            let mut tilemaps = Vec::new();
-            for tilemap_data in &self.tilemaps {
-                tilemaps.push(tilemap_data.to_tilemap());
-            }
+        if !tilemaps.is_empty() {
             *pyxel.tilemaps.lock() = tilemaps;
         }
+        if !exclude_images && !self.animations.is_empty() {
+            let mut animations = Vec::new();
+            for animation_data in &self.animations {
+                animations.push(animation_data.to_animation());
+            }
+            while animations.len() < NUM_ANIMATIONS as usize {
+                animations.push(Animation::new(ImageSource::Index(0)));
+            }
+            *pyxel.animations.lock() = animations;
+        }
         if include_channels && !self.channels.is_empty() {
             let mut channels = Vec::new();
             for channel_data in &self.channels {
@@ -346,6 +528,7 @@ impl ResourceData3 {
             }
             *pyxel.tones.lock() = tones;
         }
+        Ok(())
     }
 
     pub fn to_toml(
@@ -364,6 +547,7 @@ impl ResourceData3 {
         }
         if exclude_images {
             resource_data.images.clear();
+            resource_data.animations.clear();
         }
         if exclude_tilemaps {
             resource_data.tilemaps.clear();
@@ -397,8 +581,8 @@ pub struct ResourceData1 {
 }
 
 impl ResourceData1 {
-    pub fn from_toml(toml_text: &str) -> Self {
-        toml::from_str(toml_text).unwrap()
+    pub fn from_toml(toml_text: &str) -> Result<Self, toml::de::Error> {
+        toml::from_str(toml_text)
     }
 
     pub fn to_runtime(
@@ -411,13 +595,15 @@ impl ResourceData1 {
         include_colors: bool,
         include_channels: bool,
         include_tones: bool,
-    ) {
-        if include_colors && !self.colors.is_empty() {
-            *pyxel.colors.lock() = self
-                .colors
-                .iter()
-                .map(|hex| u32::from_str_radix(hex, 16).unwrap() as Rgb24)
-                .collect();
+    ) -> Result<(), String> {
+        let (colors, tilemaps) = checked_colors_and_tilemaps(
+            &self.colors,
+            &self.tilemaps,
+            include_colors,
+            exclude_tilemaps,
+        )?;
+        if !colors.is_empty() {
+            *pyxel.colors.lock() = colors;
         }
         if !exclude_images && !self.images.is_empty() {
             let mut images = Vec::new();
@@ -426,11 +612,7 @@ impl ResourceData1 {
             }
             *pyxel.images.lock() = images;
         }
-        if !exclude_tilemaps && !self.tilemaps.is_empty() {
-            let mut tilemaps = Vec::new();
-            for tilemap_data in &self.tilemaps {
-                tilemaps.push(tilemap_data.to_tilemap());
-            }
+        if !tilemaps.is_empty() {
             *pyxel.tilemaps.lock() = tilemaps;
         }
         if include_channels && !self.channels.is_empty() {
@@ -461,5 +643,53 @@ impl ResourceData1 {
             }
             *pyxel.tones.lock() = tones;
         }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_checked_colors_and_tilemaps() {
+        let colors = ["000000".to_string(), "ff8800".to_string()];
+        let tilemap = |tile_width| TilemapData {
+            width: 1,
+            height: 1,
+            imgsrc: 0,
+            data: vec![vec![0, 0]],
+            flags: Vec::new(),
+            tile_width,
+            tile_height: 8,
+        };
+
+        let (checked_colors, tilemaps) =
+            checked_colors_and_tilemaps(&colors, &[tilemap(8)], true, false).unwrap();
+        assert_eq!(checked_colors, [0x000000, 0xff8800]);
+        assert_eq!(tilemaps.len(), 1);
+
+        let broken_colors = ["00000g".to_string()];
+        assert!(checked_colors_and_tilemaps(&broken_colors, &[], true, false).is_err());
+        assert!(checked_colors_and_tilemaps(&broken_colors, &[], false, false).is_ok());
+        assert!(checked_colors_and_tilemaps(&colors, &[tilemap(0)], true, false).is_err());
+        assert!(checked_colors_and_tilemaps(&colors, &[tilemap(0)], true, true).is_ok());
+    }
+
+    #[test]
+    fn test_rotated_tilemap_data() {
+        let tilemap = |tile_width| TilemapData {
+            width: 1,
+            height: 1,
+            imgsrc: 0,
+            data: vec![vec![0, 0]],
+            flags: vec![vec![TILE_ROTATE_90]],
+            tile_width,
+            tile_height: 8,
+        };
+
+        let rotated = tilemap(8).to_tilemap().unwrap();
+        assert_eq!(rotated.lock().canvas.data[0], (0, 0, TILE_ROTATE_90));
+        assert!(tilemap(16).to_tilemap().is_err());
     }
 }
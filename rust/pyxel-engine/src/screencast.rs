@@ -2,9 +2,10 @@ use std::borrow::Cow;
 use std::cmp::{max, min};
 use std::fs::File;
 
-use gif::{DisposalMethod, Encoder, Frame, Repeat};
+use gif::{DisposalMethod, Encoder, EncodingError, Frame, Repeat};
 use indexmap::IndexMap;
 
+use crate::error::{PyxelError, PyxelResult};
 use crate::image::{Color, Rgb24};
 use crate::rect_area::RectArea;
 use crate::utils::add_file_extension;
@@ -93,13 +94,17 @@ impl Screencast {
         self.num_captured_screens += 1;
     }
 
-    pub fn save(&mut self, filename: &str, scale: u32) {
+    pub fn try_save(&mut self, filename: &str, scale: u32) -> PyxelResult<()> {
         if self.num_captured_screens == 0 {
-            return;
+            return Ok(());
         }
         let filename = add_file_extension(filename, ".gif");
+        let encoding_error = |err: EncodingError| match err {
+            EncodingError::Io(err) => PyxelError::from_io_error(&filename, &err),
+            EncodingError::Format(err) => PyxelError::FileAccess(filename.clone(), err.to_string()),
+        };
         let mut file =
-            File::create(&filename).unwrap_or_else(|_| panic!("Unable to open file '{filename}'"));
+            File::create(&filename).map_err(|err| PyxelError::from_io_error(&filename, &err))?;
         let screen = self.screen(0);
         let mut encoder = Encoder::new(
             &mut file,
@@ -107,8 +112,10 @@ impl Screencast {
             (screen.height * scale) as u16,
             &[],
         )
-        .unwrap();
-        encoder.set_repeat(Repeat::Infinite).unwrap();
+        .map_err(encoding_error)?;
+        encoder
+            .set_repeat(Repeat::Infinite)
+            .map_err(encoding_error)?;
 
         // Write first frame
         let mut base_image = screen.to_rgb_image();
@@ -131,7 +138,7 @@ impl Screencast {
                 palette: Some(palette),
                 buffer: Cow::Borrowed(&buffer),
             })
-            .unwrap();
+            .map_err(encoding_error)?;
 
         // Write subsequent frames
         for i in 1..self.num_captured_screens {
@@ -153,9 +160,10 @@ impl Screencast {
                     palette: Some(palette),
                     buffer: Cow::Borrowed(&buffer),
                 })
-                .unwrap();
+                .map_err(encoding_error)?;
         }
         self.reset();
+        Ok(())
     }
 
     fn screen(&self, index: u32) -> &Screen {
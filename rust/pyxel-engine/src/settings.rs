@@ -1,7 +1,12 @@
+use crate::animation::AnimationMode;
 use crate::channel::{Note, Speed, Volume};
 use crate::image::{Color, Rgb24};
 use crate::keys::{Key, KEY_ESCAPE};
 use crate::oscillator::{Effect, Gain};
+use crate::quantize::DitherMode;
+use crate::system::ScaleMode;
+use crate::text::TextAlign;
+use crate::tilemap::TileFlags;
 use crate::tone::{Noise, Waveform};
 
 // System
@@ -44,8 +49,9 @@ pub const APP_FILE_EXTENSION: &str = ".pyxapp";
 pub const APP_STARTUP_SCRIPT_FILE: &str = ".pyxapp_startup_script";
 pub const RESOURCE_FILE_EXTENSION: &str = ".pyxres";
 pub const RESOURCE_ARCHIVE_NAME: &str = "pyxel_resource.toml";
-pub const RESOURCE_FORMAT_VERSION: u32 = 3;
+pub const RESOURCE_FORMAT_VERSION: u32 = 4;
 pub const PALETTE_FILE_EXTENSION: &str = ".pyxpal";
+pub const INPUT_RECORD_FORMAT_VERSION: u32 = 1;
 
 // Graphics
 pub const NUM_COLORS: u32 = 16;
@@ -55,6 +61,13 @@ pub const IMAGE_SIZE: u32 = 256;
 pub const NUM_TILEMAPS: u32 = 8;
 pub const TILEMAP_SIZE: u32 = 256;
 pub const TILE_SIZE: u32 = 8;
+pub const TILE_FLIP_H: TileFlags = 0x1;
+pub const TILE_FLIP_V: TileFlags = 0x2;
+pub const TILE_ROTATE_90: TileFlags = 0x4; // Ignored unless tiles are square
+pub const NUM_ANIMATIONS: u32 = 64;
+pub const ANIM_LOOP: AnimationMode = 0;
+pub const ANIM_PINGPONG: AnimationMode = 1;
+pub const ANIM_ONCE: AnimationMode = 2;
 pub const DEFAULT_COLORS: [Rgb24; NUM_COLORS as usize] = [
     0x000000, 0x2b335f, 0x7e2072, 0x19959c, 0x8b4852, 0x395c98, 0xa9c1ff, 0xeeeeee, //
     0xd4186c, 0xd38441, 0xe9c35b, 0x70c6a9, 0x7696de, 0xa3a3a3, 0xFF9798, 0xedc7b0,
@@ -98,7 +111,17 @@ pub const FONT_DATA: [u32; MAX_FONT_CODE as usize - MIN_FONT_CODE as usize + 1]
     0x06aa62, 0x068880, 0x06c6c0, 0x4e4460, 0x0aaa60, 0x0aaa40, 0x0aaee0, 0x0a44a0, 0x0aa624,
     0x0e24e0, 0x64c460, 0x444440, 0xc464c0, 0x6c0000, 0xeeeee0,
 ];
+pub const TEXT_ALIGN_LEFT: TextAlign = 0;
+pub const TEXT_ALIGN_CENTER: TextAlign = 1;
+pub const TEXT_ALIGN_RIGHT: TextAlign = 2;
 pub const NUM_SCREEN_TYPES: u32 = 3;
+pub const SCALE_INTEGER: ScaleMode = 0;
+pub const SCALE_FIT: ScaleMode = 1;
+pub const SCALE_STRETCH: ScaleMode = 2;
+pub const SCALE_FIXED: ScaleMode = 3;
+pub const DITHER_NONE: DitherMode = 0;
+pub const DITHER_FLOYD_STEINBERG: DitherMode = 1;
+pub const DITHER_ORDERED: DitherMode = 2;
 
 // Audio
 pub const CLOCK_RATE: u32 = 120_000_000; // 120MHz clock rate
@@ -1,10 +1,15 @@
+uniform vec2 u_windowSize;
 uniform vec2 u_screenPos;
 uniform vec2 u_screenSize;
 uniform float u_screenScale;
+uniform vec2 u_screenTextureSize;
 uniform int u_numColors;
 uniform vec3 u_backgroundColor;
 uniform sampler2D u_screenTexture;
 uniform sampler2D u_colorsTexture;
+uniform sampler2D u_borderTexture;
+uniform vec2 u_borderTextureSize;
+uniform float u_time;
 
 void getScreenParams(out vec2 screenFragCoord, out vec2 screenTexCoord) {
     screenFragCoord = gl_FragCoord.xy - u_screenPos;
@@ -21,3 +26,15 @@ vec3 getScreenColor(vec2 screenTexCoord) {
     vec2 colorsTexCoord = vec2((indexColor + 0.5) / float(u_numColors), 0.5);
     return texture2D(u_colorsTexture, colorsTexCoord).rgb;
 }
+
+vec3 getBorderColor() {
+    if (u_borderTextureSize.x <= 0.0 || u_borderTextureSize.y <= 0.0) {
+        return u_backgroundColor;
+    }
+    vec2 borderScale = u_windowSize / u_borderTextureSize;
+    vec2 borderTexCoord = (gl_FragCoord.xy - u_windowSize * 0.5) / (u_borderTextureSize * max(borderScale.x, borderScale.y)) + 0.5;
+    borderTexCoord.y = 1.0 - borderTexCoord.y;
+    float indexColor = texture2D(u_borderTexture, borderTexCoord).r * 255.0;
+    vec2 colorsTexCoord = vec2((indexColor + 0.5) / float(u_numColors), 0.5);
+    return texture2D(u_colorsTexture, colorsTexCoord).rgb;
+}
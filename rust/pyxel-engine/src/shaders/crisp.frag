@@ -4,6 +4,6 @@ void main() {
     if (isInScreen(screenTexCoord)) {
         gl_FragColor = vec4(getScreenColor(screenTexCoord), 1.0);
     } else {
-        gl_FragColor = vec4(u_backgroundColor, 1.0);
+        gl_FragColor = vec4(getBorderColor(), 1.0);
     }
 }
@@ -45,6 +45,6 @@ void main() {
         color *= getScanlineFactor(screenFragCoord, screenTexCoord);
         gl_FragColor = vec4(color, 1.0);
     } else {
-        gl_FragColor = vec4(u_backgroundColor, 1.0);
+        gl_FragColor = vec4(getBorderColor(), 1.0);
     }
 }
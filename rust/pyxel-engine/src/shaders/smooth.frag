@@ -26,7 +26,7 @@
 // Modified for Pyxel
 #define FragColor gl_FragColor
 #define OutputSize u_screenSize
-#define TextureSize (u_screenSize / u_screenScale)
+#define TextureSize u_screenTextureSize
 #define vTexCoord screenTexCoord
 
 #define SourceSize vec4(TextureSize, 1.0 / TextureSize) //either TextureSize or InputSize
@@ -79,7 +79,7 @@ void main() {
     vec2 screenFragCoord, screenTexCoord;
     getScreenParams(screenFragCoord, screenTexCoord);
     if (!isInScreen(screenTexCoord)) {
-        FragColor = vec4(u_backgroundColor, 1.0);
+        FragColor = vec4(getBorderColor(), 1.0);
         return;
     }
 
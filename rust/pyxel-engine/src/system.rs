@@ -1,4 +1,4 @@
-use std::cmp::{max, min};
+use std::cmp::max;
 
 use cfg_if::cfg_if;
 use pyxel_platform::Event;
@@ -7,17 +7,22 @@ use crate::image::{Color, Image, SharedImage};
 use crate::keys::{Key, KEY_0, KEY_1, KEY_2, KEY_3, KEY_9, KEY_ALT, KEY_RETURN, KEY_SHIFT};
 use crate::profiler::Profiler;
 use crate::pyxel::Pyxel;
-use crate::settings::{MAX_ELAPSED_MS, NUM_MEASURE_FRAMES, NUM_SCREEN_TYPES};
+use crate::settings::{
+    MAX_ELAPSED_MS, NUM_MEASURE_FRAMES, NUM_SCREEN_TYPES, SCALE_FIT, SCALE_FIXED, SCALE_INTEGER,
+    SCALE_STRETCH,
+};
 use crate::utils;
 use crate::watch_info::WatchInfo;
 
+pub type ScaleMode = u32;
+
 pub trait PyxelCallback {
     fn update(&mut self, pyxel: &mut Pyxel);
     fn draw(&mut self, pyxel: &mut Pyxel);
 }
 
 pub struct System {
-    one_frame_ms: f64,
+    pub(crate) one_frame_ms: f64,
     next_update_ms: f64,
     quit_key: Key,
     paused: bool,
@@ -28,8 +33,13 @@ pub struct System {
     watch_info: WatchInfo,
     pub screen_x: i32,
     pub screen_y: i32,
-    pub screen_scale: u32,
+    pub screen_width: u32,
+    pub screen_height: u32,
+    pub screen_scale: f64,
+    pub scale_mode: ScaleMode,
+    pub fixed_scale: f64,
     pub screen_mode: u32,
+    pub num_screen_modes: u32,
 }
 
 impl System {
@@ -46,8 +56,13 @@ impl System {
             watch_info: WatchInfo::new(),
             screen_x: 0,
             screen_y: 0,
-            screen_scale: 0,
+            screen_width: 0,
+            screen_height: 0,
+            screen_scale: 0.0,
+            scale_mode: SCALE_INTEGER,
+            fixed_scale: 1.0,
             screen_mode: 0,
+            num_screen_modes: NUM_SCREEN_TYPES,
         }
     }
 }
@@ -59,6 +74,17 @@ impl Pyxel {
         });
     }
 
+    /// Runs exactly `n_frames` update and draw cycles on a simulated clock.
+    ///
+    /// Only headless builds provide this, since their clock and audio advance with each
+    /// stepped frame instead of with wall-clock time.
+    #[cfg(feature = "headless")]
+    pub fn step(&mut self, callback: &mut dyn PyxelCallback, n_frames: u32) {
+        for _ in 0..n_frames {
+            self.process_frame_for_step(callback);
+        }
+    }
+
     pub fn show(&mut self) {
         struct App {
             image: SharedImage,
@@ -76,6 +102,8 @@ impl Pyxel {
                     pyxel.width as f64,
                     pyxel.height as f64,
                     None,
+                    None,
+                    None,
                 );
             }
         }
@@ -90,6 +118,8 @@ impl Pyxel {
             self.width as f64,
             self.height as f64,
             None,
+            None,
+            None,
         );
         self.run(App { image });
     }
@@ -157,9 +187,27 @@ impl Pyxel {
         self.system.screen_mode = screen_mode;
     }
 
+    pub fn resize(&mut self, width: u32, height: u32) {
+        assert!(width > 0 && height > 0, "Screen size must be positive");
+        self.width = width;
+        self.height = height;
+        self.screen.lock().resize(width, height);
+        self.reset_screencast();
+        self.update_screen_params();
+    }
+
+    pub fn scale_mode(&mut self, scale_mode: ScaleMode, fixed_scale: Option<f64>) {
+        self.system.scale_mode = scale_mode;
+        if let Some(fixed_scale) = fixed_scale {
+            assert!(fixed_scale > 0.0, "Fixed scale must be positive");
+            self.system.fixed_scale = fixed_scale;
+        }
+    }
+
     fn process_events(&mut self) {
         self.reset_input_states();
         let events = pyxel_platform::poll_events();
+        let events = self.input_record.process_events(self.frame_count, events);
         for event in events {
             match event {
                 Event::WindowShown => {
@@ -215,7 +263,8 @@ impl Pyxel {
             } else if self.btnp(KEY_3, None, None) {
                 self.screencast(None);
             } else if self.btnp(KEY_9, None, None) {
-                self.system.screen_mode = (self.system.screen_mode + 1) % NUM_SCREEN_TYPES;
+                self.system.screen_mode =
+                    (self.system.screen_mode + 1) % self.system.num_screen_modes;
             } else if self.btnp(KEY_RETURN, None, None) {
                 self.fullscreen(!pyxel_platform::is_fullscreen());
             }
@@ -224,14 +273,22 @@ impl Pyxel {
 
     fn update_screen_params(&mut self) {
         let (window_width, window_height) = pyxel_platform::window_size();
-        self.system.screen_scale = max(
-            min(window_width / self.width, window_height / self.height),
-            1,
-        );
-        self.system.screen_x =
-            (window_width as i32 - (self.width * self.system.screen_scale) as i32) / 2;
-        self.system.screen_y =
-            (window_height as i32 - (self.height * self.system.screen_scale) as i32) / 2;
+        let scale_x = window_width as f64 / self.width as f64;
+        let scale_y = window_height as f64 / self.height as f64;
+        let (scale_x, scale_y) = match self.system.scale_mode {
+            SCALE_FIT => (scale_x.min(scale_y), scale_x.min(scale_y)),
+            SCALE_STRETCH => (scale_x, scale_y),
+            SCALE_FIXED => (self.system.fixed_scale, self.system.fixed_scale),
+            _ => {
+                let scale = scale_x.min(scale_y).floor().max(1.0);
+                (scale, scale)
+            }
+        };
+        self.system.screen_width = max((self.width as f64 * scale_x).round() as u32, 1);
+        self.system.screen_height = max((self.height as f64 * scale_y).round() as u32, 1);
+        self.system.screen_scale = scale_x.min(scale_y);
+        self.system.screen_x = (window_width as i32 - self.system.screen_width as i32) / 2;
+        self.system.screen_y = (window_height as i32 - self.system.screen_height as i32) / 2;
     }
 
     fn update_frame(&mut self, callback: Option<&mut dyn PyxelCallback>) {
@@ -262,23 +319,26 @@ impl Pyxel {
         let palette1 = screen.palette[1];
         let palette2 = screen.palette[2];
         let alpha = screen.canvas.alpha;
+        let fill_pattern = screen.canvas.fill_pattern;
+        let blend = screen.canvas.blend.take();
         screen.clip0();
         screen.camera0();
         screen.pal(1, 1);
         screen.pal(2, 9);
         screen.dither(1.0);
+        screen.fillp0();
 
         let fps = format!("{:.*}", 2, self.system.fps_profiler.average_fps());
-        screen.text(1.0, 0.0, &fps, 1);
-        screen.text(0.0, 0.0, &fps, 2);
+        screen.text(1.0, 0.0, &fps, 1, None);
+        screen.text(0.0, 0.0, &fps, 2, None);
 
         let update_time = format!("{:.*}", 2, self.system.update_profiler.average_time());
-        screen.text(1.0, 6.0, &update_time, 1);
-        screen.text(0.0, 6.0, &update_time, 2);
+        screen.text(1.0, 6.0, &update_time, 1, None);
+        screen.text(0.0, 6.0, &update_time, 2, None);
 
         let draw_time = format!("{:.*}", 2, self.system.draw_profiler.average_time());
-        screen.text(1.0, 12.0, &draw_time, 1);
-        screen.text(0.0, 12.0, &draw_time, 2);
+        screen.text(1.0, 12.0, &draw_time, 1, None);
+        screen.text(0.0, 12.0, &draw_time, 2, None);
 
         screen.canvas.clip_rect = clip_rect;
         screen.canvas.camera_x = camera_x;
@@ -286,6 +346,10 @@ impl Pyxel {
         screen.pal(1, palette1);
         screen.pal(2, palette2);
         screen.dither(alpha);
+        if let Some((pattern, secondary_color)) = fill_pattern {
+            screen.canvas.fillp(pattern, secondary_color);
+        }
+        screen.canvas.blend = blend;
     }
 
     fn draw_cursor(&self) {
@@ -307,6 +371,7 @@ impl Pyxel {
         let camera_x = screen.canvas.camera_x;
         let camera_y = screen.canvas.camera_y;
         let palette = screen.palette;
+        let blend = screen.canvas.blend.take();
         screen.clip0();
         screen.camera0();
         screen.blt(
@@ -318,11 +383,14 @@ impl Pyxel {
             width as f64,
             height as f64,
             Some(0),
+            None,
+            None,
         );
         screen.canvas.clip_rect = clip_rect;
         screen.canvas.camera_x = camera_x;
         screen.canvas.camera_y = camera_y;
         screen.palette = palette;
+        screen.canvas.blend = blend;
     }
 
     fn draw_frame(&mut self, callback: Option<&mut dyn PyxelCallback>) {
@@ -338,6 +406,7 @@ impl Pyxel {
         self.system.watch_info.update();
         self.draw_perf_monitor();
         self.draw_cursor();
+        #[cfg(not(feature = "headless"))]
         self.render_screen();
         self.capture_screen();
         self.system
@@ -376,6 +445,21 @@ impl Pyxel {
         self.frame_count += 1;
     }
 
+    #[cfg(feature = "headless")]
+    fn process_frame_for_step(&mut self, callback: &mut dyn PyxelCallback) {
+        self.update_screen_params();
+        self.update_frame(Some(callback));
+        self.draw_frame(Some(callback));
+        self.frame_count += 1;
+        self.system.next_update_ms += self.system.one_frame_ms;
+
+        // Advance the virtual clock so that audio is rendered for exactly one frame
+        let wait_ms = self.system.next_update_ms - pyxel_platform::elapsed_time() as f64;
+        if wait_ms > 0.0 {
+            pyxel_platform::sleep(wait_ms as u32);
+        }
+    }
+
     #[cfg(not(target_os = "emscripten"))]
     fn process_frame_for_flip(&mut self) {
         self.system
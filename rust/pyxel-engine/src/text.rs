@@ -0,0 +1,220 @@
+use std::mem;
+
+use crate::font::Font;
+use crate::image::{Color, Image};
+use crate::pyxel::FONT_IMAGE;
+use crate::settings::{
+    FONT_HEIGHT, FONT_WIDTH, MAX_FONT_CODE, MIN_FONT_CODE, NUM_FONT_ROWS, TEXT_ALIGN_CENTER,
+    TEXT_ALIGN_RIGHT,
+};
+use crate::utils;
+
+pub type TextAlign = u8;
+
+const COLOR_CODE: char = '\x0c';
+const RESET_CODE: char = 'r';
+
+enum TextToken {
+    Char(char),
+    Color(Option<Color>),
+}
+
+fn tokenize(string: &str) -> Vec<(&str, TextToken)> {
+    let mut tokens = Vec::new();
+    let mut chars = string.char_indices().peekable();
+    while let Some((start, c)) = chars.next() {
+        if c != COLOR_CODE {
+            tokens.push((&string[start..start + c.len_utf8()], TextToken::Char(c)));
+            continue;
+        }
+        let color = match chars.peek() {
+            Some(&(_, RESET_CODE)) => None,
+            Some(&(_, code)) if code.is_ascii_hexdigit() => code.to_digit(16).map(|c| c as Color),
+            _ => continue,
+        };
+        let (end, code) = chars.next().unwrap();
+        tokens.push((
+            &string[start..end + code.len_utf8()],
+            TextToken::Color(color),
+        ));
+    }
+    tokens
+}
+
+fn char_width(c: char, font: Option<&Font>) -> i32 {
+    match font {
+        Some(font) => font.char_width(c),
+        None if (MIN_FONT_CODE..=MAX_FONT_CODE).contains(&c) => FONT_WIDTH as i32,
+        None => 0,
+    }
+}
+
+fn line_height(font: Option<&Font>) -> i32 {
+    font.map_or(FONT_HEIGHT as i32, Font::line_height)
+}
+
+fn line_width(line: &str, font: Option<&Font>) -> i32 {
+    tokenize(line)
+        .iter()
+        .map(|(_, token)| match token {
+            TextToken::Char(c) => char_width(*c, font),
+            TextToken::Color(_) => 0,
+        })
+        .sum()
+}
+
+pub fn text_size(string: &str, font: Option<&Font>) -> (i32, i32) {
+    let lines: Vec<&str> = string.split('\n').collect();
+    let width = lines
+        .iter()
+        .map(|line| line_width(line, font))
+        .max()
+        .unwrap_or(0);
+    (width, lines.len() as i32 * line_height(font))
+}
+
+pub fn wrap_text(string: &str, width: i32, font: Option<&Font>) -> String {
+    let space_width = char_width(' ', font);
+    let mut lines = Vec::new();
+    for paragraph in string.split('\n') {
+        let mut line = String::new();
+        let mut current_width = 0;
+        for (i, word) in paragraph.split(' ').enumerate() {
+            let word_width = line_width(word, font);
+            if i > 0 {
+                if current_width + space_width + word_width <= width {
+                    line.push(' ');
+                    current_width += space_width;
+                } else {
+                    lines.push(mem::take(&mut line));
+                    current_width = 0;
+                }
+            }
+            if current_width + word_width <= width {
+                line.push_str(word);
+                current_width += word_width;
+                continue;
+            }
+
+            // Break words longer than the line width at character boundaries
+            for (text, token) in tokenize(word) {
+                let token_width = match token {
+                    TextToken::Char(c) => char_width(c, font),
+                    TextToken::Color(_) => 0,
+                };
+                if current_width > 0 && current_width + token_width > width {
+                    lines.push(mem::take(&mut line));
+                    current_width = 0;
+                }
+                line.push_str(text);
+                current_width += token_width;
+            }
+        }
+        lines.push(line);
+    }
+    lines.join("\n")
+}
+
+pub(crate) fn draw_text(
+    image: &mut Image,
+    x: f64,
+    y: f64,
+    string: &str,
+    color: Color,
+    font: Option<&Font>,
+    align: TextAlign,
+    box_width: i32,
+) {
+    let start_x = utils::f64_to_i32(x); // No need to reflect camera_x
+    let mut y = utils::f64_to_i32(y); // No need to reflect camera_y
+    let palette = image.palette;
+    let mut current_color = color;
+    for line in string.split('\n') {
+        let mut x = match align {
+            TEXT_ALIGN_CENTER => start_x + (box_width - line_width(line, font)) / 2,
+            TEXT_ALIGN_RIGHT => start_x + box_width - line_width(line, font),
+            _ => start_x,
+        };
+        for (_, token) in tokenize(line) {
+            match token {
+                TextToken::Char(c) => {
+                    match font {
+                        Some(font) => font.draw_char(image, x, y, c, current_color),
+                        None => draw_builtin_char(image, x, y, c, palette[current_color as usize]),
+                    }
+                    x += char_width(c, font);
+                }
+                TextToken::Color(new_color) => current_color = new_color.unwrap_or(color),
+            }
+        }
+        y += line_height(font);
+    }
+    image.palette = palette;
+}
+
+fn draw_builtin_char(image: &mut Image, x: i32, y: i32, c: char, color: Color) {
+    if !(MIN_FONT_CODE..=MAX_FONT_CODE).contains(&c) {
+        return;
+    }
+    let code = c as i32 - MIN_FONT_CODE as i32;
+    let src_x = (code % NUM_FONT_ROWS as i32) * FONT_WIDTH as i32;
+    let src_y = (code / NUM_FONT_ROWS as i32) * FONT_HEIGHT as i32;
+    image.palette[1] = color;
+    image.blt(
+        x as f64,
+        y as f64,
+        FONT_IMAGE.clone(),
+        src_x as f64,
+        src_y as f64,
+        FONT_WIDTH as f64,
+        FONT_HEIGHT as f64,
+        Some(0),
+        None,
+        None,
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_text_size() {
+        assert_eq!(text_size("", None), (0, 6));
+        assert_eq!(text_size("abc\nde", None), (12, 12));
+        assert_eq!(text_size("\x0c8ab\x0crc\x0c", None), (12, 6));
+        assert_eq!(text_size("\x0cz", None), (4, 6));
+    }
+
+    #[test]
+    fn test_wrap_text() {
+        assert_eq!(wrap_text("aa bb cc", 20, None), "aa bb\ncc");
+        assert_eq!(wrap_text("aa bb\ncc dd", 8, None), "aa\nbb\ncc\ndd");
+        assert_eq!(wrap_text("abcdefg hi", 12, None), "abc\ndef\ng\nhi");
+        assert_eq!(wrap_text("\x0c8aa \x0c9bb", 12, None), "\x0c8aa\n\x0c9bb");
+        assert_eq!(wrap_text("a  b", 100, None), "a  b");
+    }
+
+    #[test]
+    fn test_draw_text() {
+        let image = Image::new(16, 16);
+        let pget = |x, y| image.lock().pget(x, y);
+
+        image.lock().text(0.0, 0.0, "-\x0c5-\x0cr-", 7, None);
+        assert_eq!([pget(1.0, 2.0), pget(5.0, 2.0), pget(9.0, 2.0)], [7, 5, 7]);
+        assert_eq!(image.lock().palette[1], 1);
+
+        image.lock().cls(0);
+        image
+            .lock()
+            .text_box(0.0, 0.0, 16.0, 16.0, "-\n--", 7, None, TEXT_ALIGN_RIGHT);
+        assert_eq!([pget(13.0, 2.0), pget(9.0, 2.0)], [7, 0]);
+        assert_eq!([pget(13.0, 8.0), pget(9.0, 8.0), pget(5.0, 8.0)], [7, 7, 0]);
+
+        image.lock().cls(0);
+        image
+            .lock()
+            .text_box(2.0, 0.0, 8.0, 6.0, "- -", 7, None, TEXT_ALIGN_CENTER);
+        assert_eq!([pget(3.0, 2.0), pget(5.0, 2.0), pget(5.0, 8.0)], [0, 7, 0]);
+    }
+}
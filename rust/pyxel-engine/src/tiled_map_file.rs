@@ -1,110 +1,1005 @@
-use std::fs::File;
+use std::fs;
 use std::io::Read;
+use std::path::Path;
 
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+use flate2::read::{GzDecoder, ZlibDecoder};
+use indexmap::IndexMap;
+use ruzstd::decoding::StreamingDecoder;
+use serde::de::IgnoredAny;
 use serde::Deserialize;
 
-use crate::settings::TILE_SIZE;
-use crate::tilemap::{ImageSource, Tilemap};
+use crate::error::{PyxelError, PyxelResult};
+use crate::settings::{NUM_IMAGES, TILE_FLIP_H, TILE_FLIP_V, TILE_ROTATE_90};
+use crate::tilemap::{ImageSource, Tile, TileCoord, TileFlags, Tilemap};
 use crate::utils::remove_whitespace;
 use crate::SharedTilemap;
 
+// The upper four bits of a GID hold Tiled's flip and rotation flags
+const TILE_GID_MASK: u32 = 0x0fff_ffff;
+const TILE_GID_FLIP_H: u32 = 0x8000_0000;
+const TILE_GID_FLIP_V: u32 = 0x4000_0000;
+const TILE_GID_FLIP_D: u32 = 0x2000_0000;
+
+#[derive(Clone, Debug, Deserialize)]
+struct Property {
+    name: String,
+    #[serde(rename = "type")]
+    property_type: Option<String>,
+    value: Option<String>,
+    #[serde(rename = "$value")]
+    text: Option<String>,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+struct Properties {
+    #[serde(rename = "$value", default)]
+    properties: Vec<Property>,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+struct TilesetTile {
+    id: u32,
+    properties: Option<Properties>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum TilesetElement {
+    Properties(IgnoredAny),
+    Image(IgnoredAny),
+    TileOffset(IgnoredAny),
+    Grid(IgnoredAny),
+    TerrainTypes(IgnoredAny),
+    WangSets(IgnoredAny),
+    Transformations(IgnoredAny),
+    Tile(TilesetTile),
+}
+
 #[derive(Debug, Deserialize)]
 struct Tileset {
     firstgid: u32,
+    source: Option<String>,
     columns: Option<u32>,
+    #[serde(rename = "$value", default)]
+    elements: Vec<TilesetElement>,
+}
+
+#[derive(Debug, Deserialize)]
+struct TilesetFile {
+    columns: u32,
+    #[serde(rename = "$value", default)]
+    elements: Vec<TilesetElement>,
 }
 
 #[derive(Debug, Deserialize)]
 struct LayerData {
-    #[serde(rename = "encoding")]
-    encoding: String,
-    #[serde(rename = "$value")]
+    encoding: Option<String>,
+    compression: Option<String>,
+    #[serde(rename = "$value", default)]
     tiles: String,
 }
 
 #[derive(Debug, Deserialize)]
 struct Layer {
+    name: Option<String>,
     width: u32,
     height: u32,
+    visible: Option<u8>,
+    properties: Option<Properties>,
     data: LayerData,
 }
 
+#[derive(Debug, Deserialize)]
+struct ObjectPoints {
+    points: String,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum ObjectElement {
+    Properties(Properties),
+    Ellipse(IgnoredAny),
+    Point(IgnoredAny),
+    Polygon(ObjectPoints),
+    Polyline(ObjectPoints),
+    Text(IgnoredAny),
+}
+
+#[derive(Debug, Deserialize)]
+struct Object {
+    id: u32,
+    name: Option<String>,
+    #[serde(rename = "type")]
+    object_type: Option<String>,
+    class: Option<String>,
+    x: f64,
+    y: f64,
+    width: Option<f64>,
+    height: Option<f64>,
+    rotation: Option<f64>,
+    gid: Option<u32>,
+    visible: Option<u8>,
+    #[serde(rename = "$value", default)]
+    elements: Vec<ObjectElement>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ObjectGroup {
+    name: Option<String>,
+    visible: Option<u8>,
+    #[serde(rename = "$value", default)]
+    elements: Vec<ObjectGroupElement>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum ObjectGroupElement {
+    Properties(Properties),
+    Object(Object),
+}
+
+#[derive(Debug, Deserialize)]
+struct LayerImage {
+    source: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct ImageLayer {
+    name: Option<String>,
+    visible: Option<u8>,
+    properties: Option<Properties>,
+    image: Option<LayerImage>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Group {
+    name: Option<String>,
+    visible: Option<u8>,
+    #[serde(rename = "$value", default)]
+    elements: Vec<MapElement>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum MapElement {
+    Properties(Properties),
+    EditorSettings(IgnoredAny),
+    Tileset(Tileset),
+    Layer(Layer),
+    ObjectGroup(ObjectGroup),
+    ImageLayer(ImageLayer),
+    Group(Group),
+}
+
 #[derive(Debug, Deserialize)]
 struct TiledMapFile {
+    width: u32,
+    height: u32,
     tilewidth: u32,
     tileheight: u32,
-    #[serde(rename = "tileset", default)]
-    tilesets: Vec<Tileset>,
-    #[serde(rename = "layer", default)]
-    layers: Vec<Layer>,
+    #[serde(rename = "$value", default)]
+    elements: Vec<MapElement>,
 }
 
-impl Tilemap {
-    pub fn from_tmx(filename: &str, layer_index: u32) -> SharedTilemap {
-        macro_rules! assert_or_break {
-            ($condition:expr, $fmt:expr $(,$arg:tt)*) => {
-                if !$condition {
-                    println!($fmt, $($arg)*);
-                    break;
+struct ResolvedTileset {
+    firstgid: u32,
+    columns: u32,
+    tiles: Vec<TilesetTile>,
+}
+
+struct FlattenedLayer<'a> {
+    group: String,
+    is_visible: bool,
+    element: &'a MapElement,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum TiledProperty {
+    Bool(bool),
+    Int(i64),
+    Float(f64),
+    String(String),
+}
+
+pub type TiledProperties = IndexMap<String, TiledProperty>;
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum TiledObjectShape {
+    Rect,
+    Ellipse,
+    Point,
+    Polygon(Vec<(f64, f64)>),
+    Polyline(Vec<(f64, f64)>),
+    Tile(u32, Tile),
+}
+
+#[derive(Clone, Debug)]
+pub struct TiledObject {
+    pub id: u32,
+    pub name: String,
+    pub object_type: String,
+    pub x: f64,
+    pub y: f64,
+    pub width: f64,
+    pub height: f64,
+    pub rotation: f64,
+    pub is_visible: bool,
+    pub shape: TiledObjectShape,
+    pub properties: TiledProperties,
+}
+
+#[derive(Clone, Debug)]
+pub enum TiledLayerKind {
+    Tile(u32),
+    Object(Vec<TiledObject>),
+    Image(String),
+}
+
+#[derive(Clone, Debug)]
+pub struct TiledLayer {
+    pub name: String,
+    pub group: String,
+    pub is_visible: bool,
+    pub kind: TiledLayerKind,
+    pub properties: TiledProperties,
+}
+
+#[derive(Clone, Debug)]
+pub struct TiledMap {
+    pub width: u32,
+    pub height: u32,
+    pub properties: TiledProperties,
+    pub layers: Vec<TiledLayer>,
+    pub tile_properties: IndexMap<(u32, Tile), TiledProperties>,
+}
+
+impl LayerData {
+    fn decode(&self) -> Result<Vec<u32>, String> {
+        match self.encoding.as_deref() {
+            Some("csv") => remove_whitespace(&self.tiles)
+                .split(',')
+                .map(str::parse::<u32>)
+                .collect::<Result<Vec<u32>, _>>()
+                .map_err(|err| err.to_string()),
+            Some("base64") => {
+                let data = BASE64
+                    .decode(remove_whitespace(&self.tiles))
+                    .map_err(|err| err.to_string())?;
+                let data = self.decompress(data)?;
+                if data.len() % 4 != 0 {
+                    return Err("layer data is truncated".to_string());
                 }
-            };
+                Ok(data
+                    .chunks_exact(4)
+                    .map(|bytes| u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+                    .collect())
+            }
+            Some(encoding) => Err(format!("layer encoding '{encoding}' is not supported")),
+            None => Err("layer encoding is not specified".to_string()),
         }
-        #[allow(clippy::never_loop)]
-        loop {
-            let file = File::open(filename);
-            assert_or_break!(file.is_ok(), "Failed to open file '{filename}'");
-            let mut file = file.unwrap();
-            let mut tmx_text = String::new();
-            assert_or_break!(
-                file.read_to_string(&mut tmx_text).is_ok(),
-                "Failed to read TMX file"
-            );
-            let tmx = serde_xml_rs::from_str(&tmx_text);
-            assert_or_break!(tmx.is_ok(), "Failed to parse TMX file");
-            let tmx: TiledMapFile = tmx.unwrap();
-            assert_or_break!(
-                tmx.tilewidth == TILE_SIZE && tmx.tileheight == TILE_SIZE,
-                "TMX file's tile size is not {TILE_SIZE}x{TILE_SIZE}"
-            );
-            assert_or_break!(!tmx.tilesets.is_empty(), "Tileset not found in TMX file");
-            let tileset = &tmx.tilesets[0];
-            assert_or_break!(
-                tileset.columns.is_some(),
-                "Tileset is not embedded in TMX file"
-            );
-            let tileset_columns = tileset.columns.unwrap();
-            assert_or_break!(
-                layer_index < tmx.layers.len() as u32,
-                "Layer {layer_index} not found in TMX file"
-            );
-            let layer = &tmx.layers[layer_index as usize];
-            assert_or_break!(
-                layer.data.encoding == "csv",
-                "TMX file's encoding is not CSV"
-            );
-            let layer_data: Vec<u32> = remove_whitespace(&layer.data.tiles)
-                .split(',')
-                .map(|s| s.parse::<u32>().unwrap())
-                .collect();
-            let tilemap = Self::new(layer.width, layer.height, ImageSource::Index(0));
-            {
-                let mut tilemap = tilemap.lock();
-                for (i, tile_id) in layer_data.iter().enumerate() {
-                    let x = i % layer.width as usize;
-                    let y = i / layer.width as usize;
-                    let tile_id = if *tile_id > tileset.firstgid {
-                        tile_id - tileset.firstgid
+    }
+
+    fn decompress(&self, data: Vec<u8>) -> Result<Vec<u8>, String> {
+        let mut decompressed_data = Vec::new();
+        match self.compression.as_deref() {
+            None => return Ok(data),
+            Some("zlib") => ZlibDecoder::new(&data[..])
+                .read_to_end(&mut decompressed_data)
+                .map_err(|err| err.to_string())?,
+            Some("gzip") => GzDecoder::new(&data[..])
+                .read_to_end(&mut decompressed_data)
+                .map_err(|err| err.to_string())?,
+            Some("zstd") => StreamingDecoder::new(&data[..])
+                .map_err(|err| err.to_string())?
+                .read_to_end(&mut decompressed_data)
+                .map_err(|err| err.to_string())?,
+            Some(compression) => {
+                return Err(format!(
+                    "layer compression '{compression}' is not supported"
+                ));
+            }
+        };
+        Ok(decompressed_data)
+    }
+}
+
+impl Property {
+    fn to_tiled_property(&self) -> Result<TiledProperty, String> {
+        let value = self
+            .value
+            .clone()
+            .or_else(|| self.text.clone())
+            .unwrap_or_default();
+        let invalid_value = || format!("property '{}' has invalid value '{value}'", self.name);
+        Ok(match self.property_type.as_deref() {
+            Some("bool") => TiledProperty::Bool(value.parse().map_err(|_| invalid_value())?),
+            Some("int") => TiledProperty::Int(value.parse().map_err(|_| invalid_value())?),
+            Some("float") => TiledProperty::Float(value.parse().map_err(|_| invalid_value())?),
+            _ => TiledProperty::String(value),
+        })
+    }
+}
+
+impl Properties {
+    fn to_tiled_properties(properties: Option<&Self>) -> Result<TiledProperties, String> {
+        properties.map_or(Ok(TiledProperties::new()), |properties| {
+            properties
+                .properties
+                .iter()
+                .map(|property| Ok((property.name.clone(), property.to_tiled_property()?)))
+                .collect()
+        })
+    }
+}
+
+impl TilesetElement {
+    fn tiles(elements: &[Self]) -> Vec<TilesetTile> {
+        elements
+            .iter()
+            .filter_map(|element| match element {
+                Self::Tile(tile) => Some(tile.clone()),
+                _ => None,
+            })
+            .collect()
+    }
+}
+
+impl Object {
+    fn to_tiled_object(&self, tilesets: &[ResolvedTileset]) -> Result<TiledObject, String> {
+        let mut properties = None;
+        let mut shape = TiledObjectShape::Rect;
+        for element in &self.elements {
+            match element {
+                ObjectElement::Properties(object_properties) => {
+                    properties = Some(object_properties);
+                }
+                ObjectElement::Ellipse(_) => shape = TiledObjectShape::Ellipse,
+                ObjectElement::Point(_) => shape = TiledObjectShape::Point,
+                ObjectElement::Polygon(points) => {
+                    shape = TiledObjectShape::Polygon(points.parse()?);
+                }
+                ObjectElement::Polyline(points) => {
+                    shape = TiledObjectShape::Polyline(points.parse()?);
+                }
+                ObjectElement::Text(_) => {}
+            }
+        }
+        if let Some(gid) = self.gid {
+            let tileset_index = ResolvedTileset::index(tilesets, gid & TILE_GID_MASK);
+            let tile = tilesets[tileset_index].tile(gid)?;
+            shape = TiledObjectShape::Tile(tileset_index as u32, tile);
+        }
+        Ok(TiledObject {
+            id: self.id,
+            name: self.name.clone().unwrap_or_default(),
+            object_type: self
+                .class
+                .clone()
+                .or_else(|| self.object_type.clone())
+                .unwrap_or_default(),
+            x: self.x,
+            y: self.y,
+            width: self.width.unwrap_or(0.0),
+            height: self.height.unwrap_or(0.0),
+            rotation: self.rotation.unwrap_or(0.0),
+            is_visible: self.visible != Some(0),
+            shape,
+            properties: Properties::to_tiled_properties(properties)?,
+        })
+    }
+}
+
+impl ObjectPoints {
+    fn parse(&self) -> Result<Vec<(f64, f64)>, String> {
+        self.points
+            .split_whitespace()
+            .map(|point| {
+                point
+                    .split_once(',')
+                    .and_then(|(x, y)| Some((x.parse().ok()?, y.parse().ok()?)))
+                    .ok_or_else(|| format!("object has invalid point '{point}'"))
+            })
+            .collect()
+    }
+}
+
+impl ResolvedTileset {
+    fn index(tilesets: &[Self], gid: u32) -> usize {
+        tilesets
+            .iter()
+            .rposition(|tileset| tileset.firstgid <= gid)
+            .unwrap_or(0)
+    }
+
+    fn tile(&self, gid: u32) -> Result<Tile, String> {
+        let (tile_x, tile_y, _) =
+            self.tile_from_id((gid & TILE_GID_MASK).saturating_sub(self.firstgid))?;
+        Ok((tile_x, tile_y, Self::tile_flags(gid)))
+    }
+
+    fn tile_from_id(&self, tile_id: u32) -> Result<Tile, String> {
+        // Tile coordinates are stored in a byte, which limits tilesets to 256 by 256 tiles
+        let to_coord = |value: u32| {
+            TileCoord::try_from(value).map_err(|_| format!("tile {tile_id} is out of range"))
+        };
+        Ok((
+            to_coord(tile_id % self.columns)?,
+            to_coord(tile_id / self.columns)?,
+            0,
+        ))
+    }
+
+    fn tile_flags(gid: u32) -> TileFlags {
+        // Tiled's diagonal flip equals a 90-degree rotation followed by a horizontal flip
+        let is_diagonal = gid & TILE_GID_FLIP_D != 0;
+        let mut flags = 0;
+        if is_diagonal {
+            flags |= TILE_ROTATE_90;
+        }
+        if (gid & TILE_GID_FLIP_H != 0) != is_diagonal {
+            flags |= TILE_FLIP_H;
+        }
+        if gid & TILE_GID_FLIP_V != 0 {
+            flags |= TILE_FLIP_V;
+        }
+        flags
+    }
+}
+
+impl TiledMapFile {
+    fn load(filename: &str) -> PyxelResult<Self> {
+        let tmx_text = fs::read_to_string(filename)
+            .map_err(|err| PyxelError::from_io_error(filename, &err))?;
+        serde_xml_rs::from_str(&tmx_text).map_err(|err| PyxelError::invalid_format(filename, err))
+    }
+
+    fn tilesets(&self, filename: &str) -> PyxelResult<Vec<ResolvedTileset>> {
+        let tilesets = self
+            .elements
+            .iter()
+            .filter_map(|element| match element {
+                MapElement::Tileset(tileset) => Some(Self::resolve_tileset(filename, tileset)),
+                _ => None,
+            })
+            .collect::<PyxelResult<Vec<ResolvedTileset>>>()?;
+        if tilesets.is_empty() {
+            return Err(PyxelError::invalid_format(filename, "tileset not found"));
+        }
+        Ok(tilesets)
+    }
+
+    fn resolve_tileset(filename: &str, tileset: &Tileset) -> PyxelResult<ResolvedTileset> {
+        let (columns, tiles) = if let Some(columns) = tileset.columns {
+            (columns, TilesetElement::tiles(&tileset.elements))
+        } else {
+            let source = tileset.source.as_ref().ok_or_else(|| {
+                PyxelError::invalid_format(filename, "tileset has neither columns nor source")
+            })?;
+            let tsx_path = Path::new(filename).with_file_name(source);
+            let tsx_filename = tsx_path.to_string_lossy();
+            let tsx_text = fs::read_to_string(&tsx_path)
+                .map_err(|err| PyxelError::from_io_error(&tsx_filename, &err))?;
+            let tsx: TilesetFile = serde_xml_rs::from_str(&tsx_text)
+                .map_err(|err| PyxelError::invalid_format(&tsx_filename, err))?;
+            (tsx.columns, TilesetElement::tiles(&tsx.elements))
+        };
+        // Tiled writes zero columns for tilesets made of separate images
+        if columns == 0 {
+            return Err(PyxelError::invalid_format(
+                filename,
+                "image collection tilesets are not supported",
+            ));
+        }
+        Ok(ResolvedTileset {
+            firstgid: tileset.firstgid,
+            columns,
+            tiles,
+        })
+    }
+
+    fn layers(&self) -> Vec<FlattenedLayer<'_>> {
+        let mut layers = Vec::new();
+        Self::flatten_layers(&self.elements, "", true, &mut layers);
+        layers
+    }
+
+    fn flatten_layers<'a>(
+        elements: &'a [MapElement],
+        group: &str,
+        is_visible: bool,
+        layers: &mut Vec<FlattenedLayer<'a>>,
+    ) {
+        for element in elements {
+            let visible = match element {
+                MapElement::Layer(Layer { visible, .. })
+                | MapElement::ObjectGroup(ObjectGroup { visible, .. })
+                | MapElement::ImageLayer(ImageLayer { visible, .. }) => *visible,
+                MapElement::Group(group_element) => {
+                    let name = group_element.name.as_deref().unwrap_or_default();
+                    let child_group = if group.is_empty() {
+                        name.to_string()
                     } else {
-                        0
+                        format!("{group}/{name}")
                     };
-                    let tile_x = (tile_id % tileset_columns) as u8;
-                    let tile_y = (tile_id / tileset_columns) as u8;
-                    tilemap.canvas.write_data(x, y, (tile_x, tile_y));
+                    Self::flatten_layers(
+                        &group_element.elements,
+                        &child_group,
+                        is_visible && group_element.visible != Some(0),
+                        layers,
+                    );
+                    continue;
+                }
+                _ => continue,
+            };
+            layers.push(FlattenedLayer {
+                group: group.to_string(),
+                is_visible: is_visible && visible != Some(0),
+                element,
+            });
+        }
+    }
+
+    fn tile_layer(&self, filename: &str, layer_index: u32) -> PyxelResult<&Layer> {
+        self.layers()
+            .into_iter()
+            .filter_map(|layer| match layer.element {
+                MapElement::Layer(layer) => Some(layer),
+                _ => None,
+            })
+            .nth(layer_index as usize)
+            .ok_or_else(|| {
+                PyxelError::invalid_format(filename, format!("layer {layer_index} not found"))
+            })
+    }
+}
+
+impl TiledMap {
+    pub fn from_tmx(filename: &str) -> PyxelResult<Self> {
+        let tmx = TiledMapFile::load(filename)?;
+        let tilesets = tmx.tilesets(filename)?;
+        let to_invalid_format = |err: String| PyxelError::invalid_format(filename, err);
+
+        let map_properties = tmx.elements.iter().find_map(|element| match element {
+            MapElement::Properties(properties) => Some(properties),
+            _ => None,
+        });
+        let mut layers = Vec::new();
+        let mut tile_layer_index = 0;
+        for layer in tmx.layers() {
+            let (name, kind, properties) = match layer.element {
+                MapElement::Layer(tile_layer) => {
+                    tile_layer_index += 1;
+                    (
+                        &tile_layer.name,
+                        TiledLayerKind::Tile(tile_layer_index - 1),
+                        tile_layer.properties.as_ref(),
+                    )
+                }
+                MapElement::ObjectGroup(object_group) => (
+                    &object_group.name,
+                    TiledLayerKind::Object(
+                        object_group
+                            .elements
+                            .iter()
+                            .filter_map(|element| match element {
+                                ObjectGroupElement::Object(object) => {
+                                    Some(object.to_tiled_object(&tilesets))
+                                }
+                                ObjectGroupElement::Properties(_) => None,
+                            })
+                            .collect::<Result<_, _>>()
+                            .map_err(to_invalid_format)?,
+                    ),
+                    object_group
+                        .elements
+                        .iter()
+                        .find_map(|element| match element {
+                            ObjectGroupElement::Properties(properties) => Some(properties),
+                            ObjectGroupElement::Object(_) => None,
+                        }),
+                ),
+                MapElement::ImageLayer(image_layer) => (
+                    &image_layer.name,
+                    TiledLayerKind::Image(image_layer.image.as_ref().map_or_else(
+                        String::new,
+                        |image| {
+                            Path::new(filename)
+                                .with_file_name(&image.source)
+                                .to_string_lossy()
+                                .to_string()
+                        },
+                    )),
+                    image_layer.properties.as_ref(),
+                ),
+                _ => unreachable!(),
+            };
+            layers.push(TiledLayer {
+                name: name.clone().unwrap_or_default(),
+                group: layer.group,
+                is_visible: layer.is_visible,
+                kind,
+                properties: Properties::to_tiled_properties(properties)
+                    .map_err(to_invalid_format)?,
+            });
+        }
+
+        let mut tile_properties = IndexMap::new();
+        for (tileset_index, tileset) in tilesets.iter().enumerate() {
+            for tile in &tileset.tiles {
+                if tile.properties.is_some() {
+                    tile_properties.insert(
+                        (
+                            tileset_index as u32,
+                            tileset.tile_from_id(tile.id).map_err(to_invalid_format)?,
+                        ),
+                        Properties::to_tiled_properties(tile.properties.as_ref())
+                            .map_err(to_invalid_format)?,
+                    );
+                }
+            }
+        }
+
+        Ok(Self {
+            width: tmx.width,
+            height: tmx.height,
+            properties: Properties::to_tiled_properties(map_properties)
+                .map_err(to_invalid_format)?,
+            layers,
+            tile_properties,
+        })
+    }
+}
+
+impl Tilemap {
+    pub fn from_tmx(filename: &str, layer_index: u32) -> SharedTilemap {
+        Self::try_from_tmx(filename, layer_index).unwrap_or_else(|err| {
+            println!("{err}");
+            Self::new(1, 1, ImageSource::Index(0))
+        })
+    }
+
+    pub fn try_from_tmx(filename: &str, layer_index: u32) -> PyxelResult<SharedTilemap> {
+        let tmx = TiledMapFile::load(filename)?;
+        if tmx.tilewidth == 0 || tmx.tileheight == 0 {
+            return Err(PyxelError::invalid_format(filename, "tile size is zero"));
+        }
+        let tilesets = tmx.tilesets(filename)?;
+        let layer = tmx.tile_layer(filename, layer_index)?;
+        let layer_data = layer
+            .data
+            .decode()
+            .map_err(|err| PyxelError::invalid_format(filename, err))?;
+        if layer_data.len() != (layer.width * layer.height) as usize {
+            return Err(PyxelError::invalid_format(
+                filename,
+                format!("layer {layer_index} size does not match its data"),
+            ));
+        }
+
+        // Each tileset is mapped to the image bank of the same index, so a layer
+        // can only refer to the tiles of one tileset
+        let mut used_tileset_index = None;
+        for gid in layer_data.iter().map(|gid| gid & TILE_GID_MASK) {
+            if gid == 0 {
+                continue;
+            }
+            let index = ResolvedTileset::index(&tilesets, gid);
+            if used_tileset_index.is_some_and(|used_index| used_index != index) {
+                return Err(PyxelError::invalid_format(
+                    filename,
+                    format!("layer {layer_index} refers to multiple tilesets"),
+                ));
+            }
+            used_tileset_index = Some(index);
+        }
+        let used_tileset_index = used_tileset_index.unwrap_or(0);
+        if used_tileset_index >= NUM_IMAGES as usize {
+            return Err(PyxelError::invalid_format(
+                filename,
+                format!("tileset {used_tileset_index} has no corresponding image"),
+            ));
+        }
+        let tileset = &tilesets[used_tileset_index];
+
+        let tilemap = Self::new(
+            layer.width,
+            layer.height,
+            ImageSource::Index(used_tileset_index as u32),
+        );
+        {
+            let mut tilemap = tilemap.lock();
+            tilemap.tile_width = tmx.tilewidth;
+            tilemap.tile_height = tmx.tileheight;
+            for (i, gid) in layer_data.iter().enumerate() {
+                let x = i % layer.width as usize;
+                let y = i / layer.width as usize;
+                let tile = tileset
+                    .tile(*gid)
+                    .map_err(|err| PyxelError::invalid_format(filename, err))?;
+                if tile.2 & TILE_ROTATE_90 != 0 && tmx.tilewidth != tmx.tileheight {
+                    return Err(PyxelError::invalid_format(
+                        filename,
+                        "rotated tiles require a square tile size",
+                    ));
                 }
+                tilemap.canvas.write_data(x, y, tile);
             }
-            return tilemap;
         }
-        // Return a blank tilemap due to an error
-        Self::new(1, 1, ImageSource::Index(0))
+        Ok(tilemap)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+
+    use flate2::write::{GzEncoder, ZlibEncoder};
+    use flate2::Compression;
+    use ruzstd::encoding::{compress_to_vec, CompressionLevel};
+
+    use super::*;
+
+    #[test]
+    fn test_tile_flags() {
+        assert_eq!(ResolvedTileset::tile_flags(5), 0);
+        assert_eq!(
+            ResolvedTileset::tile_flags(TILE_GID_FLIP_H | 5),
+            TILE_FLIP_H
+        );
+        assert_eq!(
+            ResolvedTileset::tile_flags(TILE_GID_FLIP_V | 5),
+            TILE_FLIP_V
+        );
+        assert_eq!(
+            ResolvedTileset::tile_flags(TILE_GID_FLIP_D | TILE_GID_FLIP_H | 5),
+            TILE_ROTATE_90
+        );
+        assert_eq!(
+            ResolvedTileset::tile_flags(TILE_GID_FLIP_D | 5),
+            TILE_ROTATE_90 | TILE_FLIP_H
+        );
+    }
+
+    #[test]
+    fn test_tile_from_id() {
+        let tileset = |columns| ResolvedTileset {
+            firstgid: 1,
+            columns,
+            tiles: Vec::new(),
+        };
+        assert_eq!(tileset(8).tile_from_id(10), Ok((2, 1, 0)));
+        assert_eq!(tileset(8).tile_from_id(8 * 256 - 1), Ok((7, 255, 0)));
+        assert!(tileset(8).tile_from_id(8 * 256).is_err());
+        assert!(tileset(300).tile_from_id(299).is_err());
+        assert_eq!(
+            tileset(8).tile(TILE_GID_FLIP_H | 0x0b),
+            Ok((2, 1, TILE_FLIP_H))
+        );
+    }
+
+    #[test]
+    fn test_decode_layer_data() {
+        let gids: [u32; 4] = [0, 1, 0x8000_0002, 300];
+        let bytes: Vec<u8> = gids.iter().flat_map(|gid| gid.to_le_bytes()).collect();
+        let layer_data = |encoding: &str, compression: Option<&str>, tiles: String| LayerData {
+            encoding: Some(encoding.to_string()),
+            compression: compression.map(str::to_string),
+            tiles,
+        };
+
+        let csv_data = layer_data("csv", None, "0,1,\n2147483650,300\n".to_string());
+        assert_eq!(csv_data.decode().unwrap(), gids);
+
+        let base64_data = layer_data("base64", None, BASE64.encode(&bytes));
+        assert_eq!(base64_data.decode().unwrap(), gids);
+
+        let mut encoder = ZlibEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(&bytes).unwrap();
+        let zlib_data = layer_data(
+            "base64",
+            Some("zlib"),
+            BASE64.encode(encoder.finish().unwrap()),
+        );
+        assert_eq!(zlib_data.decode().unwrap(), gids);
+
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(&bytes).unwrap();
+        let gzip_data = layer_data(
+            "base64",
+            Some("gzip"),
+            BASE64.encode(encoder.finish().unwrap()),
+        );
+        assert_eq!(gzip_data.decode().unwrap(), gids);
+
+        let zstd_bytes = compress_to_vec(&bytes[..], CompressionLevel::Fastest);
+        let zstd_data = layer_data("base64", Some("zstd"), BASE64.encode(zstd_bytes));
+        assert_eq!(zstd_data.decode().unwrap(), gids);
+
+        let unknown_data = layer_data("base64", Some("lzma"), BASE64.encode(&bytes));
+        assert!(unknown_data.decode().is_err());
+    }
+
+    fn write_tmx_files(dir: &Path) -> String {
+        fs::create_dir_all(dir).unwrap();
+        fs::write(
+            dir.join("tiles.tsx"),
+            r#"<?xml version="1.0" encoding="UTF-8"?>
+<tileset name="tiles" tilewidth="16" tileheight="8" tilecount="64" columns="8">
+ <image source="tiles.png" width="128" height="64"/>
+ <tile id="9"><properties><property name="solid" type="bool" value="true"/></properties></tile>
+</tileset>"#,
+        )
+        .unwrap();
+        let tmx_path = dir.join("map.tmx");
+        fs::write(
+            &tmx_path,
+            r#"<?xml version="1.0" encoding="UTF-8"?>
+<map version="1.10" orientation="orthogonal" width="2" height="2" tilewidth="16" tileheight="8">
+ <editorsettings><export target="map.json" format="json"/></editorsettings>
+ <properties>
+  <property name="title" value="Stage 1"/>
+  <property name="note">multi
+line</property>
+ </properties>
+ <tileset firstgid="1" source="tiles.tsx"/>
+ <layer id="1" name="ground" width="2" height="2">
+  <data encoding="csv">1,2,0,10</data>
+ </layer>
+ <objectgroup id="2" name="spawns">
+  <properties><property name="speed" type="float" value="1.5"/></properties>
+  <object id="1" name="player" type="spawn" x="8" y="16"><point/></object>
+  <object id="2" x="0" y="0" width="16" height="8"/>
+  <object id="3" x="4" y="4"><polygon points="0,0 8,0 4,-6.5"/></object>
+  <object id="4" gid="2147483658" x="0" y="8" width="8" height="8"/>
+ </objectgroup>
+ <group id="3" name="deco" visible="0">
+  <imagelayer id="4" name="sky"><image source="sky.png" width="16" height="16"/></imagelayer>
+  <layer id="5" name="flowers" width="2" height="2">
+   <properties><property name="depth" type="int" value="-2"/></properties>
+   <data encoding="base64">AwAAAAAAAAAAAAAAAAAAAA==</data>
+  </layer>
+ </group>
+</map>"#,
+        )
+        .unwrap();
+        tmx_path.to_string_lossy().to_string()
+    }
+
+    #[test]
+    fn test_tiled_map_from_tmx() {
+        let dir = std::env::temp_dir().join("pyxel_test_tiled_map_from_tmx");
+        let tmx_filename = write_tmx_files(&dir);
+
+        let tiled_map = TiledMap::from_tmx(&tmx_filename).unwrap();
+        assert_eq!((tiled_map.width, tiled_map.height), (2, 2));
+        assert_eq!(
+            tiled_map.properties["title"],
+            TiledProperty::String("Stage 1".to_string())
+        );
+        assert_eq!(
+            tiled_map.properties["note"],
+            TiledProperty::String("multi\nline".to_string())
+        );
+        assert_eq!(
+            tiled_map.tile_properties[&(0, (1, 1, 0))]["solid"],
+            TiledProperty::Bool(true)
+        );
+
+        let layer_names: Vec<(&str, &str, bool)> = tiled_map
+            .layers
+            .iter()
+            .map(|layer| (layer.name.as_str(), layer.group.as_str(), layer.is_visible))
+            .collect();
+        assert_eq!(
+            layer_names,
+            [
+                ("ground", "", true),
+                ("spawns", "", true),
+                ("sky", "deco", false),
+                ("flowers", "deco", false)
+            ]
+        );
+        assert!(matches!(tiled_map.layers[0].kind, TiledLayerKind::Tile(0)));
+        assert!(
+            matches!(&tiled_map.layers[2].kind, TiledLayerKind::Image(source) if source.ends_with("sky.png"))
+        );
+        assert!(matches!(tiled_map.layers[3].kind, TiledLayerKind::Tile(1)));
+        assert_eq!(
+            tiled_map.layers[3].properties["depth"],
+            TiledProperty::Int(-2)
+        );
+
+        let spawns = &tiled_map.layers[1];
+        assert_eq!(spawns.properties["speed"], TiledProperty::Float(1.5));
+        let TiledLayerKind::Object(objects) = &spawns.kind else {
+            panic!("object layer expected");
+        };
+        assert_eq!(objects.len(), 4);
+        assert_eq!(
+            (objects[0].name.as_str(), objects[0].object_type.as_str()),
+            ("player", "spawn")
+        );
+        assert_eq!((objects[0].x, objects[0].y), (8.0, 16.0));
+        assert_eq!(objects[0].shape, TiledObjectShape::Point);
+        assert_eq!(objects[1].shape, TiledObjectShape::Rect);
+        assert_eq!((objects[1].width, objects[1].height), (16.0, 8.0));
+        assert_eq!(
+            objects[2].shape,
+            TiledObjectShape::Polygon(vec![(0.0, 0.0), (8.0, 0.0), (4.0, -6.5)])
+        );
+        assert_eq!(
+            objects[3].shape,
+            TiledObjectShape::Tile(0, (1, 1, TILE_FLIP_H))
+        );
+
+        let tilemap = Tilemap::try_from_tmx(&tmx_filename, 1).unwrap();
+        let tilemap = tilemap.lock();
+        assert_eq!((tilemap.tile_width, tilemap.tile_height), (16, 8));
+        assert_eq!(tilemap.canvas.read_data(0, 0), (2, 0, 0));
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_image_collection_tileset() {
+        let dir = std::env::temp_dir().join("pyxel_test_image_collection_tileset");
+        fs::create_dir_all(&dir).unwrap();
+        let tmx_path = dir.join("map.tmx");
+        fs::write(
+            &tmx_path,
+            r#"<?xml version="1.0" encoding="UTF-8"?>
+<map version="1.10" orientation="orthogonal" width="1" height="1" tilewidth="16" tileheight="16">
+ <tileset firstgid="1" name="sprites" tilewidth="16" tileheight="16" tilecount="1" columns="0">
+  <grid orientation="orthogonal" width="1" height="1"/>
+  <tile id="0"><image source="tree.png" width="16" height="16"/></tile>
+ </tileset>
+ <layer id="1" name="ground" width="1" height="1">
+  <data encoding="csv">1</data>
+ </layer>
+</map>"#,
+        )
+        .unwrap();
+        let tmx_filename = tmx_path.to_string_lossy();
+
+        for result in [
+            TiledMap::from_tmx(&tmx_filename).err(),
+            Tilemap::try_from_tmx(&tmx_filename, 0).err(),
+        ] {
+            assert!(
+                matches!(result, Some(PyxelError::InvalidFormat(_, message)) if message.contains("image collection"))
+            );
+        }
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_rotated_tile_with_non_square_size() {
+        let dir = std::env::temp_dir().join("pyxel_test_rotated_tile_with_non_square_size");
+        fs::create_dir_all(&dir).unwrap();
+        let tmx_path = dir.join("map.tmx");
+        fs::write(
+            &tmx_path,
+            r#"<?xml version="1.0" encoding="UTF-8"?>
+<map version="1.10" orientation="orthogonal" width="1" height="1" tilewidth="16" tileheight="8">
+ <tileset firstgid="1" name="tiles" tilewidth="16" tileheight="8" tilecount="64" columns="8">
+  <image source="tiles.png" width="128" height="64"/>
+ </tileset>
+ <layer id="1" name="ground" width="1" height="1">
+  <data encoding="csv">2684354561</data>
+ </layer>
+</map>"#,
+        )
+        .unwrap();
+        let tmx_filename = tmx_path.to_string_lossy();
+
+        let result = Tilemap::try_from_tmx(&tmx_filename, 0).err();
+        assert!(
+            matches!(result, Some(PyxelError::InvalidFormat(_, message)) if message.contains("square tile size"))
+        );
+
+        fs::remove_dir_all(&dir).unwrap();
     }
 }
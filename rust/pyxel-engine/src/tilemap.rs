@@ -1,9 +1,12 @@
 use crate::canvas::{Canvas, ToIndex};
+use crate::error::PyxelResult;
 use crate::image::SharedImage;
+use crate::settings::TILE_SIZE;
 use crate::utils::{f64_to_u32, parse_hex_string, simplify_string};
 
 pub type TileCoord = u8;
-pub type Tile = (TileCoord, TileCoord);
+pub type TileFlags = u8;
+pub type Tile = (TileCoord, TileCoord, TileFlags);
 
 impl ToIndex for Tile {
     fn to_index(&self) -> usize {
@@ -20,6 +23,8 @@ pub enum ImageSource {
 pub struct Tilemap {
     pub(crate) canvas: Canvas<Tile>,
     pub imgsrc: ImageSource,
+    pub(crate) tile_width: u32,
+    pub(crate) tile_height: u32,
 }
 
 pub type SharedTilemap = shared_type!(Tilemap);
@@ -29,6 +34,8 @@ impl Tilemap {
         new_shared_type!(Self {
             canvas: Canvas::new(width, height),
             imgsrc,
+            tile_width: TILE_SIZE,
+            tile_height: TILE_SIZE,
         })
     }
 
@@ -40,6 +47,24 @@ impl Tilemap {
         self.canvas.height()
     }
 
+    pub const fn tile_width(&self) -> u32 {
+        self.tile_width
+    }
+
+    pub fn set_tile_width(&mut self, tile_width: u32) {
+        assert!(tile_width > 0, "Tile width must be positive");
+        self.tile_width = tile_width;
+    }
+
+    pub const fn tile_height(&self) -> u32 {
+        self.tile_height
+    }
+
+    pub fn set_tile_height(&mut self, tile_height: u32) {
+        assert!(tile_height > 0, "Tile height must be positive");
+        self.tile_height = tile_height;
+    }
+
     pub fn data_ptr(&mut self) -> *mut Tile {
         self.canvas.data_ptr()
     }
@@ -61,6 +86,7 @@ impl Tilemap {
                         (
                             ((tile >> 8) & 0xff) as TileCoord,
                             (tile & 0xff) as TileCoord,
+                            0,
                         ),
                     );
                 }
@@ -80,6 +106,22 @@ impl Tilemap {
 
     pub fn load(&mut self, x: i32, y: i32, filename: &str, layer_index: u32) {
         let tilemap = Self::from_tmx(filename, layer_index);
+        self.blt_tilemap(x, y, tilemap);
+    }
+
+    pub fn try_load(
+        &mut self,
+        x: i32,
+        y: i32,
+        filename: &str,
+        layer_index: u32,
+    ) -> PyxelResult<()> {
+        let tilemap = Self::try_from_tmx(filename, layer_index)?;
+        self.blt_tilemap(x, y, tilemap);
+        Ok(())
+    }
+
+    fn blt_tilemap(&mut self, x: i32, y: i32, tilemap: SharedTilemap) {
         let tilemap_width = tilemap.lock().width();
         let tilemap_height = tilemap.lock().height();
         self.blt(
@@ -158,10 +200,58 @@ impl Tilemap {
         self.canvas.trib(x1, y1, x2, y2, x3, y3, tile);
     }
 
+    pub fn poly(&mut self, points: &[(f64, f64)], tile: Tile) {
+        self.canvas.poly(points, tile);
+    }
+
+    pub fn polyb(&mut self, points: &[(f64, f64)], tile: Tile) {
+        self.canvas.polyb(points, tile);
+    }
+
+    pub fn thick_line(&mut self, x1: f64, y1: f64, x2: f64, y2: f64, thickness: f64, tile: Tile) {
+        self.canvas.thick_line(x1, y1, x2, y2, thickness, tile);
+    }
+
+    pub fn rrect(&mut self, x: f64, y: f64, width: f64, height: f64, radius: f64, tile: Tile) {
+        self.canvas.rrect(x, y, width, height, radius, tile);
+    }
+
+    pub fn rrectb(&mut self, x: f64, y: f64, width: f64, height: f64, radius: f64, tile: Tile) {
+        self.canvas.rrectb(x, y, width, height, radius, tile);
+    }
+
+    pub fn arc(
+        &mut self,
+        x: f64,
+        y: f64,
+        radius: f64,
+        start_angle: f64,
+        end_angle: f64,
+        tile: Tile,
+    ) {
+        self.canvas.arc(x, y, radius, start_angle, end_angle, tile);
+    }
+
+    pub fn pie(
+        &mut self,
+        x: f64,
+        y: f64,
+        radius: f64,
+        start_angle: f64,
+        end_angle: f64,
+        tile: Tile,
+    ) {
+        self.canvas.pie(x, y, radius, start_angle, end_angle, tile);
+    }
+
     pub fn fill(&mut self, x: f64, y: f64, tile: Tile) {
         self.canvas.fill(x, y, tile);
     }
 
+    pub fn fill_boundary(&mut self, x: f64, y: f64, boundary_tile: Tile, tile: Tile) {
+        self.canvas.fill_boundary(x, y, boundary_tile, tile);
+    }
+
     pub fn blt(
         &mut self,
         x: f64,
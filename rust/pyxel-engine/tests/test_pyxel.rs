@@ -70,6 +70,13 @@ impl App {
         pyxel.play(2, &[4], None, true);
 
         let app = App { x: 0.0, y: 0.0 };
+        #[cfg(feature = "headless")]
+        {
+            let mut app = app;
+            pyxel.step(&mut app, 120);
+            assert_eq!(pyxel.frame_count, 120);
+        }
+        #[cfg(not(feature = "headless"))]
         pyxel.run(app);
     }
 }
@@ -92,7 +99,7 @@ impl PyxelCallback for App {
         pyxel.rect(self.x + 10.0, 25.0, 15.0, 10.0, 8);
         pyxel.rectb(self.x + 15.0, 45.0, 15.0, 10.0, pyxel::COLOR_WHITE);
 
-        pyxel.blt(0.0, 0.0, 0, 0.0, 0.0, 8.0, 8.0, None);
+        pyxel.blt(0.0, 0.0, 0, 0.0, 0.0, 8.0, 8.0, None, None, None);
 
         pyxel.screen.lock().blt(
             50.0,
@@ -103,6 +110,8 @@ impl PyxelCallback for App {
             100.0,
             100.0,
             None,
+            None,
+            None,
         );
     }
 }
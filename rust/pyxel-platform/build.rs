@@ -206,5 +206,8 @@ impl SDL2BindingsBuilder {
 }
 
 fn main() {
+    if var("CARGO_FEATURE_HEADLESS").is_ok() {
+        return;
+    }
     SDL2BindingsBuilder::new().build();
 }
@@ -1,18 +1,27 @@
+#[cfg(not(feature = "headless"))]
 use std::mem::MaybeUninit;
+#[cfg(not(feature = "headless"))]
 use std::os::raw::{c_int, c_void};
+#[cfg(not(feature = "headless"))]
 use std::ptr::null_mut;
+#[cfg(not(feature = "headless"))]
 use std::slice;
+#[cfg(not(feature = "headless"))]
 use std::sync::Arc;
 
+#[cfg(not(feature = "headless"))]
 use parking_lot::Mutex;
 
+#[cfg(not(feature = "headless"))]
 use crate::platform::platform;
+#[cfg(not(feature = "headless"))]
 use crate::sdl2_sys::*;
 
 pub trait AudioCallback {
     fn update(&mut self, out: &mut [i16]);
 }
 
+#[cfg(not(feature = "headless"))]
 extern "C" fn c_audio_callback(userdata: *mut c_void, stream: *mut u8, len: c_int) {
     let audio_callback = unsafe { &*userdata.cast::<Arc<Mutex<dyn AudioCallback>>>() };
     let stream: &mut [i16] =
@@ -20,6 +29,7 @@ extern "C" fn c_audio_callback(userdata: *mut c_void, stream: *mut u8, len: c_in
     audio_callback.lock().update(stream);
 }
 
+#[cfg(not(feature = "headless"))]
 pub fn start_audio(
     sample_rate: u32,
     num_channels: u8,
@@ -47,6 +57,7 @@ pub fn start_audio(
     set_audio_enabled(true);
 }
 
+#[cfg(not(feature = "headless"))]
 pub fn set_audio_enabled(enabled: bool) {
     let pause_on = i32::from(!enabled);
     let audio_device_id = platform().audio_device_id;
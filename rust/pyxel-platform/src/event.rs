@@ -1,18 +1,25 @@
+#[cfg(not(feature = "headless"))]
 use std::mem::zeroed;
+#[cfg(not(feature = "headless"))]
 use std::ptr::addr_of_mut;
 
+#[cfg(not(feature = "headless"))]
 use crate::gamepad::{
     handle_controller_axis_motion, handle_controller_button_down, handle_controller_button_up,
     handle_controller_device_added, handle_controller_device_removed,
 };
-#[cfg(target_os = "emscripten")]
+#[cfg(all(target_os = "emscripten", not(feature = "headless")))]
 use crate::gamepad::{handle_joy_button_down, handle_joy_button_up, handle_virtual_gamepad_inputs};
+#[cfg(not(feature = "headless"))]
 use crate::keyboard::{handle_key_down, handle_key_up, handle_text_input};
 use crate::keys::{Key, KeyValue};
+#[cfg(not(feature = "headless"))]
 use crate::mouse::{
     handle_mouse_button_down, handle_mouse_button_up, handle_mouse_motion, handle_mouse_wheel,
 };
+#[cfg(not(feature = "headless"))]
 use crate::sdl2_sys::*;
+#[cfg(not(feature = "headless"))]
 use crate::window::{handle_drop_file, handle_quit, handle_window_event};
 
 #[derive(Clone)]
@@ -27,6 +34,7 @@ pub enum Event {
     Quit,
 }
 
+#[cfg(not(feature = "headless"))]
 pub fn poll_events() -> Vec<Event> {
     let mut pyxel_events = Vec::new();
     let mut sdl_event: SDL_Event = unsafe { zeroed() };
@@ -0,0 +1,165 @@
+use std::mem::transmute;
+use std::ptr::null_mut;
+use std::sync::Arc;
+
+use parking_lot::Mutex;
+
+use crate::audio::AudioCallback;
+use crate::event::Event;
+use crate::keys::{MOUSE_POS_X, MOUSE_POS_Y};
+
+const DISPLAY_WIDTH: u32 = 1920;
+const DISPLAY_HEIGHT: u32 = 1080;
+const FRAME_MS: f64 = 1000.0 / 60.0;
+
+struct AudioSink {
+    sample_rate: u32,
+    num_channels: u8,
+    num_samples: u16,
+    audio_callback: Arc<Mutex<dyn AudioCallback>>,
+    rendered_samples: u64,
+}
+
+pub struct Platform {
+    elapsed_ms: f64,
+    events: Vec<Event>,
+    audio_sink: Option<AudioSink>,
+    is_audio_enabled: bool,
+    window_x: i32,
+    window_y: i32,
+    window_width: u32,
+    window_height: u32,
+    is_fullscreen: bool,
+}
+
+static mut PLATFORM: *mut Platform = null_mut();
+
+fn platform() -> &'static mut Platform {
+    unsafe { &mut *PLATFORM }
+}
+
+pub fn init<'a, F: FnOnce(u32, u32) -> (&'a str, u32, u32)>(window_params: F) {
+    let (_, width, height) = window_params(DISPLAY_WIDTH, DISPLAY_HEIGHT);
+    unsafe {
+        PLATFORM = transmute::<Box<Platform>, *mut Platform>(Box::new(Platform {
+            elapsed_ms: 0.0,
+            events: Vec::new(),
+            audio_sink: None,
+            is_audio_enabled: false,
+            window_x: 0,
+            window_y: 0,
+            window_width: width,
+            window_height: height,
+            is_fullscreen: false,
+        }));
+    }
+}
+
+pub fn run<F: FnMut()>(mut main_loop: F) {
+    loop {
+        main_loop();
+        advance_time(FRAME_MS);
+    }
+}
+
+pub fn quit() {
+    std::process::exit(0);
+}
+
+pub fn elapsed_time() -> u32 {
+    platform().elapsed_ms as u32
+}
+
+pub fn sleep(ms: u32) {
+    advance_time(ms as f64);
+}
+
+fn advance_time(ms: f64) {
+    let platform = platform();
+    platform.elapsed_ms += ms;
+    if let Some(audio_sink) = &mut platform.audio_sink {
+        let target_samples = (platform.elapsed_ms * audio_sink.sample_rate as f64 / 1000.0) as u64;
+        if !platform.is_audio_enabled {
+            audio_sink.rendered_samples = target_samples;
+            return;
+        }
+        let mut buffer =
+            vec![0; audio_sink.num_samples as usize * audio_sink.num_channels as usize];
+        while audio_sink.rendered_samples + (audio_sink.num_samples as u64) <= target_samples {
+            audio_sink.audio_callback.lock().update(&mut buffer);
+            audio_sink.rendered_samples += audio_sink.num_samples as u64;
+        }
+    }
+}
+
+pub fn start_audio(
+    sample_rate: u32,
+    num_channels: u8,
+    num_samples: u16,
+    audio_callback: Arc<Mutex<dyn AudioCallback>>,
+) {
+    let platform = platform();
+    platform.audio_sink = Some(AudioSink {
+        sample_rate,
+        num_channels,
+        num_samples,
+        audio_callback,
+        rendered_samples: (platform.elapsed_ms * sample_rate as f64 / 1000.0) as u64,
+    });
+    set_audio_enabled(true);
+}
+
+pub fn set_audio_enabled(enabled: bool) {
+    platform().is_audio_enabled = enabled;
+}
+
+pub fn poll_events() -> Vec<Event> {
+    platform().events.drain(..).collect()
+}
+
+pub fn push_event(event: Event) {
+    platform().events.push(event);
+}
+
+pub fn set_window_title(_title: &str) {}
+
+pub fn set_window_icon(_width: u32, _height: u32, _rgba_data: &[u8]) {}
+
+pub fn window_pos() -> (i32, i32) {
+    (platform().window_x, platform().window_y)
+}
+
+pub fn set_window_pos(x: i32, y: i32) {
+    platform().window_x = x;
+    platform().window_y = y;
+}
+
+pub fn window_size() -> (u32, u32) {
+    (platform().window_width, platform().window_height)
+}
+
+pub fn set_window_size(width: u32, height: u32) {
+    platform().window_width = width;
+    platform().window_height = height;
+}
+
+pub fn is_fullscreen() -> bool {
+    platform().is_fullscreen
+}
+
+pub fn set_fullscreen(full: bool) {
+    platform().is_fullscreen = full;
+}
+
+pub fn set_mouse_visible(_visible: bool) {}
+
+pub fn set_mouse_pos(x: i32, y: i32) {
+    push_event(Event::KeyValueChanged {
+        key: MOUSE_POS_X,
+        value: x,
+    });
+    push_event(Event::KeyValueChanged {
+        key: MOUSE_POS_Y,
+        value: y,
+    });
+}
@@ -1,5 +1,8 @@
 use paste::paste;
 
+#[cfg(feature = "headless")]
+use crate::sdl2_keycodes::*;
+#[cfg(not(feature = "headless"))]
 use crate::sdl2_sys::*;
 
 pub type Key = u32;
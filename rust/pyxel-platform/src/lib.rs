@@ -13,23 +13,49 @@
     clippy::wildcard_imports
 )]
 
+use cfg_if::cfg_if;
+
 mod audio;
 #[cfg(target_os = "emscripten")]
 pub mod emscripten;
 mod event;
+#[cfg(not(feature = "headless"))]
 mod gamepad;
+#[cfg(feature = "headless")]
+mod headless;
+#[cfg(not(feature = "headless"))]
 mod keyboard;
 pub mod keys;
+#[cfg(not(feature = "headless"))]
 mod mouse;
+#[cfg(not(feature = "headless"))]
 mod platform;
+#[cfg(feature = "headless")]
+mod sdl2_keycodes;
+#[cfg(not(feature = "headless"))]
 mod sdl2_sys;
+#[cfg(not(feature = "headless"))]
 mod window;
 
-pub use crate::audio::{set_audio_enabled, start_audio, AudioCallback};
-pub use crate::event::{poll_events, Event};
-pub use crate::platform::{elapsed_time, init, quit, run, sleep};
-pub use crate::window::{
-    glow_context, is_fullscreen, is_gles_enabled, set_fullscreen, set_mouse_pos, set_mouse_visible,
-    set_window_icon, set_window_pos, set_window_size, set_window_title, swap_window, window_pos,
-    window_size,
-};
+pub use crate::audio::AudioCallback;
+pub use crate::event::Event;
+
+cfg_if! {
+    if #[cfg(feature = "headless")] {
+        pub use crate::headless::{
+            elapsed_time, init, is_fullscreen, poll_events, push_event, quit, run,
+            set_audio_enabled, set_fullscreen, set_mouse_pos, set_mouse_visible, set_window_icon,
+            set_window_pos, set_window_size, set_window_title, sleep, start_audio, window_pos,
+            window_size,
+        };
+    } else {
+        pub use crate::audio::{set_audio_enabled, start_audio};
+        pub use crate::event::poll_events;
+        pub use crate::platform::{elapsed_time, init, quit, run, sleep};
+        pub use crate::window::{
+            glow_context, is_fullscreen, is_gles_enabled, set_fullscreen, set_mouse_pos,
+            set_mouse_visible, set_window_icon, set_window_pos, set_window_size, set_window_title,
+            swap_window, window_pos, window_size,
+        };
+    }
+}
@@ -0,0 +1,250 @@
+#![allow(non_upper_case_globals)]
+
+// SDL2 keycodes for builds without the SDL2 bindings (based on SDL_keycode.h in SDL 2.24.2)
+
+const SCANCODE_MASK: i32 = 1 << 30;
+
+const fn scancode_to_keycode(scancode: i32) -> i32 {
+    scancode | SCANCODE_MASK
+}
+
+pub const SDLK_UNKNOWN: i32 = 0;
+pub const SDLK_RETURN: i32 = '\r' as i32;
+pub const SDLK_ESCAPE: i32 = '\x1B' as i32;
+pub const SDLK_BACKSPACE: i32 = '\x08' as i32;
+pub const SDLK_TAB: i32 = '\t' as i32;
+pub const SDLK_SPACE: i32 = ' ' as i32;
+pub const SDLK_EXCLAIM: i32 = '!' as i32;
+pub const SDLK_QUOTEDBL: i32 = '"' as i32;
+pub const SDLK_HASH: i32 = '#' as i32;
+pub const SDLK_PERCENT: i32 = '%' as i32;
+pub const SDLK_DOLLAR: i32 = '$' as i32;
+pub const SDLK_AMPERSAND: i32 = '&' as i32;
+pub const SDLK_QUOTE: i32 = '\'' as i32;
+pub const SDLK_LEFTPAREN: i32 = '(' as i32;
+pub const SDLK_RIGHTPAREN: i32 = ')' as i32;
+pub const SDLK_ASTERISK: i32 = '*' as i32;
+pub const SDLK_PLUS: i32 = '+' as i32;
+pub const SDLK_COMMA: i32 = ',' as i32;
+pub const SDLK_MINUS: i32 = '-' as i32;
+pub const SDLK_PERIOD: i32 = '.' as i32;
+pub const SDLK_SLASH: i32 = '/' as i32;
+pub const SDLK_0: i32 = '0' as i32;
+pub const SDLK_1: i32 = '1' as i32;
+pub const SDLK_2: i32 = '2' as i32;
+pub const SDLK_3: i32 = '3' as i32;
+pub const SDLK_4: i32 = '4' as i32;
+pub const SDLK_5: i32 = '5' as i32;
+pub const SDLK_6: i32 = '6' as i32;
+pub const SDLK_7: i32 = '7' as i32;
+pub const SDLK_8: i32 = '8' as i32;
+pub const SDLK_9: i32 = '9' as i32;
+pub const SDLK_COLON: i32 = ':' as i32;
+pub const SDLK_SEMICOLON: i32 = ';' as i32;
+pub const SDLK_LESS: i32 = '<' as i32;
+pub const SDLK_EQUALS: i32 = '=' as i32;
+pub const SDLK_GREATER: i32 = '>' as i32;
+pub const SDLK_QUESTION: i32 = '?' as i32;
+pub const SDLK_AT: i32 = '@' as i32;
+pub const SDLK_LEFTBRACKET: i32 = '[' as i32;
+pub const SDLK_BACKSLASH: i32 = '\\' as i32;
+pub const SDLK_RIGHTBRACKET: i32 = ']' as i32;
+pub const SDLK_CARET: i32 = '^' as i32;
+pub const SDLK_UNDERSCORE: i32 = '_' as i32;
+pub const SDLK_BACKQUOTE: i32 = '`' as i32;
+pub const SDLK_a: i32 = 'a' as i32;
+pub const SDLK_b: i32 = 'b' as i32;
+pub const SDLK_c: i32 = 'c' as i32;
+pub const SDLK_d: i32 = 'd' as i32;
+pub const SDLK_e: i32 = 'e' as i32;
+pub const SDLK_f: i32 = 'f' as i32;
+pub const SDLK_g: i32 = 'g' as i32;
+pub const SDLK_h: i32 = 'h' as i32;
+pub const SDLK_i: i32 = 'i' as i32;
+pub const SDLK_j: i32 = 'j' as i32;
+pub const SDLK_k: i32 = 'k' as i32;
+pub const SDLK_l: i32 = 'l' as i32;
+pub const SDLK_m: i32 = 'm' as i32;
+pub const SDLK_n: i32 = 'n' as i32;
+pub const SDLK_o: i32 = 'o' as i32;
+pub const SDLK_p: i32 = 'p' as i32;
+pub const SDLK_q: i32 = 'q' as i32;
+pub const SDLK_r: i32 = 'r' as i32;
+pub const SDLK_s: i32 = 's' as i32;
+pub const SDLK_t: i32 = 't' as i32;
+pub const SDLK_u: i32 = 'u' as i32;
+pub const SDLK_v: i32 = 'v' as i32;
+pub const SDLK_w: i32 = 'w' as i32;
+pub const SDLK_x: i32 = 'x' as i32;
+pub const SDLK_y: i32 = 'y' as i32;
+pub const SDLK_z: i32 = 'z' as i32;
+pub const SDLK_DELETE: i32 = '\x7F' as i32;
+pub const SDLK_CAPSLOCK: i32 = scancode_to_keycode(57);
+pub const SDLK_F1: i32 = scancode_to_keycode(58);
+pub const SDLK_F2: i32 = scancode_to_keycode(59);
+pub const SDLK_F3: i32 = scancode_to_keycode(60);
+pub const SDLK_F4: i32 = scancode_to_keycode(61);
+pub const SDLK_F5: i32 = scancode_to_keycode(62);
+pub const SDLK_F6: i32 = scancode_to_keycode(63);
+pub const SDLK_F7: i32 = scancode_to_keycode(64);
+pub const SDLK_F8: i32 = scancode_to_keycode(65);
+pub const SDLK_F9: i32 = scancode_to_keycode(66);
+pub const SDLK_F10: i32 = scancode_to_keycode(67);
+pub const SDLK_F11: i32 = scancode_to_keycode(68);
+pub const SDLK_F12: i32 = scancode_to_keycode(69);
+pub const SDLK_PRINTSCREEN: i32 = scancode_to_keycode(70);
+pub const SDLK_SCROLLLOCK: i32 = scancode_to_keycode(71);
+pub const SDLK_PAUSE: i32 = scancode_to_keycode(72);
+pub const SDLK_INSERT: i32 = scancode_to_keycode(73);
+pub const SDLK_HOME: i32 = scancode_to_keycode(74);
+pub const SDLK_PAGEUP: i32 = scancode_to_keycode(75);
+pub const SDLK_END: i32 = scancode_to_keycode(77);
+pub const SDLK_PAGEDOWN: i32 = scancode_to_keycode(78);
+pub const SDLK_RIGHT: i32 = scancode_to_keycode(79);
+pub const SDLK_LEFT: i32 = scancode_to_keycode(80);
+pub const SDLK_DOWN: i32 = scancode_to_keycode(81);
+pub const SDLK_UP: i32 = scancode_to_keycode(82);
+pub const SDLK_NUMLOCKCLEAR: i32 = scancode_to_keycode(83);
+pub const SDLK_KP_DIVIDE: i32 = scancode_to_keycode(84);
+pub const SDLK_KP_MULTIPLY: i32 = scancode_to_keycode(85);
+pub const SDLK_KP_MINUS: i32 = scancode_to_keycode(86);
+pub const SDLK_KP_PLUS: i32 = scancode_to_keycode(87);
+pub const SDLK_KP_ENTER: i32 = scancode_to_keycode(88);
+pub const SDLK_KP_1: i32 = scancode_to_keycode(89);
+pub const SDLK_KP_2: i32 = scancode_to_keycode(90);
+pub const SDLK_KP_3: i32 = scancode_to_keycode(91);
+pub const SDLK_KP_4: i32 = scancode_to_keycode(92);
+pub const SDLK_KP_5: i32 = scancode_to_keycode(93);
+pub const SDLK_KP_6: i32 = scancode_to_keycode(94);
+pub const SDLK_KP_7: i32 = scancode_to_keycode(95);
+pub const SDLK_KP_8: i32 = scancode_to_keycode(96);
+pub const SDLK_KP_9: i32 = scancode_to_keycode(97);
+pub const SDLK_KP_0: i32 = scancode_to_keycode(98);
+pub const SDLK_KP_PERIOD: i32 = scancode_to_keycode(99);
+pub const SDLK_APPLICATION: i32 = scancode_to_keycode(101);
+pub const SDLK_POWER: i32 = scancode_to_keycode(102);
+pub const SDLK_KP_EQUALS: i32 = scancode_to_keycode(103);
+pub const SDLK_F13: i32 = scancode_to_keycode(104);
+pub const SDLK_F14: i32 = scancode_to_keycode(105);
+pub const SDLK_F15: i32 = scancode_to_keycode(106);
+pub const SDLK_F16: i32 = scancode_to_keycode(107);
+pub const SDLK_F17: i32 = scancode_to_keycode(108);
+pub const SDLK_F18: i32 = scancode_to_keycode(109);
+pub const SDLK_F19: i32 = scancode_to_keycode(110);
+pub const SDLK_F20: i32 = scancode_to_keycode(111);
+pub const SDLK_F21: i32 = scancode_to_keycode(112);
+pub const SDLK_F22: i32 = scancode_to_keycode(113);
+pub const SDLK_F23: i32 = scancode_to_keycode(114);
+pub const SDLK_F24: i32 = scancode_to_keycode(115);
+pub const SDLK_EXECUTE: i32 = scancode_to_keycode(116);
+pub const SDLK_HELP: i32 = scancode_to_keycode(117);
+pub const SDLK_MENU: i32 = scancode_to_keycode(118);
+pub const SDLK_SELECT: i32 = scancode_to_keycode(119);
+pub const SDLK_STOP: i32 = scancode_to_keycode(120);
+pub const SDLK_AGAIN: i32 = scancode_to_keycode(121);
+pub const SDLK_UNDO: i32 = scancode_to_keycode(122);
+pub const SDLK_CUT: i32 = scancode_to_keycode(123);
+pub const SDLK_COPY: i32 = scancode_to_keycode(124);
+pub const SDLK_PASTE: i32 = scancode_to_keycode(125);
+pub const SDLK_FIND: i32 = scancode_to_keycode(126);
+pub const SDLK_MUTE: i32 = scancode_to_keycode(127);
+pub const SDLK_VOLUMEUP: i32 = scancode_to_keycode(128);
+pub const SDLK_VOLUMEDOWN: i32 = scancode_to_keycode(129);
+pub const SDLK_KP_COMMA: i32 = scancode_to_keycode(133);
+pub const SDLK_KP_EQUALSAS400: i32 = scancode_to_keycode(134);
+pub const SDLK_ALTERASE: i32 = scancode_to_keycode(153);
+pub const SDLK_SYSREQ: i32 = scancode_to_keycode(154);
+pub const SDLK_CANCEL: i32 = scancode_to_keycode(155);
+pub const SDLK_CLEAR: i32 = scancode_to_keycode(156);
+pub const SDLK_PRIOR: i32 = scancode_to_keycode(157);
+pub const SDLK_RETURN2: i32 = scancode_to_keycode(158);
+pub const SDLK_SEPARATOR: i32 = scancode_to_keycode(159);
+pub const SDLK_OUT: i32 = scancode_to_keycode(160);
+pub const SDLK_OPER: i32 = scancode_to_keycode(161);
+pub const SDLK_CLEARAGAIN: i32 = scancode_to_keycode(162);
+pub const SDLK_CRSEL: i32 = scancode_to_keycode(163);
+pub const SDLK_EXSEL: i32 = scancode_to_keycode(164);
+pub const SDLK_KP_00: i32 = scancode_to_keycode(176);
+pub const SDLK_KP_000: i32 = scancode_to_keycode(177);
+pub const SDLK_THOUSANDSSEPARATOR: i32 = scancode_to_keycode(178);
+pub const SDLK_DECIMALSEPARATOR: i32 = scancode_to_keycode(179);
+pub const SDLK_CURRENCYUNIT: i32 = scancode_to_keycode(180);
+pub const SDLK_CURRENCYSUBUNIT: i32 = scancode_to_keycode(181);
+pub const SDLK_KP_LEFTPAREN: i32 = scancode_to_keycode(182);
+pub const SDLK_KP_RIGHTPAREN: i32 = scancode_to_keycode(183);
+pub const SDLK_KP_LEFTBRACE: i32 = scancode_to_keycode(184);
+pub const SDLK_KP_RIGHTBRACE: i32 = scancode_to_keycode(185);
+pub const SDLK_KP_TAB: i32 = scancode_to_keycode(186);
+pub const SDLK_KP_BACKSPACE: i32 = scancode_to_keycode(187);
+pub const SDLK_KP_A: i32 = scancode_to_keycode(188);
+pub const SDLK_KP_B: i32 = scancode_to_keycode(189);
+pub const SDLK_KP_C: i32 = scancode_to_keycode(190);
+pub const SDLK_KP_D: i32 = scancode_to_keycode(191);
+pub const SDLK_KP_E: i32 = scancode_to_keycode(192);
+pub const SDLK_KP_F: i32 = scancode_to_keycode(193);
+pub const SDLK_KP_XOR: i32 = scancode_to_keycode(194);
+pub const SDLK_KP_POWER: i32 = scancode_to_keycode(195);
+pub const SDLK_KP_PERCENT: i32 = scancode_to_keycode(196);
+pub const SDLK_KP_LESS: i32 = scancode_to_keycode(197);
+pub const SDLK_KP_GREATER: i32 = scancode_to_keycode(198);
+pub const SDLK_KP_AMPERSAND: i32 = scancode_to_keycode(199);
+pub const SDLK_KP_DBLAMPERSAND: i32 = scancode_to_keycode(200);
+pub const SDLK_KP_VERTICALBAR: i32 = scancode_to_keycode(201);
+pub const SDLK_KP_DBLVERTICALBAR: i32 = scancode_to_keycode(202);
+pub const SDLK_KP_COLON: i32 = scancode_to_keycode(203);
+pub const SDLK_KP_HASH: i32 = scancode_to_keycode(204);
+pub const SDLK_KP_SPACE: i32 = scancode_to_keycode(205);
+pub const SDLK_KP_AT: i32 = scancode_to_keycode(206);
+pub const SDLK_KP_EXCLAM: i32 = scancode_to_keycode(207);
+pub const SDLK_KP_MEMSTORE: i32 = scancode_to_keycode(208);
+pub const SDLK_KP_MEMRECALL: i32 = scancode_to_keycode(209);
+pub const SDLK_KP_MEMCLEAR: i32 = scancode_to_keycode(210);
+pub const SDLK_KP_MEMADD: i32 = scancode_to_keycode(211);
+pub const SDLK_KP_MEMSUBTRACT: i32 = scancode_to_keycode(212);
+pub const SDLK_KP_MEMMULTIPLY: i32 = scancode_to_keycode(213);
+pub const SDLK_KP_MEMDIVIDE: i32 = scancode_to_keycode(214);
+pub const SDLK_KP_PLUSMINUS: i32 = scancode_to_keycode(215);
+pub const SDLK_KP_CLEAR: i32 = scancode_to_keycode(216);
+pub const SDLK_KP_CLEARENTRY: i32 = scancode_to_keycode(217);
+pub const SDLK_KP_BINARY: i32 = scancode_to_keycode(218);
+pub const SDLK_KP_OCTAL: i32 = scancode_to_keycode(219);
+pub const SDLK_KP_DECIMAL: i32 = scancode_to_keycode(220);
+pub const SDLK_KP_HEXADECIMAL: i32 = scancode_to_keycode(221);
+pub const SDLK_LCTRL: i32 = scancode_to_keycode(224);
+pub const SDLK_LSHIFT: i32 = scancode_to_keycode(225);
+pub const SDLK_LALT: i32 = scancode_to_keycode(226);
+pub const SDLK_LGUI: i32 = scancode_to_keycode(227);
+pub const SDLK_RCTRL: i32 = scancode_to_keycode(228);
+pub const SDLK_RSHIFT: i32 = scancode_to_keycode(229);
+pub const SDLK_RALT: i32 = scancode_to_keycode(230);
+pub const SDLK_RGUI: i32 = scancode_to_keycode(231);
+pub const SDLK_MODE: i32 = scancode_to_keycode(257);
+pub const SDLK_AUDIONEXT: i32 = scancode_to_keycode(258);
+pub const SDLK_AUDIOPREV: i32 = scancode_to_keycode(259);
+pub const SDLK_AUDIOSTOP: i32 = scancode_to_keycode(260);
+pub const SDLK_AUDIOPLAY: i32 = scancode_to_keycode(261);
+pub const SDLK_AUDIOMUTE: i32 = scancode_to_keycode(262);
+pub const SDLK_MEDIASELECT: i32 = scancode_to_keycode(263);
+pub const SDLK_WWW: i32 = scancode_to_keycode(264);
+pub const SDLK_MAIL: i32 = scancode_to_keycode(265);
+pub const SDLK_CALCULATOR: i32 = scancode_to_keycode(266);
+pub const SDLK_COMPUTER: i32 = scancode_to_keycode(267);
+pub const SDLK_AC_SEARCH: i32 = scancode_to_keycode(268);
+pub const SDLK_AC_HOME: i32 = scancode_to_keycode(269);
+pub const SDLK_AC_BACK: i32 = scancode_to_keycode(270);
+pub const SDLK_AC_FORWARD: i32 = scancode_to_keycode(271);
+pub const SDLK_AC_STOP: i32 = scancode_to_keycode(272);
+pub const SDLK_AC_REFRESH: i32 = scancode_to_keycode(273);
+pub const SDLK_AC_BOOKMARKS: i32 = scancode_to_keycode(274);
+pub const SDLK_BRIGHTNESSDOWN: i32 = scancode_to_keycode(275);
+pub const SDLK_BRIGHTNESSUP: i32 = scancode_to_keycode(276);
+pub const SDLK_DISPLAYSWITCH: i32 = scancode_to_keycode(277);
+pub const SDLK_KBDILLUMTOGGLE: i32 = scancode_to_keycode(278);
+pub const SDLK_KBDILLUMDOWN: i32 = scancode_to_keycode(279);
+pub const SDLK_KBDILLUMUP: i32 = scancode_to_keycode(280);
+pub const SDLK_EJECT: i32 = scancode_to_keycode(281);
+pub const SDLK_SLEEP: i32 = scancode_to_keycode(282);
+pub const SDLK_APP1: i32 = scancode_to_keycode(283);
+pub const SDLK_APP2: i32 = scancode_to_keycode(284);
+pub const SDLK_AUDIOREWIND: i32 = scancode_to_keycode(285);
+pub const SDLK_AUDIOFASTFORWARD: i32 = scancode_to_keycode(286);
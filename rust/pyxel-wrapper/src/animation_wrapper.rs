@@ -0,0 +1,115 @@
+use pyo3::prelude::*;
+
+use crate::image_wrapper::Image;
+
+type FrameTuple = (u32, u32, u32, u32, u32);
+type TagTuple = (String, u32, u32, pyxel::AnimationMode);
+
+#[pyclass]
+#[derive(Clone)]
+pub struct Animation {
+    pub(crate) inner: pyxel::SharedAnimation,
+}
+
+impl Animation {
+    pub fn wrap(inner: pyxel::SharedAnimation) -> Self {
+        Self { inner }
+    }
+}
+
+#[pymethods]
+impl Animation {
+    #[new]
+    pub fn new(img: &PyAny) -> PyResult<Self> {
+        let imgsrc = cast_pyany! {
+            img,
+            (u32, { pyxel::ImageSource::Index(img) }),
+            (Image, { pyxel::ImageSource::Image(img.inner) })
+        };
+        Ok(Animation::wrap(pyxel::Animation::new(imgsrc)))
+    }
+
+    #[getter]
+    pub fn imgsrc(&self, py: Python) -> PyObject {
+        let animation = self.inner.lock();
+        match &animation.imgsrc {
+            pyxel::ImageSource::Index(index) => index.into_py(py),
+            pyxel::ImageSource::Image(image) => Image::wrap(image.clone()).into_py(py),
+        }
+    }
+
+    #[setter]
+    pub fn set_imgsrc(&self, img: &PyAny) -> PyResult<()> {
+        let imgsrc = cast_pyany! {
+            img,
+            (u32, { pyxel::ImageSource::Index(img) }),
+            (Image, { pyxel::ImageSource::Image(img.inner) })
+        };
+        self.inner.lock().imgsrc = imgsrc;
+        Ok(())
+    }
+
+    #[getter]
+    pub fn frames(&self) -> Vec<FrameTuple> {
+        self.inner
+            .lock()
+            .frames
+            .iter()
+            .map(|frame| (frame.u, frame.v, frame.width, frame.height, frame.duration))
+            .collect()
+    }
+
+    #[setter]
+    pub fn set_frames(&self, frames: Vec<FrameTuple>) {
+        let mut animation = self.inner.lock();
+        animation.frames.clear();
+        for (u, v, w, h, duration) in frames {
+            animation.add_frame(u, v, w, h, duration);
+        }
+    }
+
+    #[getter]
+    pub fn tags(&self) -> Vec<TagTuple> {
+        self.inner
+            .lock()
+            .tags
+            .iter()
+            .map(|tag| (tag.name.clone(), tag.start, tag.end, tag.mode))
+            .collect()
+    }
+
+    #[getter]
+    pub fn get_mode(&self) -> pyxel::AnimationMode {
+        self.inner.lock().mode
+    }
+
+    #[setter]
+    pub fn set_mode(&self, mode: pyxel::AnimationMode) {
+        self.inner.lock().mode = mode;
+    }
+
+    pub fn add_frame(&self, u: u32, v: u32, w: u32, h: u32, duration: u32) {
+        self.inner.lock().add_frame(u, v, w, h, duration);
+    }
+
+    pub fn add_tag(&self, name: &str, start: u32, end: u32, mode: Option<pyxel::AnimationMode>) {
+        self.inner.lock().add_tag(name, start, end, mode);
+    }
+
+    pub fn remove_tag(&self, name: &str) {
+        self.inner.lock().remove_tag(name);
+    }
+
+    pub fn duration(&self, tag: Option<&str>) -> u32 {
+        self.inner.lock().duration(tag)
+    }
+
+    pub fn frame_index(&self, t: u32, tag: Option<&str>) -> Option<usize> {
+        self.inner.lock().frame_index(t, tag)
+    }
+}
+
+pub fn add_animation_class(m: &PyModule) -> PyResult<()> {
+    m.add_class::<Animation>()?;
+    Ok(())
+}
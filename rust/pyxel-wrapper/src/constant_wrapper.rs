@@ -23,6 +23,13 @@ pub fn add_module_constants(m: &PyModule) -> PyResult<()> {
     add_constant!(NUM_TILEMAPS)?;
     add_constant!(TILEMAP_SIZE)?;
     add_constant!(TILE_SIZE)?;
+    add_constant!(TILE_FLIP_H)?;
+    add_constant!(TILE_FLIP_V)?;
+    add_constant!(TILE_ROTATE_90)?;
+    add_constant!(NUM_ANIMATIONS)?;
+    add_constant!(ANIM_LOOP)?;
+    add_constant!(ANIM_PINGPONG)?;
+    add_constant!(ANIM_ONCE)?;
     add_constant!(COLOR_BLACK)?;
     add_constant!(COLOR_NAVY)?;
     add_constant!(COLOR_PURPLE)?;
@@ -41,6 +48,16 @@ pub fn add_module_constants(m: &PyModule) -> PyResult<()> {
     add_constant!(COLOR_PEACH)?;
     add_constant!(FONT_WIDTH)?;
     add_constant!(FONT_HEIGHT)?;
+    add_constant!(TEXT_ALIGN_LEFT)?;
+    add_constant!(TEXT_ALIGN_CENTER)?;
+    add_constant!(TEXT_ALIGN_RIGHT)?;
+    add_constant!(SCALE_INTEGER)?;
+    add_constant!(SCALE_FIT)?;
+    add_constant!(SCALE_STRETCH)?;
+    add_constant!(SCALE_FIXED)?;
+    add_constant!(DITHER_NONE)?;
+    add_constant!(DITHER_FLOYD_STEINBERG)?;
+    add_constant!(DITHER_ORDERED)?;
 
     add_constant!(NUM_CHANNELS)?;
     add_constant!(NUM_TONES)?;
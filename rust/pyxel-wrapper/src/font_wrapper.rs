@@ -0,0 +1,39 @@
+use pyo3::prelude::*;
+
+use crate::utils::to_python_error;
+
+#[pyclass]
+#[derive(Clone)]
+pub struct Font {
+    pub(crate) inner: pyxel::SharedFont,
+}
+
+impl Font {
+    pub fn wrap(inner: pyxel::SharedFont) -> Self {
+        Self { inner }
+    }
+}
+
+#[pymethods]
+impl Font {
+    #[new]
+    pub fn new(filename: &str) -> PyResult<Self> {
+        pyxel::Font::try_from_bdf(filename)
+            .map(Self::wrap)
+            .map_err(to_python_error)
+    }
+
+    #[getter]
+    pub fn line_height(&self) -> i32 {
+        self.inner.lock().line_height()
+    }
+
+    pub fn text_width(&self, s: &str) -> i32 {
+        self.inner.lock().text_width(s)
+    }
+}
+
+pub fn add_font_class(m: &PyModule) -> PyResult<()> {
+    m.add_class::<Font>()?;
+    Ok(())
+}
@@ -2,9 +2,12 @@ use std::sync::Once;
 
 use pyo3::prelude::*;
 
-use crate::image_wrapper::Image;
+use crate::animation_wrapper::Animation;
+use crate::font_wrapper::Font;
+use crate::image_wrapper::{check_dither_matrix, scanline_matrices, Image};
 use crate::pyxel_singleton::pyxel;
 use crate::tilemap_wrapper::Tilemap;
+use crate::utils::to_python_error;
 
 static IMAGE_ONCE: Once = Once::new();
 static TILEMAP_ONCE: Once = Once::new();
@@ -34,22 +37,96 @@ fn camera(x: Option<f64>, y: Option<f64>) -> PyResult<()> {
 }
 
 #[pyfunction]
-fn pal(col1: Option<pyxel::Color>, col2: Option<pyxel::Color>) -> PyResult<()> {
+#[pyo3(text_signature = "(col1, col2, *, screen)")]
+fn pal(
+    col1: Option<pyxel::Color>,
+    col2: Option<pyxel::Color>,
+    screen: Option<bool>,
+) -> PyResult<()> {
+    let screen = screen.unwrap_or(false);
     if let (Some(col1), Some(col2)) = (col1, col2) {
-        pyxel().pal(col1, col2);
+        if screen {
+            pyxel().screen_pal(col1, col2);
+        } else {
+            pyxel().pal(col1, col2);
+        }
     } else if (col1, col2) == (None, None) {
-        pyxel().pal0();
+        if screen {
+            pyxel().screen_pal0();
+        } else {
+            pyxel().pal0();
+        }
     } else {
         python_type_error!("pal() takes 0 or 2 arguments");
     }
     Ok(())
 }
 
+#[pyfunction]
+fn store_palette(name: &str) {
+    pyxel().store_palette(name);
+}
+
+#[pyfunction]
+fn load_palette(name: &str) -> PyResult<()> {
+    pyxel().load_palette(name).map_err(to_python_error)
+}
+
+#[pyfunction]
+fn lerp_palette(name1: &str, name2: &str, t: f64) -> PyResult<()> {
+    pyxel()
+        .lerp_palette(name1, name2, t)
+        .map_err(to_python_error)
+}
+
 #[pyfunction]
 fn dither(alpha: f32) {
     pyxel().dither(alpha);
 }
 
+#[pyfunction]
+fn dither_matrix(matrix: Option<Vec<f32>>) -> PyResult<()> {
+    if let Some(matrix) = matrix {
+        check_dither_matrix(&matrix)?;
+        pyxel().dither_matrix(&matrix);
+    } else {
+        pyxel().dither_matrix0();
+    }
+    Ok(())
+}
+
+#[pyfunction]
+fn fillp(pattern: Option<u16>, col2: Option<pyxel::Color>) -> PyResult<()> {
+    if let Some(pattern) = pattern {
+        pyxel().fillp(pattern, col2);
+    } else if col2.is_none() {
+        pyxel().fillp0();
+    } else {
+        python_type_error!("fillp() takes a pattern when col2 is given");
+    }
+    Ok(())
+}
+
+#[pyfunction]
+fn blend(mask: Option<pyxel::Color>, table: Option<&PyAny>) -> PyResult<()> {
+    match (mask, table) {
+        (Some(mask), None) => pyxel().blend_mask(mask),
+        (None, Some(table)) => {
+            cast_pyany! {
+                table,
+                (u32, {
+                    let image = pyxel().images.lock()[table as usize].clone();
+                    pyxel().blend_table(image);
+                }),
+                (Image, { pyxel().blend_table(table.inner); })
+            }
+        }
+        (None, None) => pyxel().blend0(),
+        (Some(_), Some(_)) => python_type_error!("blend() takes either mask or table"),
+    }
+    Ok(())
+}
+
 #[pyfunction]
 fn cls(col: pyxel::Color) {
     pyxel().cls(col);
@@ -110,12 +187,48 @@ fn trib(x1: f64, y1: f64, x2: f64, y2: f64, x3: f64, y3: f64, col: pyxel::Color)
     pyxel().trib(x1, y1, x2, y2, x3, y3, col);
 }
 
+#[pyfunction]
+fn poly(points: Vec<(f64, f64)>, col: pyxel::Color) {
+    pyxel().poly(&points, col);
+}
+
+#[pyfunction]
+fn polyb(points: Vec<(f64, f64)>, col: pyxel::Color) {
+    pyxel().polyb(&points, col);
+}
+
+#[pyfunction]
+fn thick_line(x1: f64, y1: f64, x2: f64, y2: f64, thickness: f64, col: pyxel::Color) {
+    pyxel().thick_line(x1, y1, x2, y2, thickness, col);
+}
+
+#[pyfunction]
+fn rrect(x: f64, y: f64, w: f64, h: f64, r: f64, col: pyxel::Color) {
+    pyxel().rrect(x, y, w, h, r, col);
+}
+
+#[pyfunction]
+fn rrectb(x: f64, y: f64, w: f64, h: f64, r: f64, col: pyxel::Color) {
+    pyxel().rrectb(x, y, w, h, r, col);
+}
+
+#[pyfunction]
+fn arc(x: f64, y: f64, r: f64, start: f64, end: f64, col: pyxel::Color) {
+    pyxel().arc(x, y, r, start, end, col);
+}
+
+#[pyfunction]
+fn pie(x: f64, y: f64, r: f64, start: f64, end: f64, col: pyxel::Color) {
+    pyxel().pie(x, y, r, start, end, col);
+}
+
 #[pyfunction]
 fn fill(x: f64, y: f64, col: pyxel::Color) {
     pyxel().fill(x, y, col);
 }
 
 #[pyfunction]
+#[pyo3(text_signature = "(x, y, img, u, v, w, h, colkey, *, rotate, scale)")]
 fn blt(
     x: f64,
     y: f64,
@@ -125,11 +238,16 @@ fn blt(
     w: f64,
     h: f64,
     colkey: Option<pyxel::Color>,
+    rotate: Option<f64>,
+    scale: Option<f64>,
 ) -> PyResult<()> {
     cast_pyany! {
         img,
-        (u32, { pyxel().blt(x, y, img, u, v, w, h, colkey); }),
-        (Image, { pyxel().screen.lock().blt(x, y, img.inner, u, v, w, h, colkey); })
+        (u32, { pyxel().blt(x, y, img, u, v, w, h, colkey, rotate, scale); }),
+        (Image, { pyxel()
+                .screen
+                .lock()
+                .blt(x, y, img.inner, u, v, w, h, colkey, rotate, scale); })
     }
     Ok(())
 }
@@ -154,8 +272,98 @@ fn bltm(
 }
 
 #[pyfunction]
-fn text(x: f64, y: f64, s: &str, col: pyxel::Color) {
-    pyxel().text(x, y, s, col);
+#[pyo3(text_signature = "(x, y, anim, t, tag, colkey, *, flip_h, flip_v)")]
+fn blt_animation(
+    x: f64,
+    y: f64,
+    anim: &PyAny,
+    t: u32,
+    tag: Option<&str>,
+    colkey: Option<pyxel::Color>,
+    flip_h: Option<bool>,
+    flip_v: Option<bool>,
+) -> PyResult<()> {
+    let flip_h = flip_h.unwrap_or(false);
+    let flip_v = flip_v.unwrap_or(false);
+    cast_pyany! {
+        anim,
+        (u32, { pyxel().blt_animation(x, y, anim, t, tag, colkey, flip_h, flip_v); }),
+        (Animation, {
+            pyxel()
+                .screen
+                .lock()
+                .blt_animation(x, y, anim.inner, t, tag, colkey, flip_h, flip_v);
+        })
+    }
+    Ok(())
+}
+
+#[pyfunction]
+#[pyo3(text_signature = "(x, y, tm, w, h, matrix, colkey, *, wrap)")]
+fn bltm_affine(
+    x: f64,
+    y: f64,
+    tm: &PyAny,
+    w: f64,
+    h: f64,
+    matrix: &PyAny,
+    colkey: Option<pyxel::Color>,
+    wrap: Option<bool>,
+) -> PyResult<()> {
+    let matrices = scanline_matrices(matrix, h)?;
+    let scanline = |y: i32| matrices[y as usize];
+    let wrap = wrap.unwrap_or(false);
+    cast_pyany! {
+        tm,
+        (u32, { pyxel().bltm_scanline(x, y, tm, w, h, scanline, colkey, wrap); }),
+        (Tilemap, {
+            pyxel()
+                .screen
+                .lock()
+                .bltm_scanline(x, y, tm.inner, w, h, scanline, colkey, wrap);
+        })
+    }
+    Ok(())
+}
+
+#[pyfunction]
+fn text(x: f64, y: f64, s: &str, col: pyxel::Color, font: Option<Font>) {
+    pyxel().text(x, y, s, col, font.map(|font| font.inner));
+}
+
+#[pyfunction]
+fn text_box(
+    x: f64,
+    y: f64,
+    w: f64,
+    h: f64,
+    s: &str,
+    col: pyxel::Color,
+    font: Option<Font>,
+    align: Option<pyxel::TextAlign>,
+) {
+    pyxel().text_box(
+        x,
+        y,
+        w,
+        h,
+        s,
+        col,
+        font.map(|font| font.inner),
+        align.unwrap_or(pyxel::TEXT_ALIGN_LEFT),
+    );
+}
+
+#[pyfunction]
+fn text_size(s: &str, font: Option<Font>) -> (i32, i32) {
+    let font = font.as_ref().map(|font| font.inner.lock());
+    pyxel::text_size(s, font.as_deref())
+}
+
+#[pyfunction]
+fn wrap_text(s: &str, w: i32, font: Option<Font>) -> String {
+    let font = font.as_ref().map(|font| font.inner.lock());
+    pyxel::wrap_text(s, w, font.as_deref())
 }
 
 #[pyfunction]
@@ -180,7 +388,13 @@ pub fn add_graphics_functions(m: &PyModule) -> PyResult<()> {
     m.add_function(wrap_pyfunction!(clip, m)?)?;
     m.add_function(wrap_pyfunction!(camera, m)?)?;
     m.add_function(wrap_pyfunction!(pal, m)?)?;
+    m.add_function(wrap_pyfunction!(store_palette, m)?)?;
+    m.add_function(wrap_pyfunction!(load_palette, m)?)?;
+    m.add_function(wrap_pyfunction!(lerp_palette, m)?)?;
     m.add_function(wrap_pyfunction!(dither, m)?)?;
+    m.add_function(wrap_pyfunction!(dither_matrix, m)?)?;
+    m.add_function(wrap_pyfunction!(fillp, m)?)?;
+    m.add_function(wrap_pyfunction!(blend, m)?)?;
     m.add_function(wrap_pyfunction!(cls, m)?)?;
     m.add_function(wrap_pyfunction!(pget, m)?)?;
     m.add_function(wrap_pyfunction!(pset, m)?)?;
@@ -193,10 +407,22 @@ pub fn add_graphics_functions(m: &PyModule) -> PyResult<()> {
     m.add_function(wrap_pyfunction!(ellib, m)?)?;
     m.add_function(wrap_pyfunction!(tri, m)?)?;
     m.add_function(wrap_pyfunction!(trib, m)?)?;
+    m.add_function(wrap_pyfunction!(poly, m)?)?;
+    m.add_function(wrap_pyfunction!(polyb, m)?)?;
+    m.add_function(wrap_pyfunction!(thick_line, m)?)?;
+    m.add_function(wrap_pyfunction!(rrect, m)?)?;
+    m.add_function(wrap_pyfunction!(rrectb, m)?)?;
+    m.add_function(wrap_pyfunction!(arc, m)?)?;
+    m.add_function(wrap_pyfunction!(pie, m)?)?;
     m.add_function(wrap_pyfunction!(fill, m)?)?;
     m.add_function(wrap_pyfunction!(blt, m)?)?;
     m.add_function(wrap_pyfunction!(bltm, m)?)?;
+    m.add_function(wrap_pyfunction!(bltm_affine, m)?)?;
+    m.add_function(wrap_pyfunction!(blt_animation, m)?)?;
     m.add_function(wrap_pyfunction!(text, m)?)?;
+    m.add_function(wrap_pyfunction!(text_box, m)?)?;
+    m.add_function(wrap_pyfunction!(text_size, m)?)?;
+    m.add_function(wrap_pyfunction!(wrap_text, m)?)?;
 
     // Deprecated functions
     m.add_function(wrap_pyfunction!(image, m)?)?;
@@ -1,7 +1,56 @@
 use pyo3::prelude::*;
 
+use crate::animation_wrapper::Animation;
+use crate::font_wrapper::Font;
 use crate::pyxel_singleton::pyxel;
 use crate::tilemap_wrapper::Tilemap;
+use crate::utils::to_python_error;
+
+pub fn check_dither_matrix(matrix: &[f32]) -> PyResult<()> {
+    let size = matrix.len().isqrt();
+    if size == 0 || size * size != matrix.len() {
+        return Err(pyo3::exceptions::PyValueError::new_err(
+            "dither matrix must have a square number of elements",
+        ));
+    }
+    Ok(())
+}
+
+fn check_num_colors(num_colors: Option<u32>) -> PyResult<()> {
+    if let Some(num_colors) = num_colors {
+        if !(1..=pyxel::MAX_COLORS).contains(&num_colors) {
+            return Err(pyo3::exceptions::PyValueError::new_err(format!(
+                "num_colors must be between 1 and {}",
+                pyxel::MAX_COLORS
+            )));
+        }
+    }
+    Ok(())
+}
+
+type AffineMatrixArg = (f64, f64, f64, f64, f64, f64);
+
+pub fn scanline_matrices(
+    matrix: &PyAny,
+    height: f64,
+) -> PyResult<Vec<Option<pyxel::AffineMatrix>>> {
+    let to_matrix =
+        |(a, b, c, d, tx, ty): AffineMatrixArg| pyxel::AffineMatrix::new(a, b, c, d, tx, ty);
+    let num_rows = height.max(0.0).round() as usize;
+    if matrix.is_callable() {
+        (0..num_rows)
+            .map(|y| {
+                let matrix: Option<AffineMatrixArg> = matrix.call1((y,))?.extract()?;
+                Ok(matrix.map(to_matrix))
+            })
+            .collect()
+    } else {
+        let Ok(matrix) = matrix.extract::<AffineMatrixArg>() else {
+            python_type_error!("matrix must be a tuple of 6 floats or a callable");
+        };
+        Ok(vec![Some(to_matrix(matrix)); num_rows])
+    }
+}
 
 #[pyclass]
 #[derive(Clone)]
@@ -23,9 +72,17 @@ impl Image {
     }
 
     #[staticmethod]
-    #[pyo3(text_signature = "(filename, *, incl_colors)")]
-    pub fn from_image(filename: &str, incl_colors: Option<bool>) -> Self {
-        Self::wrap(pyxel::Image::from_image(filename, incl_colors))
+    #[pyo3(text_signature = "(filename, *, incl_colors, dither, num_colors)")]
+    pub fn from_image(
+        filename: &str,
+        incl_colors: Option<bool>,
+        dither: Option<pyxel::DitherMode>,
+        num_colors: Option<u32>,
+    ) -> PyResult<Self> {
+        check_num_colors(num_colors)?;
+        pyxel::Image::try_from_image(filename, incl_colors, dither, num_colors)
+            .map(Self::wrap)
+            .map_err(to_python_error)
     }
 
     #[getter]
@@ -54,13 +111,36 @@ impl Image {
         self.inner.lock().set(x, y, &data);
     }
 
-    #[pyo3(text_signature = "($self, x, y, filename, *, incl_colors)")]
-    pub fn load(&self, x: i32, y: i32, filename: &str, incl_colors: Option<bool>) {
-        self.inner.lock().load(x, y, filename, incl_colors);
+    #[pyo3(text_signature = "($self, x, y, filename, *, incl_colors, dither, num_colors)")]
+    pub fn load(
+        &self,
+        x: i32,
+        y: i32,
+        filename: &str,
+        incl_colors: Option<bool>,
+        dither: Option<pyxel::DitherMode>,
+        num_colors: Option<u32>,
+    ) -> PyResult<()> {
+        check_num_colors(num_colors)?;
+        self.inner
+            .lock()
+            .try_load(x, y, filename, incl_colors, dither, num_colors)
+            .map_err(to_python_error)
     }
 
-    pub fn save(&self, filename: &str, scale: u32) {
-        self.inner.lock().save(filename, scale);
+    #[pyo3(text_signature = "($self, filename, scale, *, colkey, indexed, rect)")]
+    pub fn save(
+        &self,
+        filename: &str,
+        scale: u32,
+        colkey: Option<pyxel::Color>,
+        indexed: Option<bool>,
+        rect: Option<(u32, u32, u32, u32)>,
+    ) -> PyResult<()> {
+        self.inner
+            .lock()
+            .try_save(filename, scale, colkey, indexed, rect)
+            .map_err(to_python_error)
     }
 
     pub fn clip(
@@ -106,6 +186,46 @@ impl Image {
         self.inner.lock().dither(alpha);
     }
 
+    pub fn dither_matrix(&self, matrix: Option<Vec<f32>>) -> PyResult<()> {
+        if let Some(matrix) = matrix {
+            check_dither_matrix(&matrix)?;
+            self.inner.lock().dither_matrix(&matrix);
+        } else {
+            self.inner.lock().dither_matrix0();
+        }
+        Ok(())
+    }
+
+    pub fn fillp(&self, pattern: Option<u16>, col2: Option<pyxel::Color>) -> PyResult<()> {
+        if let Some(pattern) = pattern {
+            self.inner.lock().fillp(pattern, col2);
+        } else if col2.is_none() {
+            self.inner.lock().fillp0();
+        } else {
+            python_type_error!("fillp() takes a pattern when col2 is given");
+        }
+        Ok(())
+    }
+
+    pub fn blend(&self, mask: Option<pyxel::Color>, table: Option<&PyAny>) -> PyResult<()> {
+        match (mask, table) {
+            (Some(mask), None) => self.inner.lock().blend_mask(mask),
+            (None, Some(table)) => {
+                cast_pyany! {
+                    table,
+                    (u32, {
+                        let image = pyxel().images.lock()[table as usize].clone();
+                        self.inner.lock().blend_table(image);
+                    }),
+                    (Image, { self.inner.lock().blend_table(table.inner); })
+                }
+            }
+            (None, None) => self.inner.lock().blend0(),
+            (Some(_), Some(_)) => python_type_error!("blend() takes either mask or table"),
+        }
+        Ok(())
+    }
+
     pub fn cls(&self, col: pyxel::Color) {
         self.inner.lock().cls(col);
     }
@@ -154,10 +274,72 @@ impl Image {
         self.inner.lock().trib(x1, y1, x2, y2, x3, y3, col);
     }
 
+    pub fn poly(&self, points: Vec<(f64, f64)>, col: pyxel::Color) {
+        self.inner.lock().poly(&points, col);
+    }
+
+    pub fn polyb(&self, points: Vec<(f64, f64)>, col: pyxel::Color) {
+        self.inner.lock().polyb(&points, col);
+    }
+
+    pub fn thick_line(
+        &self,
+        x1: f64,
+        y1: f64,
+        x2: f64,
+        y2: f64,
+        thickness: f64,
+        col: pyxel::Color,
+    ) {
+        self.inner.lock().thick_line(x1, y1, x2, y2, thickness, col);
+    }
+
+    pub fn rrect(&self, x: f64, y: f64, w: f64, h: f64, r: f64, col: pyxel::Color) {
+        self.inner.lock().rrect(x, y, w, h, r, col);
+    }
+
+    pub fn rrectb(&self, x: f64, y: f64, w: f64, h: f64, r: f64, col: pyxel::Color) {
+        self.inner.lock().rrectb(x, y, w, h, r, col);
+    }
+
+    pub fn arc(&self, x: f64, y: f64, r: f64, start: f64, end: f64, col: pyxel::Color) {
+        self.inner.lock().arc(x, y, r, start, end, col);
+    }
+
+    pub fn pie(&self, x: f64, y: f64, r: f64, start: f64, end: f64, col: pyxel::Color) {
+        self.inner.lock().pie(x, y, r, start, end, col);
+    }
+
     pub fn fill(&self, x: f64, y: f64, col: pyxel::Color) {
         self.inner.lock().fill(x, y, col);
     }
 
+    pub fn fill_boundary(&self, x: f64, y: f64, bcol: pyxel::Color, col: pyxel::Color) {
+        self.inner.lock().fill_boundary(x, y, bcol, col);
+    }
+
+    pub fn fill_pattern(
+        &self,
+        x: f64,
+        y: f64,
+        img: &PyAny,
+        u: f64,
+        v: f64,
+        w: f64,
+        h: f64,
+    ) -> PyResult<()> {
+        cast_pyany! {
+            img,
+            (u32, {
+                let image = pyxel().images.lock()[img as usize].clone();
+                self.inner.lock().fill_pattern(x, y, image, u, v, w, h);
+            }),
+            (Image, { self.inner.lock().fill_pattern(x, y, img.inner, u, v, w, h); })
+        }
+        Ok(())
+    }
+
+    #[pyo3(text_signature = "($self, x, y, img, u, v, w, h, colkey, *, rotate, scale)")]
     pub fn blt(
         &self,
         x: f64,
@@ -168,14 +350,16 @@ impl Image {
         w: f64,
         h: f64,
         colkey: Option<pyxel::Color>,
+        rotate: Option<f64>,
+        scale: Option<f64>,
     ) -> PyResult<()> {
         cast_pyany! {
             img,
             (u32, {
                 let image = pyxel().images.lock()[img as usize].clone();
-                self.inner.lock().blt(x, y, image, u, v, w, h, colkey);
+                self.inner.lock().blt(x, y, image, u, v, w, h, colkey, rotate, scale);
             }),
-            (Image, { self.inner.lock().blt(x, y, img.inner, u, v, w, h, colkey); })
+            (Image, { self.inner.lock().blt(x, y, img.inner, u, v, w, h, colkey, rotate, scale); })
         }
         Ok(())
     }
@@ -202,8 +386,86 @@ impl Image {
         Ok(())
     }
 
-    pub fn text(&self, x: f64, y: f64, s: &str, col: pyxel::Color) {
-        self.inner.lock().text(x, y, s, col);
+    #[pyo3(text_signature = "($self, x, y, anim, t, tag, colkey, *, flip_h, flip_v)")]
+    pub fn blt_animation(
+        &self,
+        x: f64,
+        y: f64,
+        anim: &PyAny,
+        t: u32,
+        tag: Option<&str>,
+        colkey: Option<pyxel::Color>,
+        flip_h: Option<bool>,
+        flip_v: Option<bool>,
+    ) -> PyResult<()> {
+        let flip_h = flip_h.unwrap_or(false);
+        let flip_v = flip_v.unwrap_or(false);
+        let animation = cast_pyany! {
+            anim,
+            (u32, { pyxel().animations.lock()[anim as usize].clone() }),
+            (Animation, { anim.inner })
+        };
+        self.inner
+            .lock()
+            .blt_animation(x, y, animation, t, tag, colkey, flip_h, flip_v);
+        Ok(())
+    }
+
+    #[pyo3(text_signature = "($self, x, y, tm, w, h, matrix, colkey, *, wrap)")]
+    pub fn bltm_affine(
+        &self,
+        x: f64,
+        y: f64,
+        tm: &PyAny,
+        w: f64,
+        h: f64,
+        matrix: &PyAny,
+        colkey: Option<pyxel::Color>,
+        wrap: Option<bool>,
+    ) -> PyResult<()> {
+        let matrices = scanline_matrices(matrix, h)?;
+        let scanline = |y: i32| matrices[y as usize];
+        let wrap = wrap.unwrap_or(false);
+        cast_pyany! {
+            tm,
+            (u32, {
+                let tilemap = pyxel().tilemaps.lock()[tm as usize].clone();
+                self.inner.lock().bltm_scanline(x, y, tilemap, w, h, scanline, colkey, wrap);
+            }),
+            (Tilemap, {
+                self.inner.lock().bltm_scanline(x, y, tm.inner, w, h, scanline, colkey, wrap);
+            })
+        }
+        Ok(())
+    }
+
+    pub fn text(&self, x: f64, y: f64, s: &str, col: pyxel::Color, font: Option<Font>) {
+        self.inner
+            .lock()
+            .text(x, y, s, col, font.map(|font| font.inner));
+    }
+
+    pub fn text_box(
+        &self,
+        x: f64,
+        y: f64,
+        w: f64,
+        h: f64,
+        s: &str,
+        col: pyxel::Color,
+        font: Option<Font>,
+        align: Option<pyxel::TextAlign>,
+    ) {
+        self.inner.lock().text_box(
+            x,
+            y,
+            w,
+            h,
+            s,
+            col,
+            font.map(|font| font.inner),
+            align.unwrap_or(pyxel::TEXT_ALIGN_LEFT),
+        );
     }
 }
 
@@ -1,6 +1,7 @@
 use pyo3::prelude::*;
 
 use crate::pyxel_singleton::pyxel;
+use crate::utils::to_python_error;
 
 #[pyfunction]
 fn btn(key: pyxel::Key) -> bool {
@@ -33,6 +34,33 @@ pub fn warp_mouse(x: f64, y: f64) {
     pyxel().warp_mouse(x, y);
 }
 
+#[pyfunction]
+fn start_recording() {
+    pyxel().start_recording();
+}
+
+#[pyfunction]
+fn stop_recording(filename: &str) -> PyResult<()> {
+    pyxel()
+        .try_stop_recording(filename)
+        .map_err(to_python_error)
+}
+
+#[pyfunction]
+fn start_replay(filename: &str) -> PyResult<()> {
+    pyxel().try_start_replay(filename).map_err(to_python_error)
+}
+
+#[pyfunction]
+fn stop_replay() {
+    pyxel().stop_replay();
+}
+
+#[pyfunction]
+fn is_replaying() -> bool {
+    pyxel().is_replaying()
+}
+
 pub fn add_input_functions(m: &PyModule) -> PyResult<()> {
     m.add_function(wrap_pyfunction!(btn, m)?)?;
     m.add_function(wrap_pyfunction!(btnp, m)?)?;
@@ -40,5 +68,10 @@ pub fn add_input_functions(m: &PyModule) -> PyResult<()> {
     m.add_function(wrap_pyfunction!(btnv, m)?)?;
     m.add_function(wrap_pyfunction!(mouse, m)?)?;
     m.add_function(wrap_pyfunction!(warp_mouse, m)?)?;
+    m.add_function(wrap_pyfunction!(start_recording, m)?)?;
+    m.add_function(wrap_pyfunction!(stop_recording, m)?)?;
+    m.add_function(wrap_pyfunction!(start_replay, m)?)?;
+    m.add_function(wrap_pyfunction!(stop_replay, m)?)?;
+    m.add_function(wrap_pyfunction!(is_replaying, m)?)?;
     Ok(())
 }
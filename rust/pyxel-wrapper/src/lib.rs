@@ -1,5 +1,6 @@
 #![warn(clippy::pedantic, clippy::cargo)]
 #![allow(
+    clippy::cast_possible_truncation,
     clippy::cast_possible_wrap,
     clippy::cast_sign_loss,
     clippy::many_single_char_names,
@@ -14,9 +15,11 @@
 
 #[macro_use]
 mod utils;
+mod animation_wrapper;
 mod audio_wrapper;
 mod channel_wrapper;
 mod constant_wrapper;
+mod font_wrapper;
 mod graphics_wrapper;
 mod image_wrapper;
 mod input_wrapper;
@@ -36,6 +39,8 @@ use pyo3::prelude::*;
 fn pyxel_wrapper(_py: Python, m: &PyModule) -> PyResult<()> {
     crate::image_wrapper::add_image_class(m)?;
     crate::tilemap_wrapper::add_tilemap_class(m)?;
+    crate::animation_wrapper::add_animation_class(m)?;
+    crate::font_wrapper::add_font_class(m)?;
     crate::channel_wrapper::add_channel_class(m)?;
     crate::sound_wrapper::add_sound_class(m)?;
     crate::music_wrapper::add_music_class(m)?;
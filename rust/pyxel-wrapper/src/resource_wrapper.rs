@@ -1,6 +1,8 @@
 use pyo3::prelude::*;
 
+use crate::animation_wrapper::Animation;
 use crate::pyxel_singleton::pyxel;
+use crate::utils::to_python_error;
 
 #[pyfunction]
 #[pyo3(
@@ -15,17 +17,19 @@ fn load(
     incl_colors: Option<bool>,
     incl_channels: Option<bool>,
     incl_tones: Option<bool>,
-) {
-    pyxel().load(
-        filename,
-        excl_images,
-        excl_tilemaps,
-        excl_sounds,
-        excl_musics,
-        incl_colors,
-        incl_channels,
-        incl_tones,
-    );
+) -> PyResult<()> {
+    pyxel()
+        .try_load(
+            filename,
+            excl_images,
+            excl_tilemaps,
+            excl_sounds,
+            excl_musics,
+            incl_colors,
+            incl_channels,
+            incl_tones,
+        )
+        .map_err(to_python_error)
 }
 
 #[pyfunction]
@@ -41,17 +45,34 @@ fn save(
     incl_colors: Option<bool>,
     incl_channels: Option<bool>,
     incl_tones: Option<bool>,
-) {
-    pyxel().save(
-        filename,
-        excl_images,
-        excl_tilemaps,
-        excl_sounds,
-        excl_musics,
-        incl_colors,
-        incl_channels,
-        incl_tones,
-    );
+) -> PyResult<()> {
+    pyxel()
+        .try_save(
+            filename,
+            excl_images,
+            excl_tilemaps,
+            excl_sounds,
+            excl_musics,
+            incl_colors,
+            incl_channels,
+            incl_tones,
+        )
+        .map_err(to_python_error)
+}
+
+#[pyfunction]
+#[pyo3(text_signature = "(filename, img, x, y, *, incl_colors)")]
+fn load_aseprite(
+    filename: &str,
+    img: u32,
+    x: u32,
+    y: u32,
+    incl_colors: Option<bool>,
+) -> PyResult<Animation> {
+    pyxel()
+        .try_load_aseprite(filename, img, x, y, incl_colors)
+        .map(Animation::wrap)
+        .map_err(to_python_error)
 }
 
 #[pyfunction]
@@ -60,8 +81,8 @@ fn screenshot(scale: Option<u32>) {
 }
 
 #[pyfunction]
-fn screencast(scale: Option<u32>) {
-    pyxel().screencast(scale);
+fn screencast(scale: Option<u32>) -> PyResult<()> {
+    pyxel().try_screencast(scale).map_err(to_python_error)
 }
 
 #[pyfunction]
@@ -72,6 +93,7 @@ fn reset_screencast() {
 pub fn add_resource_functions(m: &PyModule) -> PyResult<()> {
     m.add_function(wrap_pyfunction!(load, m)?)?;
     m.add_function(wrap_pyfunction!(save, m)?)?;
+    m.add_function(wrap_pyfunction!(load_aseprite, m)?)?;
     m.add_function(wrap_pyfunction!(screenshot, m)?)?;
     m.add_function(wrap_pyfunction!(screencast, m)?)?;
     m.add_function(wrap_pyfunction!(reset_screencast, m)?)?;
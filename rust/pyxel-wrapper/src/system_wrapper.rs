@@ -6,7 +6,9 @@ use pyxel::{Pyxel, PyxelCallback};
 #[cfg(not(target_os = "emscripten"))]
 use sysinfo::{Pid, System};
 
+use crate::image_wrapper::Image;
 use crate::pyxel_singleton::{pyxel, set_pyxel_instance};
+use crate::utils::to_python_error;
 
 #[pyfunction]
 #[pyo3(
@@ -106,6 +108,60 @@ fn screen_mode(scr: u32) {
     pyxel().screen_mode(scr);
 }
 
+#[pyfunction]
+fn resize(width: u32, height: u32) -> PyResult<()> {
+    if width == 0 || height == 0 {
+        return Err(pyo3::exceptions::PyValueError::new_err(
+            "width and height must be positive",
+        ));
+    }
+    pyxel().resize(width, height);
+    Ok(())
+}
+
+#[pyfunction]
+fn scale_mode(mode: pyxel::ScaleMode, scale: Option<f64>) -> PyResult<()> {
+    if scale.is_some_and(|scale| scale <= 0.0) {
+        return Err(pyo3::exceptions::PyValueError::new_err(
+            "scale must be positive",
+        ));
+    }
+    pyxel().scale_mode(mode, scale);
+    Ok(())
+}
+
+#[pyfunction]
+fn border_color(rgb: pyxel::Rgb24) {
+    pyxel().border_color(rgb);
+}
+
+#[pyfunction]
+fn border_image(img: Option<&PyAny>) -> PyResult<()> {
+    let Some(img) = img else {
+        pyxel().border_image(None);
+        return Ok(());
+    };
+    cast_pyany! {
+        img,
+        (u32, {
+            let image = pyxel().images.lock()[img as usize].clone();
+            pyxel().border_image(Some(image));
+        }),
+        (Image, { pyxel().border_image(Some(img.inner)); })
+    }
+    Ok(())
+}
+
+#[pyfunction]
+fn add_screen_shader(code: &str) -> PyResult<u32> {
+    pyxel().add_screen_shader(code).map_err(to_python_error)
+}
+
+#[pyfunction]
+fn screen_uniform(name: &str, value: f32) {
+    pyxel().screen_uniform(name, value);
+}
+
 #[cfg(not(target_os = "emscripten"))]
 #[pyfunction]
 fn process_exists(pid: u32) -> bool {
@@ -123,6 +179,12 @@ pub fn add_system_functions(m: &PyModule) -> PyResult<()> {
     m.add_function(wrap_pyfunction!(icon, m)?)?;
     m.add_function(wrap_pyfunction!(fullscreen, m)?)?;
     m.add_function(wrap_pyfunction!(screen_mode, m)?)?;
+    m.add_function(wrap_pyfunction!(resize, m)?)?;
+    m.add_function(wrap_pyfunction!(scale_mode, m)?)?;
+    m.add_function(wrap_pyfunction!(border_color, m)?)?;
+    m.add_function(wrap_pyfunction!(border_image, m)?)?;
+    m.add_function(wrap_pyfunction!(add_screen_shader, m)?)?;
+    m.add_function(wrap_pyfunction!(screen_uniform, m)?)?;
     #[cfg(not(target_os = "emscripten"))]
     m.add_function(wrap_pyfunction!(process_exists, m)?)?;
     Ok(())
@@ -1,15 +1,41 @@
 use std::sync::Once;
 
 use pyo3::prelude::*;
+use pyo3::types::{PyDict, PyList};
 
 use crate::image_wrapper::Image;
 use crate::pyxel_singleton::pyxel;
+use crate::utils::to_python_error;
 
 static IMAGE_ONCE: Once = Once::new();
 static SET_IMAGE_ONCE: Once = Once::new();
 static REFIMG_ONCE: Once = Once::new();
 static SET_REFIMG_ONCE: Once = Once::new();
 
+#[derive(FromPyObject)]
+pub enum TileArg {
+    Tile(pyxel::TileCoord, pyxel::TileCoord),
+    FlaggedTile(pyxel::TileCoord, pyxel::TileCoord, pyxel::TileFlags),
+}
+
+impl TileArg {
+    fn to_tile(&self) -> pyxel::Tile {
+        match *self {
+            Self::Tile(tile_x, tile_y) => (tile_x, tile_y, 0),
+            Self::FlaggedTile(tile_x, tile_y, flags) => (tile_x, tile_y, flags),
+        }
+    }
+}
+
+fn tile_to_object(py: Python, tile: pyxel::Tile) -> PyObject {
+    // Tiles without flags stay as pairs so that comparisons like tile == (1, 0) keep working
+    if tile.2 == 0 {
+        (tile.0, tile.1).to_object(py)
+    } else {
+        tile.to_object(py)
+    }
+}
+
 #[pyclass]
 #[derive(Clone)]
 pub struct Tilemap {
@@ -35,8 +61,62 @@ impl Tilemap {
     }
 
     #[staticmethod]
-    pub fn from_tmx(filename: &str, layer: u32) -> Self {
-        Self::wrap(pyxel::Tilemap::from_tmx(filename, layer))
+    pub fn from_tmx(filename: &str, layer: u32) -> PyResult<Self> {
+        pyxel::Tilemap::try_from_tmx(filename, layer)
+            .map(Self::wrap)
+            .map_err(to_python_error)
+    }
+
+    #[staticmethod]
+    pub fn tmx_data(py: Python, filename: &str) -> PyResult<PyObject> {
+        let tiled_map = pyxel::TiledMap::from_tmx(filename).map_err(to_python_error)?;
+        let layers = PyList::empty(py);
+        for layer in &tiled_map.layers {
+            let layer_dict = PyDict::new(py);
+            layer_dict.set_item("name", &layer.name)?;
+            layer_dict.set_item("group", &layer.group)?;
+            layer_dict.set_item("visible", layer.is_visible)?;
+            match &layer.kind {
+                pyxel::TiledLayerKind::Tile(index) => {
+                    layer_dict.set_item("type", "tile")?;
+                    layer_dict.set_item("index", index)?;
+                }
+                pyxel::TiledLayerKind::Object(objects) => {
+                    let object_list = PyList::empty(py);
+                    for object in objects {
+                        object_list.append(tiled_object_to_dict(py, object)?)?;
+                    }
+                    layer_dict.set_item("type", "object")?;
+                    layer_dict.set_item("objects", object_list)?;
+                }
+                pyxel::TiledLayerKind::Image(source) => {
+                    layer_dict.set_item("type", "image")?;
+                    layer_dict.set_item("image", source)?;
+                }
+            }
+            layer_dict.set_item(
+                "properties",
+                tiled_properties_to_dict(py, &layer.properties)?,
+            )?;
+            layers.append(layer_dict)?;
+        }
+        let tile_properties = PyDict::new(py);
+        for ((img, tile), properties) in &tiled_map.tile_properties {
+            tile_properties.set_item(
+                (img, tile_to_object(py, *tile)),
+                tiled_properties_to_dict(py, properties)?,
+            )?;
+        }
+        let map_dict = PyDict::new(py);
+        map_dict.set_item("width", tiled_map.width)?;
+        map_dict.set_item("height", tiled_map.height)?;
+        map_dict.set_item(
+            "properties",
+            tiled_properties_to_dict(py, &tiled_map.properties)?,
+        )?;
+        map_dict.set_item("layers", layers)?;
+        map_dict.set_item("tile_properties", tile_properties)?;
+        Ok(map_dict.to_object(py))
     }
 
     #[getter]
@@ -69,11 +149,43 @@ impl Tilemap {
         Ok(())
     }
 
+    #[getter]
+    pub fn tile_width(&self) -> u32 {
+        self.inner.lock().tile_width()
+    }
+
+    #[setter]
+    pub fn set_tile_width(&self, tile_width: u32) -> PyResult<()> {
+        if tile_width == 0 {
+            return Err(pyo3::exceptions::PyValueError::new_err(
+                "tile_width must be greater than 0",
+            ));
+        }
+        self.inner.lock().set_tile_width(tile_width);
+        Ok(())
+    }
+
+    #[getter]
+    pub fn tile_height(&self) -> u32 {
+        self.inner.lock().tile_height()
+    }
+
+    #[setter]
+    pub fn set_tile_height(&self, tile_height: u32) -> PyResult<()> {
+        if tile_height == 0 {
+            return Err(pyo3::exceptions::PyValueError::new_err(
+                "tile_height must be greater than 0",
+            ));
+        }
+        self.inner.lock().set_tile_height(tile_height);
+        Ok(())
+    }
+
     pub fn data_ptr(&self, py: Python) -> PyObject {
         let mut inner = self.inner.lock();
         let python_code = format!(
             "import ctypes; c_uint8_array = (ctypes.c_uint8 * {}).from_address({:p})",
-            inner.width() * inner.height(),
+            inner.width() * inner.height() * std::mem::size_of::<pyxel::Tile>() as u32,
             inner.data_ptr()
         );
         let locals = pyo3::types::PyDict::new(py);
@@ -85,8 +197,11 @@ impl Tilemap {
         self.inner.lock().set(x, y, &data);
     }
 
-    pub fn load(&self, x: i32, y: i32, filename: &str, layer: u32) {
-        self.inner.lock().load(x, y, filename, layer);
+    pub fn load(&self, x: i32, y: i32, filename: &str, layer: u32) -> PyResult<()> {
+        self.inner
+            .lock()
+            .try_load(x, y, filename, layer)
+            .map_err(to_python_error)
     }
 
     pub fn clip(
@@ -117,56 +232,96 @@ impl Tilemap {
         Ok(())
     }
 
-    pub fn cls(&self, tile: pyxel::Tile) {
-        self.inner.lock().cls(tile);
+    pub fn cls(&self, tile: TileArg) {
+        self.inner.lock().cls(tile.to_tile());
+    }
+
+    pub fn pget(&self, py: Python, x: f64, y: f64) -> PyObject {
+        tile_to_object(py, self.inner.lock().pget(x, y))
+    }
+
+    pub fn pset(&self, x: f64, y: f64, tile: TileArg) {
+        self.inner.lock().pset(x, y, tile.to_tile());
+    }
+
+    pub fn line(&self, x1: f64, y1: f64, x2: f64, y2: f64, tile: TileArg) {
+        self.inner.lock().line(x1, y1, x2, y2, tile.to_tile());
+    }
+
+    pub fn rect(&self, x: f64, y: f64, w: f64, h: f64, tile: TileArg) {
+        self.inner.lock().rect(x, y, w, h, tile.to_tile());
+    }
+
+    pub fn rectb(&self, x: f64, y: f64, w: f64, h: f64, tile: TileArg) {
+        self.inner.lock().rectb(x, y, w, h, tile.to_tile());
+    }
+
+    pub fn circ(&self, x: f64, y: f64, r: f64, tile: TileArg) {
+        self.inner.lock().circ(x, y, r, tile.to_tile());
     }
 
-    pub fn pget(&self, x: f64, y: f64) -> pyxel::Tile {
-        self.inner.lock().pget(x, y)
+    pub fn circb(&self, x: f64, y: f64, r: f64, tile: TileArg) {
+        self.inner.lock().circb(x, y, r, tile.to_tile());
     }
 
-    pub fn pset(&self, x: f64, y: f64, tile: pyxel::Tile) {
-        self.inner.lock().pset(x, y, tile);
+    pub fn elli(&self, x: f64, y: f64, w: f64, h: f64, tile: TileArg) {
+        self.inner.lock().elli(x, y, w, h, tile.to_tile());
     }
 
-    pub fn line(&self, x1: f64, y1: f64, x2: f64, y2: f64, tile: pyxel::Tile) {
-        self.inner.lock().line(x1, y1, x2, y2, tile);
+    pub fn ellib(&self, x: f64, y: f64, w: f64, h: f64, tile: TileArg) {
+        self.inner.lock().ellib(x, y, w, h, tile.to_tile());
     }
 
-    pub fn rect(&self, x: f64, y: f64, w: f64, h: f64, tile: pyxel::Tile) {
-        self.inner.lock().rect(x, y, w, h, tile);
+    pub fn tri(&self, x1: f64, y1: f64, x2: f64, y2: f64, x3: f64, y3: f64, tile: TileArg) {
+        self.inner
+            .lock()
+            .tri(x1, y1, x2, y2, x3, y3, tile.to_tile());
     }
 
-    pub fn rectb(&self, x: f64, y: f64, w: f64, h: f64, tile: pyxel::Tile) {
-        self.inner.lock().rectb(x, y, w, h, tile);
+    pub fn trib(&self, x1: f64, y1: f64, x2: f64, y2: f64, x3: f64, y3: f64, tile: TileArg) {
+        self.inner
+            .lock()
+            .trib(x1, y1, x2, y2, x3, y3, tile.to_tile());
     }
 
-    pub fn circ(&self, x: f64, y: f64, r: f64, tile: pyxel::Tile) {
-        self.inner.lock().circ(x, y, r, tile);
+    pub fn poly(&self, points: Vec<(f64, f64)>, tile: TileArg) {
+        self.inner.lock().poly(&points, tile.to_tile());
     }
 
-    pub fn circb(&self, x: f64, y: f64, r: f64, tile: pyxel::Tile) {
-        self.inner.lock().circb(x, y, r, tile);
+    pub fn polyb(&self, points: Vec<(f64, f64)>, tile: TileArg) {
+        self.inner.lock().polyb(&points, tile.to_tile());
     }
 
-    pub fn elli(&self, x: f64, y: f64, w: f64, h: f64, tile: pyxel::Tile) {
-        self.inner.lock().elli(x, y, w, h, tile);
+    pub fn thick_line(&self, x1: f64, y1: f64, x2: f64, y2: f64, thickness: f64, tile: TileArg) {
+        self.inner
+            .lock()
+            .thick_line(x1, y1, x2, y2, thickness, tile.to_tile());
     }
 
-    pub fn ellib(&self, x: f64, y: f64, w: f64, h: f64, tile: pyxel::Tile) {
-        self.inner.lock().ellib(x, y, w, h, tile);
+    pub fn rrect(&self, x: f64, y: f64, w: f64, h: f64, r: f64, tile: TileArg) {
+        self.inner.lock().rrect(x, y, w, h, r, tile.to_tile());
     }
 
-    pub fn tri(&self, x1: f64, y1: f64, x2: f64, y2: f64, x3: f64, y3: f64, tile: pyxel::Tile) {
-        self.inner.lock().tri(x1, y1, x2, y2, x3, y3, tile);
+    pub fn rrectb(&self, x: f64, y: f64, w: f64, h: f64, r: f64, tile: TileArg) {
+        self.inner.lock().rrectb(x, y, w, h, r, tile.to_tile());
     }
 
-    pub fn trib(&self, x1: f64, y1: f64, x2: f64, y2: f64, x3: f64, y3: f64, tile: pyxel::Tile) {
-        self.inner.lock().trib(x1, y1, x2, y2, x3, y3, tile);
+    pub fn arc(&self, x: f64, y: f64, r: f64, start: f64, end: f64, tile: TileArg) {
+        self.inner.lock().arc(x, y, r, start, end, tile.to_tile());
     }
 
-    pub fn fill(&self, x: f64, y: f64, tile: pyxel::Tile) {
-        self.inner.lock().fill(x, y, tile);
+    pub fn pie(&self, x: f64, y: f64, r: f64, start: f64, end: f64, tile: TileArg) {
+        self.inner.lock().pie(x, y, r, start, end, tile.to_tile());
+    }
+
+    pub fn fill(&self, x: f64, y: f64, tile: TileArg) {
+        self.inner.lock().fill(x, y, tile.to_tile());
+    }
+
+    pub fn fill_boundary(&self, x: f64, y: f64, btile: TileArg, tile: TileArg) {
+        self.inner
+            .lock()
+            .fill_boundary(x, y, btile.to_tile(), tile.to_tile());
     }
 
     pub fn blt(
@@ -178,8 +333,9 @@ impl Tilemap {
         v: f64,
         w: f64,
         h: f64,
-        tilekey: Option<pyxel::Tile>,
+        tilekey: Option<TileArg>,
     ) -> PyResult<()> {
+        let tilekey = tilekey.map(|tilekey| tilekey.to_tile());
         cast_pyany! {
             tm,
             (u32, {
@@ -241,3 +397,59 @@ pub fn add_tilemap_class(m: &PyModule) -> PyResult<()> {
     m.add_class::<Tilemap>()?;
     Ok(())
 }
+
+fn tiled_properties_to_dict<'py>(
+    py: Python<'py>,
+    properties: &pyxel::TiledProperties,
+) -> PyResult<&'py PyDict> {
+    let properties_dict = PyDict::new(py);
+    for (name, value) in properties {
+        let value = match value {
+            pyxel::TiledProperty::Bool(value) => value.to_object(py),
+            pyxel::TiledProperty::Int(value) => value.to_object(py),
+            pyxel::TiledProperty::Float(value) => value.to_object(py),
+            pyxel::TiledProperty::String(value) => value.to_object(py),
+        };
+        properties_dict.set_item(name, value)?;
+    }
+    Ok(properties_dict)
+}
+
+fn tiled_object_to_dict<'py>(
+    py: Python<'py>,
+    object: &pyxel::TiledObject,
+) -> PyResult<&'py PyDict> {
+    let object_dict = PyDict::new(py);
+    object_dict.set_item("id", object.id)?;
+    object_dict.set_item("name", &object.name)?;
+    object_dict.set_item("type", &object.object_type)?;
+    object_dict.set_item("x", object.x)?;
+    object_dict.set_item("y", object.y)?;
+    object_dict.set_item("width", object.width)?;
+    object_dict.set_item("height", object.height)?;
+    object_dict.set_item("rotation", object.rotation)?;
+    object_dict.set_item("visible", object.is_visible)?;
+    match &object.shape {
+        pyxel::TiledObjectShape::Rect => object_dict.set_item("shape", "rect")?,
+        pyxel::TiledObjectShape::Ellipse => object_dict.set_item("shape", "ellipse")?,
+        pyxel::TiledObjectShape::Point => object_dict.set_item("shape", "point")?,
+        pyxel::TiledObjectShape::Polygon(points) => {
+            object_dict.set_item("shape", "polygon")?;
+            object_dict.set_item("points", points)?;
+        }
+        pyxel::TiledObjectShape::Polyline(points) => {
+            object_dict.set_item("shape", "polyline")?;
+            object_dict.set_item("points", points)?;
+        }
+        pyxel::TiledObjectShape::Tile(img, tile) => {
+            object_dict.set_item("shape", "tile")?;
+            object_dict.set_item("img", img)?;
+            object_dict.set_item("tile", tile_to_object(py, *tile))?;
+        }
+    }
+    object_dict.set_item(
+        "properties",
+        tiled_properties_to_dict(py, &object.properties)?,
+    )?;
+    Ok(object_dict)
+}
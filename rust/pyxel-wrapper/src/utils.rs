@@ -76,3 +76,17 @@ macro_rules! wrap_as_python_list {
         }
     };
 }
+
+pub fn to_python_error(err: pyxel::PyxelError) -> pyo3::PyErr {
+    let msg = err.to_string();
+    match err {
+        pyxel::PyxelError::FileNotFound(_) => pyo3::exceptions::PyFileNotFoundError::new_err(msg),
+        pyxel::PyxelError::FileAccess(..) => pyo3::exceptions::PyOSError::new_err(msg),
+        pyxel::PyxelError::InvalidFormat(..)
+        | pyxel::PyxelError::UnsupportedVersion(..)
+        | pyxel::PyxelError::InvalidShader(_) => pyo3::exceptions::PyValueError::new_err(msg),
+        pyxel::PyxelError::PaletteNotFound(_) => pyo3::exceptions::PyKeyError::new_err(msg),
+        pyxel::PyxelError::ImageNotFound(_) => pyo3::exceptions::PyIndexError::new_err(msg),
+        pyxel::PyxelError::RecordingNotStarted => pyo3::exceptions::PyRuntimeError::new_err(msg),
+    }
+}
@@ -1,6 +1,7 @@
 use pyo3::exceptions::PyAttributeError;
 use pyo3::prelude::*;
 
+use crate::animation_wrapper::Animation;
 use crate::channel_wrapper::Channel;
 use crate::image_wrapper::Image;
 use crate::music_wrapper::Music;
@@ -47,6 +48,7 @@ macro_rules! wrap_shared_vec_as_python_list {
 
 wrap_shared_vec_as_python_list!(Images, Image, images);
 wrap_shared_vec_as_python_list!(Tilemaps, Tilemap, tilemaps);
+wrap_shared_vec_as_python_list!(Animations, Animation, animations);
 wrap_shared_vec_as_python_list!(Channels, Channel, channels);
 wrap_shared_vec_as_python_list!(Tones, Tone, tones);
 wrap_shared_vec_as_python_list!(Sounds, Sound, sounds);
@@ -71,6 +73,7 @@ fn __getattr__(py: Python, name: &str) -> PyResult<PyObject> {
         "colors" => Py::new(py, Colors::wrap(0))?.into_py(py),
         "images" => Py::new(py, Images::wrap(0))?.into_py(py),
         "tilemaps" => Py::new(py, Tilemaps::wrap(0))?.into_py(py),
+        "animations" => Py::new(py, Animations::wrap(0))?.into_py(py),
         "screen" => Image::wrap(pyxel().screen.clone()).into_py(py),
         "cursor" => Image::wrap(pyxel().cursor.clone()).into_py(py),
         "font" => Image::wrap(pyxel().font.clone()).into_py(py),